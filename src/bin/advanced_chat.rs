@@ -1,190 +1,21 @@
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use futures_util::{SinkExt, StreamExt};
-use bincode;
-use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use tokio::io::{self, AsyncBufReadExt};
 use rand::Rng;
 
-#[derive(Serialize, Debug)]
-enum ClientMessage {
-    Login { 
-        username: String, 
-        auth_token: String 
-    },
-    ChatMessage { 
-        channel: ChatChannel, 
-        message: String, 
-        target_id: Option<Uuid> 
-    },
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-enum ServerMessage {
-    LoginSuccess { 
-        player_id: Uuid, 
-        username: String 
-    },
-    LoginError { 
-        reason: String 
-    },
-    PlayerUpdate { 
-        player_id: Uuid, 
-        transform: Transform 
-    },
-    // 🔥 ДОБАВЬТЕ ЭТИ СТРУКТУРЫ:
-    WorldState {
-        players: Vec<PlayerUpdate>,
-        npcs: Vec<NpcUpdate>,
-        objects: Vec<ObjectUpdate>,
-        timestamp: u64,
-    },
-    PlayerJoined {
-        player_data: PlayerData,
-    },
-    PlayerLeft {
-        player_id: Uuid,
-    },
-    PlayerTransformUpdate {
-        player_id: Uuid,
-        transform: Transform,
-        velocity: Vector3,
-    },
-    // 🔥 ЭТО ОСНОВНАЯ ПРОБЛЕМА - ChatMessage должен быть ПОСЛЕДНИМ из известных вариантов
-    ChatMessage {
-        channel: ChatChannel,
-        message: String,
-        from_player: String,
-    },
-    ChatError {
-        reason: String,
-    },
-    CombatEvent {
-        source_id: Uuid,
-        target_id: Uuid,
-        damage: i32,
-        ability_id: u32,
-    },
-    HeartbeatResponse {
-        server_time: u64,
-    },
-    // Обработка неизвестных вариантов
-    #[serde(other)]
-    Unknown,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-enum ChatChannel {
-    Global,
-    Local,
-    Party,
-    Guild,
-    Whisper,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct Transform {
-    position: Vector3,
-    rotation: Quaternion,
-    scale: Vector3,
-}
-
-impl Default for Transform {
-    fn default() -> Self {
-        Self {
-            position: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
-            rotation: Quaternion { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
-            scale: Vector3 { x: 1.0, y: 1.0, z: 1.0 },
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct Vector3 {
-    x: f32,
-    y: f32,
-    z: f32,
-}
-
-impl Default for Vector3 {
-    fn default() -> Self {
-        Self { x: 0.0, y: 0.0, z: 0.0 }
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct Quaternion {
-    x: f32,
-    y: f32,
-    z: f32,
-    w: f32,
-}
-
-impl Default for Quaternion {
-    fn default() -> Self {
-        Self { x: 0.0, y: 0.0, z: 0.0, w: 1.0 }
-    }
-}
-
-// Вспомогательные структуры для полного соответствия с сервером
-#[derive(Serialize, Deserialize, Debug)]
-struct PlayerData {
-    id: Uuid,
-    name: String,
-    level: u32,
-    class: PlayerClass,
-    transform: Transform,
-    stats: PlayerStats,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct PlayerUpdate {
-    player_id: Uuid,
-    transform: Transform,
-    velocity: Vector3,
-    animation: Option<String>,
-    health: i32,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct NpcUpdate {
-    npc_id: Uuid,
-    transform: Transform,
-    health: i32,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct ObjectUpdate {
-    object_id: Uuid,
-    transform: Transform,
-    state: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-enum PlayerClass {
-    Warrior,
-    Mage,
-    Archer,
-    Rogue,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct PlayerStats {
-    health: i32,
-    max_health: i32,
-    mana: i32,
-    max_mana: i32,
-    strength: i32,
-    agility: i32,
-    intelligence: i32,
-}
+// Общий с сервером протокол, чтобы индексы вариантов bincode всегда совпадали
+#[allow(dead_code)]
+#[path = "../protocol/mod.rs"]
+mod protocol;
+use protocol::{ChatChannel, ClientMessage, ServerMessage};
 
 #[tokio::main]
 async fn main() {
     println!("🚀 Advanced Chat Client - Connecting...");
     
-    let mut rng = rand::thread_rng();
-    let username = format!("user_{}", rng.gen_range(1000..9999));
+    let mut rng = rand::rng();
+    let username = format!("user_{}", rng.random_range(1000..9999));
     
     match connect_async("ws://127.0.0.1:8080").await {
         Ok((mut ws, _)) => {
@@ -220,10 +51,11 @@ async fn main() {
                                             ChatChannel::Party => "👥", 
                                             ChatChannel::Guild => "⚔️",
                                             ChatChannel::Whisper => "🤫",
+                                            ChatChannel::Yell => "📣",
                                         };
                                         println!("\n💬 {} [{}]: {}", channel_icon, from_player, message);
                                     }
-                                    ServerMessage::LoginSuccess { player_id, username } => {
+                                    ServerMessage::LoginSuccess { player_id, username, .. } => {
                                         println!("✨ Welcome, {}! (ID: {})", username, player_id);
                                     }
                                     ServerMessage::LoginError { reason } => {
@@ -232,6 +64,18 @@ async fn main() {
                                     ServerMessage::ChatError { reason } => {
                                         println!("❌ Chat error: {}", reason);
                                     }
+                                    ServerMessage::SystemMessage { message } => {
+                                        println!("\n📢 {}", message);
+                                    }
+                                    ServerMessage::CommandError { reason } => {
+                                        println!("❌ {}", reason);
+                                    }
+                                    ServerMessage::Announcement { message } => {
+                                        println!("\n📣 [ANNOUNCEMENT] {}", message);
+                                    }
+                                    ServerMessage::Disconnected { reason } => {
+                                        println!("\n🔌 Disconnected: {}", reason);
+                                    }
                                     _ => {} // Игнорируем другие типы сообщений
                                 }
                             }
@@ -269,49 +113,63 @@ async fn main() {
                     continue;
                 }
                 
-                let chat_msg = if line.starts_with("/g ") {
+                let chat_msg = if let Some(text) = line.strip_prefix("/g ") {
                     ClientMessage::ChatMessage {
                         channel: ChatChannel::Global,
-                        message: line[3..].to_string(),
+                        message: text.to_string(),
                         target_id: None,
                     }
-                } else if line.starts_with("/l ") {
+                } else if let Some(text) = line.strip_prefix("/l ") {
                     ClientMessage::ChatMessage {
                         channel: ChatChannel::Local,
-                        message: line[3..].to_string(),
+                        message: text.to_string(),
                         target_id: None,
                     }
-                } else if line.starts_with("/w ") {
-                    let parts: Vec<&str> = line[3..].splitn(2, ' ').collect();
-                    if parts.len() == 2 {
-                        if let Ok(target_id) = Uuid::parse_str(parts[0]) {
-                            println!("🔍 Sending whisper to: {}", target_id);
-                            ClientMessage::ChatMessage {
+                } else if let Some(text) = line.strip_prefix("/y ") {
+                    ClientMessage::ChatMessage {
+                        channel: ChatChannel::Yell,
+                        message: text.to_string(),
+                        target_id: None,
+                    }
+                } else if let Some(args) = line.strip_prefix("/w ") {
+                    match args.split_once(' ') {
+                        Some((target, text)) => match Uuid::parse_str(target) {
+                            Ok(target_id) => ClientMessage::ChatMessage {
                                 channel: ChatChannel::Whisper,
-                                message: parts[1].to_string(),
+                                message: text.to_string(),
                                 target_id: Some(target_id),
-                            }
-                        } else {
-                            println!("❌ Invalid UUID. Example valid UUID: 905ddeed-4110-4c70-9387-377d8c661697");
+                            },
+                            Err(_) => ClientMessage::WhisperByName {
+                                target_name: target.to_string(),
+                                message: text.to_string(),
+                            },
+                        },
+                        None => {
+                            println!("❌ Usage: /w <name> <message>");
                             continue;
                         }
-                    } else {
-                        println!("❌ Usage: /w <player_id> <message>");
-                        continue;
+                    }
+                } else if let Some(text) = line.strip_prefix("/r ") {
+                    ClientMessage::WhisperReply {
+                        message: text.to_string(),
                     }
                 } else if line == "/help" {
                     println!("💡 Chat commands:");
                     println!("  /g <message> - Global chat");
                     println!("  /l <message> - Local chat"); 
-                    println!("  /w <player_id> <message> - Whisper");
+                    println!("  /y <message> - Yell (wider range than local chat)");
+                    println!("  /w <name> <message> - Whisper (player ID also works)");
+                    println!("  /r <message> - Reply to the last whisper");
                     println!("  <message> - Local chat (default)");
                     println!("  /help - Show this help");
+                    println!("  /commands - List server commands (/who, /roll, /played, /location...)");
                     print!("💬 Your message: ");
                     continue;
                 } else if line == "/quit" || line == "/exit" {
                     println!("👋 Goodbye!");
                     break;
                 } else {
+                    // Остальные /команды разбирает сервер
                     ClientMessage::ChatMessage {
                         channel: ChatChannel::Local,
                         message: line,
@@ -337,10 +195,3 @@ async fn main() {
     }
 }
 
-// Минимальная структура только для чата (резервная)
-#[derive(Serialize, Deserialize, Debug)]
-struct ServerMessageChatOnly {
-    channel: ChatChannel,
-    message: String,
-    from_player: String,
-}
\ No newline at end of file
@@ -1,6 +1,5 @@
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use futures_util::{SinkExt, StreamExt};
-use bincode;
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 
@@ -105,6 +104,7 @@ enum ChatChannel {
     Party,
     Guild,
     Whisper,
+    Yell,
 }
 
 #[tokio::main]
@@ -128,7 +128,6 @@ async fn main() {
             }
             
             // Ждем ответ на логин и затем отправляем движение
-            let mut logged_in = false;
             
             while let Some(message) = read.next().await {
                 match message {
@@ -143,7 +142,6 @@ async fn main() {
                                 match server_msg {
                                     ServerMessage::LoginSuccess { player_id, username } => {
                                         println!("✨ Login successful! Player: {} (ID: {})", username, player_id);
-                                        logged_in = true;
                                         
                                         // После успешного логина отправляем движение
                                         println!("🎮 Sending movement...");
@@ -5,6 +5,9 @@ use std::fs;
 pub struct ServerConfig {
     pub server: NetworkConfig,
     pub game: GameConfig,
+    pub storage: StorageConfig,
+    // Читается из конфига, но логирование пока через println
+    #[allow(dead_code)]
     pub logging: LoggingConfig,
 }
 
@@ -12,6 +15,7 @@ pub struct ServerConfig {
 pub struct NetworkConfig {
     pub host: String,
     pub port: u16,
+    #[allow(dead_code)]
     pub max_players: u32,
     pub tick_rate: u32,
 }
@@ -19,16 +23,122 @@ pub struct NetworkConfig {
 #[derive(Debug, Deserialize, Clone)]
 pub struct GameConfig {
     pub world: WorldConfig,
+    pub navigation: NavigationConfig,
+    pub items: ItemsConfig,
+    pub objects: ObjectsConfig,
+    pub guilds: GuildsConfig,
+    pub chat: ChatConfig,
+    pub accounts: AccountsConfig,
+    pub mail: MailConfig,
+    pub clock: ClockConfig,
+    pub loot: LootConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct WorldConfig {
+    #[allow(dead_code)]
     pub name: String,
     pub max_players_per_zone: u32,
-    pub save_interval: u64,
+    pub save_interval: u64, // секунды между автосохранениями игроков, 0 - только при выходе
+    pub zones_file: String,
+    pub start_zone: u32, // зона, в которую попадают при входе
+    pub instance_empty_secs: u64, // пустая копия подземелья удаляется через это время
+    pub max_move_speed: f32, // единиц в секунду в ясную погоду, погода ее замедляет; 0 - без проверки
+    pub characters_file: String,
+    pub world_state_file: String, // время суток и погода по зонам
+    pub collision_file: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
+pub struct NavigationConfig {
+    pub grids_file: String,
+    pub max_nodes_per_tick: usize,
+    pub max_nodes_per_search: usize,
+    pub cache_size: usize,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ItemsConfig {
+    pub items_file: String,
+    pub recipes_file: String,
+    pub inventory_slots: usize,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ObjectsConfig {
+    pub objects_file: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GuildsConfig {
+    pub guilds_file: String,
+    pub max_members: usize,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ChatConfig {
+    pub say_radius: f32,
+    pub yell_radius: f32,
+    pub max_message_length: usize,
+    // Не больше rate_limit_messages сообщений за rate_limit_window_secs
+    pub rate_limit_messages: usize,
+    pub rate_limit_window_secs: u64,
+    // Сколько раз подряд можно повторить одно сообщение до мута
+    pub spam_repeat_limit: u32,
+    pub spam_mute_secs: u64,
+    pub filtered_words: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AccountsConfig {
+    pub accounts_file: String,
+    pub bans_file: String,
+    pub audit_log_file: String,
+    // Эти аккаунты получают роль admin при входе
+    pub default_admins: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MailConfig {
+    pub mail_file: String,
+    pub max_mailbox_size: usize,
+    pub max_attachments: usize,
+    // Через сколько дней письмо удаляется, невостребованные вложения возвращаются отправителю
+    pub expiry_days: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LootConfig {
+    pub loot_file: String,
+    pub ownership_secs: u64, // столько добычу могут поднять только те, кому она выпала
+    pub despawn_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClockConfig {
+    pub day_length_secs: u64, // реальные секунды в игровых сутках при speed = 1
+    pub speed: f32,
+    pub start_hour: f32, // игровой час при запуске сервера
+    pub dawn_hour: f32,
+    pub dusk_hour: f32,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    File,
+    Sqlite,
+    Memory,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    pub sqlite_file: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[allow(dead_code)]
 pub struct LoggingConfig {
     pub level: String,
     pub format: String,
@@ -48,7 +158,70 @@ impl Default for ServerConfig {
                     name: "Aethelgard".to_string(),
                     max_players_per_zone: 100,
                     save_interval: 300,
+                    zones_file: "data/zones.toml".to_string(),
+                    start_zone: 1,
+                    instance_empty_secs: 300,
+                    max_move_speed: 10.0,
+                    characters_file: "saves/characters.json".to_string(),
+                    world_state_file: "saves/world_state.json".to_string(),
+                    collision_file: "data/collision.toml".to_string(),
                 },
+                navigation: NavigationConfig {
+                    grids_file: "data/navigation.toml".to_string(),
+                    max_nodes_per_tick: 2000,
+                    max_nodes_per_search: 20000,
+                    cache_size: 256,
+                },
+                items: ItemsConfig {
+                    items_file: "data/items.toml".to_string(),
+                    recipes_file: "data/recipes.toml".to_string(),
+                    inventory_slots: 30,
+                },
+                objects: ObjectsConfig {
+                    objects_file: "data/objects.toml".to_string(),
+                },
+                guilds: GuildsConfig {
+                    guilds_file: "saves/guilds.json".to_string(),
+                    max_members: 100,
+                },
+                chat: ChatConfig {
+                    say_radius: 30.0,
+                    yell_radius: 120.0,
+                    max_message_length: 256,
+                    rate_limit_messages: 5,
+                    rate_limit_window_secs: 10,
+                    spam_repeat_limit: 3,
+                    spam_mute_secs: 60,
+                    filtered_words: vec!["idiot".to_string(), "moron".to_string()],
+                },
+                accounts: AccountsConfig {
+                    accounts_file: "saves/accounts.json".to_string(),
+                    bans_file: "saves/bans.json".to_string(),
+                    audit_log_file: "saves/audit.log".to_string(),
+                    default_admins: Vec::new(),
+                },
+                mail: MailConfig {
+                    mail_file: "saves/mail.json".to_string(),
+                    max_mailbox_size: 100,
+                    max_attachments: 8,
+                    expiry_days: 30,
+                },
+                clock: ClockConfig {
+                    day_length_secs: 7200,
+                    speed: 1.0,
+                    start_hour: 8.0,
+                    dawn_hour: 6.0,
+                    dusk_hour: 20.0,
+                },
+                loot: LootConfig {
+                    loot_file: "data/loot.toml".to_string(),
+                    ownership_secs: 60,
+                    despawn_secs: 300,
+                },
+            },
+            storage: StorageConfig {
+                backend: StorageBackend::File,
+                sqlite_file: "saves/world.db".to_string(),
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
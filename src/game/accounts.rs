@@ -0,0 +1,163 @@
+use crate::config::AccountsConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+use super::roles::Role;
+use super::storage::{self, Collection, Storage};
+
+const BCRYPT_COST: u32 = 10;
+
+// Учетная запись: постоянный id игрока, роль и хеш токена входа
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub id: Uuid,
+    pub username: String,
+    password_hash: String,
+    pub role: Role,
+    pub created_at: i64,
+}
+
+#[derive(Debug)]
+pub struct AccountStore {
+    accounts: RwLock<HashMap<String, Account>>, // по имени в нижнем регистре
+    storage: Arc<dyn Storage>,
+    default_admins: Vec<String>,
+    save_lock: Mutex<()>,
+}
+
+impl AccountStore {
+    pub fn load(config: &AccountsConfig, storage: Arc<dyn Storage>) -> Self {
+        let accounts: HashMap<String, Account> = storage::load::<Account>(storage.as_ref(), Collection::Accounts)
+            .into_iter()
+            .map(|account| (account.username.to_lowercase(), account))
+            .collect();
+
+        println!("👤 Loaded {} accounts", accounts.len());
+        Self {
+            accounts: RwLock::new(accounts),
+            storage,
+            default_admins: config.default_admins.iter().map(|name| name.to_lowercase()).collect(),
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    // Пишем одну учетную запись в том виде, в каком она сейчас в памяти
+    async fn save(&self, username: &str) {
+        let _guard = self.save_lock.lock().await;
+        let Some(account) = self.find(username).await else {
+            return;
+        };
+        if let Err(e) = storage::save(&self.storage, Collection::Accounts, &[account]).await {
+            eprintln!("{}", e);
+        }
+    }
+
+    pub async fn find(&self, username: &str) -> Option<Account> {
+        let accounts = self.accounts.read().await;
+        accounts.get(&username.to_lowercase()).cloned()
+    }
+
+    // Вход: первая авторизация занимает имя и запоминает токен,
+    // дальше войти под этим именем можно только с тем же токеном
+    pub async fn login(&self, username: &str, auth_token: &str) -> Result<Account, String> {
+        let key = username.to_lowercase();
+        if auth_token.is_empty() {
+            return Err("Auth token is required".to_string());
+        }
+
+        if let Some(mut account) = self.find(username).await {
+            let (token, hash) = (auth_token.to_string(), account.password_hash.clone());
+            let valid = tokio::task::spawn_blocking(move || bcrypt::verify(token, &hash).unwrap_or(false))
+                .await
+                .unwrap_or(false);
+            if !valid {
+                return Err("Invalid credentials".to_string());
+            }
+            if self.default_admins.contains(&key) && account.role < Role::Admin {
+                account = self.set_role(username, Role::Admin).await?;
+            }
+            return Ok(account);
+        }
+
+        let password_hash = hash_token(auth_token).await?;
+
+        let account = {
+            let mut accounts = self.accounts.write().await;
+            if accounts.contains_key(&key) {
+                return Err("Account is being created, try again".to_string());
+            }
+            let account = Account {
+                id: Uuid::new_v4(),
+                username: username.to_string(),
+                password_hash,
+                role: if self.default_admins.contains(&key) { Role::Admin } else { Role::Player },
+                created_at: chrono::Utc::now().timestamp(),
+            };
+            accounts.insert(key, account.clone());
+            account
+        };
+
+        println!("[{}] 👤 Account created: {} ({})",
+            chrono::Local::now().format("%H:%M:%S"),
+            account.username, account.id
+        );
+        self.save(username).await;
+        Ok(account)
+    }
+
+    pub async fn set_role(&self, username: &str, role: Role) -> Result<Account, String> {
+        let account = {
+            let mut accounts = self.accounts.write().await;
+            let account = accounts
+                .get_mut(&username.to_lowercase())
+                .ok_or_else(|| format!("Account '{}' not found", username))?;
+            account.role = role;
+            account.clone()
+        };
+
+        self.save(username).await;
+        Ok(account)
+    }
+}
+
+async fn hash_token(auth_token: &str) -> Result<String, String> {
+    let token = auth_token.to_string();
+    tokio::task::spawn_blocking(move || bcrypt::hash(token, BCRYPT_COST))
+        .await
+        .map_err(|e| format!("Failed to hash token: {}", e))?
+        .map_err(|e| format!("Failed to hash token: {}", e))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServerConfig;
+    use crate::game::storage::MemoryStorage;
+
+    fn store(storage: Arc<dyn Storage>) -> AccountStore {
+        let mut config = ServerConfig::default().game.accounts;
+        config.default_admins = vec!["Root".to_string()];
+        AccountStore::load(&config, storage)
+    }
+
+    #[tokio::test]
+    async fn empty_token_does_not_create_account() {
+        let accounts = store(Arc::new(MemoryStorage::new()));
+        assert_eq!(accounts.login("root", "").await.unwrap_err(), "Auth token is required");
+        assert!(accounts.find("root").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn token_is_required_on_later_logins() {
+        let accounts = store(Arc::new(MemoryStorage::new()));
+        let account = accounts.login("Root", "secret").await.unwrap();
+        assert_eq!(account.role, Role::Admin);
+
+        assert_eq!(accounts.login("root", "guess").await.unwrap_err(), "Invalid credentials");
+        assert!(accounts.login("root", "").await.is_err());
+        assert_eq!(accounts.login("ROOT", "secret").await.unwrap().id, account.id);
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+// Сколько длится анимация прыжка и как часто можно прыгать
+pub const JUMP_DURATION: Duration = Duration::from_millis(800);
+pub const JUMP_COOLDOWN: Duration = Duration::from_millis(500);
+
+// Сколько игрок считается в бою после последнего боевого действия
+// Боя пока нет, таймер выставит боевая система
+#[allow(dead_code)]
+pub const COMBAT_TIMEOUT: Duration = Duration::from_secs(6);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Stance {
+    #[default]
+    Standing,
+    Sitting,
+    Dancing,
+}
+
+// Поза и одноразовые анимации игрока для репликации
+#[derive(Debug, Clone, Default)]
+pub struct AnimationState {
+    pub stance: Stance,
+    pub jump_until: Option<Instant>,
+}
+
+impl AnimationState {
+    pub fn can_jump(&self, now: Instant) -> bool {
+        self.jump_until.is_none_or(|until| now >= until + JUMP_COOLDOWN)
+    }
+
+    // None - обычная анимация передвижения на клиенте
+    pub fn animation(&self, dead: bool, now: Instant) -> Option<String> {
+        if dead {
+            return Some("dead".to_string());
+        }
+        if self.jump_until.is_some_and(|until| now < until) {
+            return Some("jump".to_string());
+        }
+        match self.stance {
+            Stance::Standing => None,
+            Stance::Sitting => Some("sit".to_string()),
+            Stance::Dancing => Some("dance".to_string()),
+        }
+    }
+}
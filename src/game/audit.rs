@@ -0,0 +1,74 @@
+use serde::Serialize;
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use super::roles::Role;
+
+#[derive(Debug, Serialize)]
+struct AuditEntry<'a> {
+    timestamp: String,
+    actor: &'a str,
+    role: Role,
+    command: &'a str,
+    args: &'a [String],
+    success: bool,
+    result: &'a str,
+}
+
+// Журнал привилегированных команд: одна JSON-запись на строку
+#[derive(Debug)]
+pub struct AuditLog {
+    file: String,
+    write_lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub fn new(file: &str) -> Self {
+        Self {
+            file: file.to_string(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub async fn record(&self, actor: &str, role: Role, command: &str, args: &[String], result: &Result<String, String>) {
+        let (success, text) = match result {
+            Ok(text) => (true, text.as_str()),
+            Err(reason) => (false, reason.as_str()),
+        };
+        println!("[{}] 📝 AUDIT {} ({}) /{} {:?} -> {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            actor, role.name(), command, args, if success { "ok" } else { "failed" }
+        );
+
+        let entry = AuditEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            actor,
+            role,
+            command,
+            args,
+            success,
+            result: text,
+        };
+        let mut line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Failed to serialize audit entry: {}", e);
+                return;
+            }
+        };
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().await;
+        if let Some(dir) = Path::new(&self.file).parent() {
+            tokio::fs::create_dir_all(dir).await.ok();
+        }
+        let file = tokio::fs::OpenOptions::new().create(true).append(true).open(&self.file).await;
+        let written = match file {
+            Ok(mut file) => file.write_all(line.as_bytes()).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = written {
+            eprintln!("Failed to write audit log {}: {}", self.file, e);
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+use super::storage::{self, Collection, Storage};
+
+// Адрес или подсеть в записи CIDR: 10.0.0.5, 10.0.0.0/24, 2001:db8::/32
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid IP address or CIDR '{}'", text);
+        let (addr, prefix_len) = match text.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (text, None),
+        };
+        let addr = addr.trim().parse::<IpAddr>().map_err(|_| invalid())?.to_canonical();
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len.trim().parse::<u8>().map_err(|_| invalid())?,
+            None => max_len,
+        };
+        if prefix_len > max_len {
+            return Err(invalid());
+        }
+
+        // Храним адрес сети, чтобы 10.0.0.5/24 и 10.0.0.0/24 совпадали
+        let addr = match addr {
+            IpAddr::V4(v4) => IpAddr::V4((u32::from(v4) & v4_mask(prefix_len)).into()),
+            IpAddr::V6(v6) => IpAddr::V6((u128::from(v6) & v6_mask(prefix_len)).into()),
+        };
+        Ok(Self { addr, prefix_len })
+    }
+
+    // Сеть из одного адреса
+    pub fn host(ip: IpAddr) -> Self {
+        let addr = ip.to_canonical();
+        Self { addr, prefix_len: if addr.is_ipv4() { 32 } else { 128 } }
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => u32::from(ip) & v4_mask(self.prefix_len) == u32::from(net),
+            (IpAddr::V6(net), IpAddr::V6(ip)) => u128::from(ip) & v6_mask(self.prefix_len) == u128::from(net),
+            _ => false,
+        }
+    }
+}
+
+fn v4_mask(prefix_len: u8) -> u32 {
+    u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0)
+}
+
+fn v6_mask(prefix_len: u8) -> u128 {
+    u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0)
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max_len = if self.addr.is_ipv4() { 32 } else { 128 };
+        if self.prefix_len == max_len {
+            write!(f, "{}", self.addr)
+        } else {
+            write!(f, "{}/{}", self.addr, self.prefix_len)
+        }
+    }
+}
+
+impl TryFrom<String> for IpNetwork {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        Self::parse(&text)
+    }
+}
+
+impl From<IpNetwork> for String {
+    fn from(network: IpNetwork) -> Self {
+        network.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BanTarget {
+    Account { account_id: Uuid, username: String },
+    Address { network: IpNetwork },
+}
+
+impl BanTarget {
+    fn same_as(&self, other: &BanTarget) -> bool {
+        match (self, other) {
+            (BanTarget::Account { account_id: a, .. }, BanTarget::Account { account_id: b, .. }) => a == b,
+            (BanTarget::Address { network: a }, BanTarget::Address { network: b }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for BanTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BanTarget::Account { username, .. } => write!(f, "{}", username),
+            BanTarget::Address { network } => write!(f, "{}", network),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ban {
+    pub target: BanTarget,
+    pub reason: String,
+    pub banned_by: String,
+    pub created_at: i64,
+    pub expires_at: Option<i64>, // None - навсегда
+}
+
+impl Ban {
+    pub fn is_active(&self, now: i64) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    // Текст для LoginError и ответа команды
+    pub fn describe(&self) -> String {
+        let until = match self.expires_at.and_then(|expires_at| DateTime::<Utc>::from_timestamp(expires_at, 0)) {
+            Some(expires_at) => format!("until {}", expires_at.format("%Y-%m-%d %H:%M UTC")),
+            None => "permanently".to_string(),
+        };
+        if self.reason.is_empty() {
+            format!("banned {}", until)
+        } else {
+            format!("banned {}: {}", until, self.reason)
+        }
+    }
+}
+
+// Ключ бана в хранилище: у одной цели один бан
+fn storage_key(target: &BanTarget) -> Option<String> {
+    Collection::Bans.key_of(&serde_json::json!({ "target": target }))
+}
+
+// Баны аккаунтов и адресов. Адреса проверяются еще до рукопожатия WebSocket
+#[derive(Debug)]
+pub struct BanList {
+    bans: RwLock<Vec<Ban>>,
+    storage: Arc<dyn Storage>,
+    save_lock: Mutex<()>,
+}
+
+impl BanList {
+    pub fn load(storage: Arc<dyn Storage>) -> Self {
+        let now = Utc::now().timestamp();
+        let bans: Vec<Ban> = storage::load::<Ban>(storage.as_ref(), Collection::Bans)
+            .into_iter()
+            .filter(|ban| ban.is_active(now))
+            .collect();
+
+        println!("🔨 Loaded {} active bans", bans.len());
+        Self {
+            bans: RwLock::new(bans),
+            storage,
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    // Пишем баны переданных целей: есть бан - вставляем или заменяем, нет - удаляем.
+    // Истекшие к этому моменту баны удаляем заодно
+    async fn save(&self, targets: Vec<BanTarget>) {
+        let _guard = self.save_lock.lock().await;
+        let (active, removed) = {
+            let mut bans = self.bans.write().await;
+            let now = Utc::now().timestamp();
+            let (active, expired): (Vec<Ban>, Vec<Ban>) = bans.drain(..).partition(|ban| ban.is_active(now));
+            *bans = active;
+
+            let mut removed: Vec<BanTarget> = expired.into_iter().map(|ban| ban.target).collect();
+            let mut active = Vec::new();
+            for target in targets {
+                match bans.iter().find(|ban| ban.target.same_as(&target)) {
+                    Some(ban) => active.push(ban.clone()),
+                    None => removed.push(target),
+                }
+            }
+            (active, removed)
+        };
+
+        if let Err(e) = storage::save(&self.storage, Collection::Bans, &active).await {
+            eprintln!("{}", e);
+        }
+        let keys = removed.iter().filter_map(storage_key).collect();
+        if let Err(e) = storage::remove(&self.storage, Collection::Bans, keys).await {
+            eprintln!("{}", e);
+        }
+    }
+
+    // Новый бан заменяет прежний бан той же цели
+    pub async fn ban(&self, ban: Ban) {
+        let target = ban.target.clone();
+        {
+            let mut bans = self.bans.write().await;
+            bans.retain(|existing| !existing.target.same_as(&ban.target));
+            bans.push(ban);
+        }
+        self.save(vec![target]).await;
+    }
+
+    pub async fn unban(&self, target: &BanTarget) -> bool {
+        let removed = {
+            let mut bans = self.bans.write().await;
+            let before = bans.len();
+            bans.retain(|ban| !ban.target.same_as(target));
+            bans.len() != before
+        };
+        if removed {
+            self.save(vec![target.clone()]).await;
+        }
+        removed
+    }
+
+    pub async fn account_ban(&self, account_id: Uuid) -> Option<Ban> {
+        self.find_active(|target| matches!(target, BanTarget::Account { account_id: id, .. } if *id == account_id)).await
+    }
+
+    pub async fn address_ban(&self, ip: IpAddr) -> Option<Ban> {
+        self.find_active(|target| matches!(target, BanTarget::Address { network } if network.contains(ip))).await
+    }
+
+    pub async fn active_bans(&self) -> Vec<Ban> {
+        let now = Utc::now().timestamp();
+        let bans = self.bans.read().await;
+        bans.iter().filter(|ban| ban.is_active(now)).cloned().collect()
+    }
+
+    async fn find_active(&self, matches: impl Fn(&BanTarget) -> bool) -> Option<Ban> {
+        let now = Utc::now().timestamp();
+        let bans = self.bans.read().await;
+        bans.iter().find(|ban| ban.is_active(now) && matches(&ban.target)).cloned()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_hosts_and_networks() {
+        assert_eq!(IpNetwork::parse("10.0.0.5").unwrap().to_string(), "10.0.0.5");
+        assert_eq!(IpNetwork::parse(" 10.0.0.5/24 ").unwrap().to_string(), "10.0.0.0/24");
+        assert_eq!(IpNetwork::parse("10.0.0.5/24"), IpNetwork::parse("10.0.0.0/24"));
+        assert_eq!(IpNetwork::parse("2001:db8::1/32").unwrap().to_string(), "2001:db8::/32");
+        assert_eq!(IpNetwork::parse("::ffff:10.0.0.5").unwrap(), IpNetwork::host(ip("10.0.0.5")));
+        for invalid in ["", "10.0.0", "10.0.0.0/33", "2001:db8::/129", "10.0.0.0/x", "host/8"] {
+            assert!(IpNetwork::parse(invalid).is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn contains_checks_prefix() {
+        let network = IpNetwork::parse("192.168.1.0/24").unwrap();
+        assert!(network.contains(ip("192.168.1.200")));
+        assert!(!network.contains(ip("192.168.2.1")));
+        // IPv4, пришедший как IPv6-mapped адрес
+        assert!(network.contains(ip("::ffff:192.168.1.7")));
+        assert!(!network.contains(ip("2001:db8::1")));
+
+        assert!(IpNetwork::parse("0.0.0.0/0").unwrap().contains(ip("8.8.8.8")));
+        assert!(IpNetwork::parse("2001:db8::/32").unwrap().contains(ip("2001:db8:ffff::1")));
+        assert!(!IpNetwork::parse("2001:db8::/32").unwrap().contains(ip("2001:db9::1")));
+        assert!(IpNetwork::host(ip("10.0.0.1")).contains(ip("10.0.0.1")));
+        assert!(!IpNetwork::host(ip("10.0.0.1")).contains(ip("10.0.0.2")));
+    }
+
+    #[test]
+    fn round_trips_through_serde() {
+        let network = IpNetwork::parse("10.1.0.0/16").unwrap();
+        let json = serde_json::to_string(&network).unwrap();
+        assert_eq!(json, "\"10.1.0.0/16\"");
+        assert_eq!(serde_json::from_str::<IpNetwork>(&json).unwrap(), network);
+        assert!(serde_json::from_str::<IpNetwork>("\"nonsense\"").is_err());
+    }
+
+    fn address_ban(network: &str, expires_at: Option<i64>) -> Ban {
+        Ban {
+            target: BanTarget::Address { network: IpNetwork::parse(network).unwrap() },
+            reason: "test".to_string(),
+            banned_by: "admin".to_string(),
+            created_at: 0,
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn bans_are_stored_per_target() {
+        use crate::game::storage::MemoryStorage;
+
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let bans = BanList::load(storage.clone());
+        bans.ban(address_ban("10.0.0.0/24", None)).await;
+        bans.ban(address_ban("10.0.0.7/24", Some(i64::MAX))).await;
+        bans.ban(address_ban("10.1.0.1", None)).await;
+        // Истекший бан при следующем сохранении удаляется из хранилища
+        bans.ban(address_ban("10.2.0.1", Some(1))).await;
+
+        let stored = BanList::load(storage.clone()).active_bans().await;
+        assert_eq!(stored.len(), 2);
+        assert!(stored.iter().any(|ban| ban.expires_at == Some(i64::MAX)));
+
+        let target = BanTarget::Address { network: IpNetwork::parse("10.0.0.0/24").unwrap() };
+        assert!(bans.unban(&target).await);
+        assert_eq!(storage.load_collection(Collection::Bans).unwrap().unwrap().len(), 1);
+    }
+}
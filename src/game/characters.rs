@@ -0,0 +1,141 @@
+use crate::protocol::{PlayerClass, PlayerStats, Transform};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use super::equipment::Equipment;
+use super::inventory::Inventory;
+use super::session::PlayerId;
+use super::spawns::BindPoint;
+use super::storage::{self, Collection, Storage};
+use super::zones::ZoneId;
+
+// Сохраненный персонаж. Баффы и состояние боя не сохраняются
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterRecord {
+    pub player_id: PlayerId,
+    pub username: String,
+    pub zone_id: ZoneId,
+    pub transform: Transform,
+    pub class: PlayerClass,
+    pub base_stats: PlayerStats,
+    pub stats: PlayerStats,
+    pub inventory: Inventory,
+    pub equipment: Equipment,
+    pub gold: u64,
+    #[serde(default)]
+    pub bind_point: Option<BindPoint>,
+    // Момент снимка в миллисекундах: по нему отбрасываются устаревшие снимки
+    pub saved_at: i64,
+}
+
+// Персонажи по id аккаунта
+#[derive(Debug)]
+pub struct CharacterStore {
+    characters: RwLock<HashMap<PlayerId, CharacterRecord>>,
+    storage: Arc<dyn Storage>,
+    save_lock: Mutex<()>,
+}
+
+impl CharacterStore {
+    pub fn load(storage: Arc<dyn Storage>) -> Self {
+        let characters: HashMap<PlayerId, CharacterRecord> =
+            storage::load::<CharacterRecord>(storage.as_ref(), Collection::Characters)
+                .into_iter()
+                .map(|record| (record.player_id, record))
+                .collect();
+
+        println!("🧙 Loaded {} characters", characters.len());
+        Self {
+            characters: RwLock::new(characters),
+            storage,
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    // Пишем только переданных персонажей, остальные записи не трогаем
+    async fn save(&self, player_ids: &[PlayerId]) {
+        let _guard = self.save_lock.lock().await;
+        let records: Vec<CharacterRecord> = {
+            let characters = self.characters.read().await;
+            player_ids.iter().filter_map(|player_id| characters.get(player_id).cloned()).collect()
+        };
+        if let Err(e) = storage::save(&self.storage, Collection::Characters, &records).await {
+            eprintln!("{}", e);
+        }
+    }
+
+    pub async fn get(&self, player_id: &PlayerId) -> Option<CharacterRecord> {
+        let characters = self.characters.read().await;
+        characters.get(player_id).cloned()
+    }
+
+    // Обновляем персонажей и сразу их сохраняем. Снимок старше сохраненного пропускаем:
+    // автосохранение могло снять игрока до выхода, а записать уже после его сохранения при выходе
+    pub async fn store(&self, records: Vec<CharacterRecord>) {
+        let mut stored = Vec::new();
+        {
+            let mut characters = self.characters.write().await;
+            for record in records {
+                if characters.get(&record.player_id).is_some_and(|saved| saved.saved_at > record.saved_at) {
+                    continue;
+                }
+                stored.push(record.player_id);
+                characters.insert(record.player_id, record);
+            }
+        }
+        if !stored.is_empty() {
+            self.save(&stored).await;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::storage::MemoryStorage;
+    use uuid::Uuid;
+
+    fn record(player_id: PlayerId, gold: u64, saved_at: i64) -> CharacterRecord {
+        let stats = PlayerStats {
+            health: 100,
+            max_health: 100,
+            mana: 50,
+            max_mana: 50,
+            strength: 10,
+            agility: 10,
+            intelligence: 10,
+        };
+        CharacterRecord {
+            player_id,
+            username: "alice".to_string(),
+            zone_id: 1,
+            transform: Transform::default(),
+            class: PlayerClass::Warrior,
+            base_stats: stats.clone(),
+            stats,
+            inventory: Inventory::new(4),
+            equipment: Equipment::default(),
+            gold,
+            bind_point: None,
+            saved_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn older_snapshot_does_not_overwrite_newer_save() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let characters = CharacterStore::load(storage.clone());
+        let player_id = Uuid::new_v4();
+
+        // Выход сохранил свежий снимок раньше, чем автосохранение записало свой
+        characters.store(vec![record(player_id, 50, 2_000)]).await;
+        characters.store(vec![record(player_id, 10, 1_000)]).await;
+        assert_eq!(characters.get(&player_id).await.unwrap().gold, 50);
+
+        characters.store(vec![record(player_id, 70, 3_000)]).await;
+        let reloaded = CharacterStore::load(storage);
+        assert_eq!(reloaded.get(&player_id).await.unwrap().gold, 70);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::config::{ClockConfig, ServerConfig};
+use crate::protocol::GameTime;
+use std::time::Instant;
+use tokio::sync::{broadcast, RwLock};
+
+const HOURS_PER_DAY: f64 = 24.0;
+// Подписчики, отставшие больше чем на столько событий, пропускают старые
+const EVENT_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayPhase {
+    Day,
+    Night,
+}
+
+// Смена времени суток; на нее подписываются спавн NPC, освещение и т.п.
+#[derive(Debug, Clone)]
+pub enum ClockEvent {
+    Dawn(GameTime),
+    Dusk(GameTime),
+}
+
+impl ClockEvent {
+    pub fn time(&self) -> &GameTime {
+        match self {
+            ClockEvent::Dawn(time) | ClockEvent::Dusk(time) => time,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ClockState {
+    // Игровое время (в часах от начала первого дня) в момент anchor
+    anchor: Instant,
+    anchor_hours: f64,
+    phase: DayPhase,
+}
+
+// Общие игровые часы мира: идут в speed раз быстрее, сутки длятся day_length_secs
+#[derive(Debug)]
+pub struct WorldClock {
+    hours_per_second: f64,
+    dawn_hour: f32,
+    dusk_hour: f32,
+    state: RwLock<ClockState>,
+    events: broadcast::Sender<ClockEvent>,
+}
+
+impl WorldClock {
+    pub fn new(config: &ClockConfig) -> Self {
+        let defaults = ServerConfig::default().game.clock;
+        let config = if config.day_length_secs == 0 || config.speed <= 0.0 {
+            eprintln!("Clock: day_length_secs and speed must be positive, using defaults");
+            &defaults
+        } else if !(0.0..HOURS_PER_DAY as f32).contains(&config.dawn_hour)
+            || !(0.0..HOURS_PER_DAY as f32).contains(&config.dusk_hour)
+            || config.dawn_hour >= config.dusk_hour
+        {
+            eprintln!("Clock: dawn_hour must be before dusk_hour within 0-24, using defaults");
+            &defaults
+        } else {
+            config
+        };
+
+        let hours_per_second = HOURS_PER_DAY * config.speed as f64 / config.day_length_secs as f64;
+        let anchor_hours = (config.start_hour as f64).rem_euclid(HOURS_PER_DAY);
+        let phase = phase_at(config.dawn_hour, config.dusk_hour, anchor_hours);
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+
+        println!("🕰️ World clock started at {:02}:00, a day lasts {:.0} real minutes",
+            anchor_hours as u32,
+            HOURS_PER_DAY / hours_per_second / 60.0
+        );
+        Self {
+            hours_per_second,
+            dawn_hour: config.dawn_hour,
+            dusk_hour: config.dusk_hour,
+            state: RwLock::new(ClockState { anchor: Instant::now(), anchor_hours, phase }),
+            events,
+        }
+    }
+
+    fn phase_at(&self, total_hours: f64) -> DayPhase {
+        phase_at(self.dawn_hour, self.dusk_hour, total_hours)
+    }
+
+    fn total_hours(&self, state: &ClockState) -> f64 {
+        state.anchor_hours + state.anchor.elapsed().as_secs_f64() * self.hours_per_second
+    }
+
+    fn game_time(&self, total_hours: f64) -> GameTime {
+        GameTime {
+            day: (total_hours / HOURS_PER_DAY) as u32 + 1,
+            hour: total_hours.rem_euclid(HOURS_PER_DAY) as f32,
+            hours_per_second: self.hours_per_second as f32,
+            is_day: self.phase_at(total_hours) == DayPhase::Day,
+        }
+    }
+
+    pub async fn now(&self) -> GameTime {
+        let state = self.state.read().await;
+        self.game_time(self.total_hours(&state))
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ClockEvent> {
+        self.events.subscribe()
+    }
+
+    // Проверяем смену времени суток; событие уходит и подписчикам, и вызывающему
+    pub async fn update(&self) -> Option<ClockEvent> {
+        let mut state = self.state.write().await;
+        let total_hours = self.total_hours(&state);
+        let phase = self.phase_at(total_hours);
+        if phase == state.phase {
+            return None;
+        }
+        state.phase = phase;
+
+        let time = self.game_time(total_hours);
+        let event = match phase {
+            DayPhase::Day => ClockEvent::Dawn(time),
+            DayPhase::Night => ClockEvent::Dusk(time),
+        };
+        println!("[{}] {} Day {}: {} at {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            if phase == DayPhase::Day { "🌅" } else { "🌇" },
+            event.time().day,
+            if phase == DayPhase::Day { "dawn" } else { "dusk" },
+            format_hour(event.time().hour)
+        );
+        // Ошибка только если подписчиков нет
+        self.events.send(event.clone()).ok();
+        Some(event)
+    }
+
+    // Игровых часов от начала первого дня: сохраняется вместе с миром
+    pub async fn elapsed_hours(&self) -> f64 {
+        let state = self.state.read().await;
+        self.total_hours(&state)
+    }
+
+    // Продолжить с сохраненного времени. Фаза выставляется сразу, без рассылки рассвета или заката
+    pub async fn restore(&self, total_hours: f64) {
+        if !total_hours.is_finite() || total_hours < 0.0 {
+            eprintln!("Clock: ignoring invalid saved time {}", total_hours);
+            return;
+        }
+        let mut state = self.state.write().await;
+        state.anchor = Instant::now();
+        state.anchor_hours = total_hours;
+        state.phase = self.phase_at(total_hours);
+    }
+
+    // Перевести часы на hour текущего дня (команда GM); события придут на следующем тике
+    pub async fn set_hour(&self, hour: f32) -> Result<GameTime, String> {
+        if !(0.0..HOURS_PER_DAY as f32).contains(&hour) {
+            return Err("Hour must be between 0 and 24".to_string());
+        }
+        let mut state = self.state.write().await;
+        let day_start = (self.total_hours(&state) / HOURS_PER_DAY).floor() * HOURS_PER_DAY;
+        state.anchor = Instant::now();
+        state.anchor_hours = day_start + hour as f64;
+        Ok(self.game_time(state.anchor_hours))
+    }
+}
+
+fn phase_at(dawn_hour: f32, dusk_hour: f32, total_hours: f64) -> DayPhase {
+    let hour = total_hours.rem_euclid(HOURS_PER_DAY) as f32;
+    if (dawn_hour..dusk_hour).contains(&hour) { DayPhase::Day } else { DayPhase::Night }
+}
+
+// 13.5 -> "13:30"
+pub fn format_hour(hour: f32) -> String {
+    let minutes = (hour * 60.0) as u32;
+    format!("{:02}:{:02}", minutes / 60 % 24, minutes % 60)
+}
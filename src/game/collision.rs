@@ -0,0 +1,406 @@
+use crate::protocol::Vector3;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use super::zones::ZoneId;
+
+// Препятствия ниже этой высоты над землей перешагиваются
+const STEP_HEIGHT: f32 = 0.5;
+// Игрок - вертикальный отрезок такого радиуса, коробки расширяем на него
+pub const PLAYER_RADIUS: f32 = 0.4;
+// Расхождение высоты с клиентом, после которого клиенту отправляется поправка
+pub const GROUND_TOLERANCE: f32 = 0.25;
+
+#[derive(Debug, Deserialize)]
+struct CollisionFile {
+    #[serde(default)]
+    zones: Vec<ZoneCollisionData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZoneCollisionData {
+    zone_id: ZoneId,
+    heightmap: Option<HeightmapData>,
+    #[serde(default)]
+    boxes: Vec<Aabb>,
+}
+
+// Высоты в узлах сетки: строки идут по оси Y, значения в строке - по оси X
+#[derive(Debug, Deserialize)]
+struct HeightmapData {
+    origin: [f32; 2],
+    cell_size: f32,
+    heights: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    origin_x: f32,
+    origin_y: f32,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    fn from_data(zone_id: ZoneId, data: HeightmapData) -> Result<Self, String> {
+        if data.cell_size <= 0.0 {
+            return Err(format!("zone {}: cell_size must be positive", zone_id));
+        }
+        let columns = data.heights.first().map(Vec::len).unwrap_or(0);
+        if columns < 2 || data.heights.len() < 2 {
+            return Err(format!("zone {}: heightmap needs at least 2x2 points", zone_id));
+        }
+        if let Some(index) = data.heights.iter().position(|row| row.len() != columns) {
+            return Err(format!("zone {}: heightmap row {} has wrong length", zone_id, index));
+        }
+
+        Ok(Self {
+            origin_x: data.origin[0],
+            origin_y: data.origin[1],
+            cell_size: data.cell_size,
+            columns,
+            rows: data.heights.len(),
+            heights: data.heights.into_iter().flatten().collect(),
+        })
+    }
+
+    fn point(&self, column: usize, row: usize) -> f32 {
+        self.heights[row * self.columns + column]
+    }
+
+    // Билинейная интерполяция между узлами; None - точка за пределами карты
+    pub fn height_at(&self, x: f32, y: f32) -> Option<f32> {
+        let gx = (x - self.origin_x) / self.cell_size;
+        let gy = (y - self.origin_y) / self.cell_size;
+        let max_x = (self.columns - 1) as f32;
+        let max_y = (self.rows - 1) as f32;
+        if !(0.0..=max_x).contains(&gx) || !(0.0..=max_y).contains(&gy) {
+            return None;
+        }
+
+        let column = (gx.floor() as usize).min(self.columns - 2);
+        let row = (gy.floor() as usize).min(self.rows - 2);
+        let (tx, ty) = (gx - column as f32, gy - row as f32);
+        let bottom = self.point(column, row) * (1.0 - tx) + self.point(column + 1, row) * tx;
+        let top = self.point(column, row + 1) * (1.0 - tx) + self.point(column + 1, row + 1) * tx;
+        Some(bottom * (1.0 - ty) + top * ty)
+    }
+
+    // Где отрезок уходит под землю: Some(t) от 0 до 1. Идем шагами в четверть клетки,
+    // потом уточняем делением пополам
+    fn segment_hit(&self, from: &Vector3, to: &Vector3) -> Option<f32> {
+        let below = |t: f32| {
+            let point = lerp(from, to, t);
+            self.height_at(point.x, point.y).is_some_and(|ground| point.z < ground)
+        };
+        if below(0.0) {
+            return Some(0.0);
+        }
+
+        let length = distance(from, to);
+        let steps = ((length / (self.cell_size * 0.25)).ceil() as usize).clamp(1, 1024);
+        let mut previous = 0.0;
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            if below(t) {
+                let (mut above, mut under) = (previous, t);
+                for _ in 0..8 {
+                    let middle = (above + under) * 0.5;
+                    if below(middle) {
+                        under = middle;
+                    } else {
+                        above = middle;
+                    }
+                }
+                return Some(under);
+            }
+            previous = t;
+        }
+        None
+    }
+}
+
+pub fn lerp(from: &Vector3, to: &Vector3, t: f32) -> Vector3 {
+    Vector3 {
+        x: from.x + (to.x - from.x) * t,
+        y: from.y + (to.y - from.y) * t,
+        z: from.z + (to.z - from.z) * t,
+    }
+}
+
+pub fn distance(a: &Vector3, b: &Vector3) -> f32 {
+    let (dx, dy, dz) = (a.x - b.x, a.y - b.y, a.z - b.z);
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+// Коробка, выровненная по осям: стены, здания, скалы
+#[derive(Debug, Clone, Deserialize)]
+pub struct Aabb {
+    pub min: Vector3,
+    pub max: Vector3,
+}
+
+impl Aabb {
+    // Коробка вокруг точки на земле: half_extent в стороны по X/Y, height вверх
+    pub fn around(base: &Vector3, half_extent: f32, height: f32) -> Aabb {
+        Aabb {
+            min: Vector3 { x: base.x - half_extent, y: base.y - half_extent, z: base.z },
+            max: Vector3 { x: base.x + half_extent, y: base.y + half_extent, z: base.z + height },
+        }
+    }
+
+    fn expanded(&self, radius: f32) -> Aabb {
+        Aabb {
+            min: Vector3 { x: self.min.x - radius, y: self.min.y - radius, z: self.min.z },
+            max: Vector3 { x: self.max.x + radius, y: self.max.y + radius, z: self.max.z },
+        }
+    }
+
+    pub fn contains(&self, point: &Vector3) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    // Пересечение луча from + dir * t с коробкой методом плоскостей; Some(t) - первая точка входа
+    pub fn ray_entry(&self, from: &Vector3, dir: &Vector3) -> Option<f32> {
+        let mut t_min = 0.0f32;
+        let mut t_max = f32::INFINITY;
+        for (origin, dir, min, max) in [
+            (from.x, dir.x, self.min.x, self.max.x),
+            (from.y, dir.y, self.min.y, self.max.y),
+            (from.z, dir.z, self.min.z, self.max.z),
+        ] {
+            if dir.abs() < f32::EPSILON {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+            let (t1, t2) = ((min - origin) / dir, (max - origin) / dir);
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    // Точка входа отрезка в коробку: Some(t) от 0 до 1
+    pub fn segment_entry(&self, from: &Vector3, to: &Vector3) -> Option<f32> {
+        let dir = Vector3 { x: to.x - from.x, y: to.y - from.y, z: to.z - from.z };
+        self.ray_entry(from, &dir).filter(|t| *t <= 1.0)
+    }
+
+    pub fn intersects_segment(&self, from: &Vector3, to: &Vector3) -> bool {
+        self.segment_entry(from, to).is_some()
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x
+            && self.min.y <= other.max.y && other.min.y <= self.max.y
+            && self.min.z <= other.max.z && other.min.z <= self.max.z
+    }
+
+    // Квадрат расстояния от точки до коробки, внутри - 0
+    pub fn distance_squared(&self, point: &Vector3) -> f32 {
+        let axis = |value: f32, min: f32, max: f32| {
+            let d = (min - value).max(0.0).max(value - max);
+            d * d
+        };
+        axis(point.x, self.min.x, self.max.x)
+            + axis(point.y, self.min.y, self.max.y)
+            + axis(point.z, self.min.z, self.max.z)
+    }
+}
+
+// Статическая геометрия зоны
+#[derive(Debug, Clone, Default)]
+pub struct ZoneCollision {
+    pub heightmap: Option<Heightmap>,
+    pub boxes: Vec<Aabb>,
+}
+
+impl ZoneCollision {
+    // Высота земли; за пределами карты высот - None
+    pub fn ground_height(&self, x: f32, y: f32) -> Option<f32> {
+        self.heightmap.as_ref().and_then(|heightmap| heightmap.height_at(x, y))
+    }
+
+    // Ставим точку на землю, если под ней есть карта высот
+    pub fn snap_to_ground(&self, position: &Vector3) -> Vector3 {
+        match self.ground_height(position.x, position.y) {
+            Some(z) => Vector3 { x: position.x, y: position.y, z },
+            None => position.clone(),
+        }
+    }
+
+    // Первое попадание отрезка в геометрию (коробки и землю): Some(t) от 0 до 1
+    pub fn raycast(&self, from: &Vector3, to: &Vector3) -> Option<f32> {
+        let boxes = self.boxes.iter().filter_map(|aabb| aabb.segment_entry(from, to));
+        let terrain = self.heightmap.as_ref().and_then(|heightmap| heightmap.segment_hit(from, to));
+        boxes.chain(terrain).min_by(f32::total_cmp)
+    }
+
+    // Перемещение игрока: позиция на земле или None, если путь проходит сквозь коробку
+    pub fn resolve_move(&self, from: &Vector3, to: &Vector3) -> Option<Vector3> {
+        let from = self.snap_to_ground(from);
+        let to = self.snap_to_ground(to);
+
+        // Проверяем на высоте шага, чтобы низкие препятствия не мешали
+        let raise = |point: &Vector3| Vector3 { x: point.x, y: point.y, z: point.z + STEP_HEIGHT };
+        let (raised_from, raised_to) = (raise(&from), raise(&to));
+        // Из коробки, в которой игрок уже оказался (телепорт), выйти можно
+        let blocked = self
+            .boxes
+            .iter()
+            .map(|aabb| aabb.expanded(PLAYER_RADIUS))
+            .filter(|aabb| !aabb.contains(&raised_from))
+            .any(|aabb| aabb.contains(&raised_to) || aabb.intersects_segment(&raised_from, &raised_to));
+        (!blocked).then_some(to)
+    }
+}
+
+pub fn load_collision(path: &str) -> HashMap<ZoneId, ZoneCollision> {
+    let data = match fs::read_to_string(path) {
+        Ok(content) => match toml::from_str::<CollisionFile>(&content) {
+            Ok(file) => file.zones,
+            Err(e) => {
+                eprintln!("Failed to parse collision data {}: {}", path, e);
+                Vec::new()
+            }
+        },
+        Err(_) => {
+            println!("Collision data not found at {}, positions are not checked", path);
+            Vec::new()
+        }
+    };
+
+    let mut zones = HashMap::new();
+    for zone in data {
+        let heightmap = match zone.heightmap.map(|data| Heightmap::from_data(zone.zone_id, data)) {
+            Some(Ok(heightmap)) => Some(heightmap),
+            Some(Err(e)) => {
+                eprintln!("Invalid heightmap in {}: {}", path, e);
+                None
+            }
+            None => None,
+        };
+        let boxes: Vec<Aabb> = zone
+            .boxes
+            .into_iter()
+            .filter(|aabb| {
+                let valid = aabb.min.x <= aabb.max.x && aabb.min.y <= aabb.max.y && aabb.min.z <= aabb.max.z;
+                if !valid {
+                    eprintln!("Zone {}: collider min must not exceed max, skipping", zone.zone_id);
+                }
+                valid
+            })
+            .collect();
+
+        println!("🧱 Loaded collision for zone {} ({} colliders{})",
+            zone.zone_id,
+            boxes.len(),
+            if heightmap.is_some() { ", heightmap" } else { "" }
+        );
+        zones.insert(zone.zone_id, ZoneCollision { heightmap, boxes });
+    }
+    zones
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    // Склон 3x3 узла по 10 единиц: высота растет на 1 с каждой клеткой по X
+    fn slope() -> Heightmap {
+        Heightmap::from_data(1, HeightmapData {
+            origin: [0.0, 0.0],
+            cell_size: 10.0,
+            heights: vec![vec![0.0, 1.0, 2.0]; 3],
+        })
+        .unwrap()
+    }
+
+    fn wall() -> Aabb {
+        Aabb { min: v(4.0, -5.0, 0.0), max: v(5.0, 5.0, 3.0) }
+    }
+
+    #[test]
+    fn heightmap_interpolates_inside_and_rejects_outside() {
+        let heightmap = slope();
+        assert!(near(heightmap.height_at(0.0, 0.0).unwrap(), 0.0));
+        assert!(near(heightmap.height_at(5.0, 7.0).unwrap(), 0.5));
+        assert!(near(heightmap.height_at(20.0, 20.0).unwrap(), 2.0));
+        assert!(heightmap.height_at(-0.1, 5.0).is_none());
+        assert!(heightmap.height_at(5.0, 20.1).is_none());
+    }
+
+    #[test]
+    fn heightmap_rejects_malformed_data() {
+        let data = |cell_size: f32, heights: Vec<Vec<f32>>| HeightmapData { origin: [0.0, 0.0], cell_size, heights };
+        assert!(Heightmap::from_data(1, data(0.0, vec![vec![0.0; 2]; 2])).is_err());
+        assert!(Heightmap::from_data(1, data(1.0, vec![vec![0.0; 2]])).is_err());
+        assert!(Heightmap::from_data(1, data(1.0, vec![vec![0.0; 2], vec![0.0; 3]])).is_err());
+    }
+
+    #[test]
+    fn raycast_finds_nearest_box_or_terrain() {
+        let collision = ZoneCollision { heightmap: None, boxes: vec![wall()] };
+        assert!(near(collision.raycast(&v(0.0, 0.0, 1.0), &v(10.0, 0.0, 1.0)).unwrap(), 0.4));
+        assert!(collision.raycast(&v(0.0, 0.0, 4.0), &v(10.0, 0.0, 4.0)).is_none());
+        assert!(collision.raycast(&v(0.0, 0.0, 1.0), &v(3.0, 0.0, 1.0)).is_none());
+
+        // Луч вниз по склону уходит под землю, где высота земли сравнивается с его высотой
+        let terrain = ZoneCollision { heightmap: Some(slope()), boxes: Vec::new() };
+        let t = terrain.raycast(&v(0.0, 5.0, 1.5), &v(20.0, 5.0, 1.5)).unwrap();
+        assert!(near(t, 0.75));
+    }
+
+    #[test]
+    fn resolve_move_snaps_to_ground_and_blocks_walls() {
+        let collision = ZoneCollision { heightmap: Some(slope()), boxes: vec![wall()] };
+
+        let moved = collision.resolve_move(&v(10.0, 10.0, 0.0), &v(15.0, 10.0, 9.0)).unwrap();
+        assert!(near(moved.z, 1.5));
+        assert!(collision.resolve_move(&v(1.0, 0.0, 0.0), &v(8.0, 0.0, 0.0)).is_none());
+        // Целевая точка у самой стены попадает в коробку, расширенную на радиус игрока
+        assert!(collision.resolve_move(&v(1.0, 0.0, 0.0), &v(3.8, 0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn resolve_move_steps_over_low_obstacles_and_leaves_boxes() {
+        let curb = Aabb { min: v(4.0, -5.0, 0.0), max: v(5.0, 5.0, STEP_HEIGHT * 0.5) };
+        let collision = ZoneCollision { heightmap: None, boxes: vec![curb] };
+        assert!(collision.resolve_move(&v(1.0, 0.0, 0.0), &v(8.0, 0.0, 0.0)).is_some());
+
+        // Игрока телепортировали внутрь стены: выйти из нее можно
+        let collision = ZoneCollision { heightmap: None, boxes: vec![wall()] };
+        assert!(collision.resolve_move(&v(4.5, 0.0, 0.0), &v(8.0, 0.0, 0.0)).is_some());
+    }
+
+    #[test]
+    fn box_queries() {
+        let aabb = wall();
+        assert!(aabb.contains(&v(4.5, 0.0, 1.0)));
+        assert!(!aabb.contains(&v(4.5, 0.0, 3.5)));
+        assert!(near(aabb.ray_entry(&v(0.0, 0.0, 1.0), &v(1.0, 0.0, 0.0)).unwrap(), 4.0));
+        assert!(aabb.ray_entry(&v(0.0, 0.0, 1.0), &v(-1.0, 0.0, 0.0)).is_none());
+        assert!(aabb.overlaps(&Aabb::around(&v(5.5, 0.0, 0.0), 1.0, 1.0)));
+        assert!(!aabb.overlaps(&Aabb::around(&v(7.0, 0.0, 0.0), 1.0, 1.0)));
+        assert!(near(aabb.distance_squared(&v(7.0, 0.0, 1.0)), 4.0));
+        assert_eq!(aabb.distance_squared(&v(4.5, 0.0, 1.0)), 0.0);
+    }
+}
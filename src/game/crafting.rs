@@ -0,0 +1,160 @@
+use crate::protocol::{ItemAmount, RecipeInfo};
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use super::inventory::Inventory;
+use super::items::{ItemDatabase, ItemGrant};
+
+pub type RecipeId = u32;
+
+// Как часто отправлять прогресс крафта
+pub const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Recipe {
+    pub id: RecipeId,
+    pub name: String,
+    // Тип станции (кузница, алхимический стол...), None - можно крафтить где угодно
+    pub station: Option<String>,
+    pub craft_time_secs: f32,
+    pub inputs: Vec<ItemGrant>,
+    pub outputs: Vec<ItemGrant>,
+}
+
+impl Recipe {
+    pub fn craft_time(&self) -> Duration {
+        Duration::from_secs_f32(self.craft_time_secs.max(0.0))
+    }
+
+    pub fn to_info(&self) -> RecipeInfo {
+        let amounts = |grants: &[ItemGrant]| {
+            grants
+                .iter()
+                .map(|grant| ItemAmount { template_id: grant.template_id, quantity: grant.quantity })
+                .collect()
+        };
+
+        RecipeInfo {
+            recipe_id: self.id,
+            name: self.name.clone(),
+            inputs: amounts(&self.inputs),
+            outputs: amounts(&self.outputs),
+            craft_time_ms: self.craft_time().as_millis() as u64,
+        }
+    }
+
+    // Хватает ли ингредиентов
+    pub fn has_inputs(&self, inventory: &Inventory) -> bool {
+        self.inputs.iter().all(|input| inventory.count(input.template_id) >= input.quantity)
+    }
+
+    // Списываем ингредиенты и кладем результат на копии инвентаря:
+    // либо применяется все, либо инвентарь остается как был
+    pub fn apply(&self, inventory: &Inventory, items: &ItemDatabase) -> Result<(Inventory, Vec<usize>), String> {
+        let mut result = inventory.clone();
+        let mut changed = BTreeSet::new();
+
+        for input in &self.inputs {
+            changed.extend(result.remove_template(input.template_id, input.quantity)?);
+        }
+        for output in &self.outputs {
+            let template = items.get(output.template_id).ok_or_else(|| "Unknown item".to_string())?;
+            changed.extend(result.add_item(template, output.quantity)?);
+        }
+
+        Ok((result, changed.into_iter().collect()))
+    }
+}
+
+// Текущий крафт игрока
+#[derive(Debug, Clone)]
+pub struct ActiveCraft {
+    pub recipe_id: RecipeId,
+    pub station_id: Option<Uuid>,
+    pub started_at: Instant,
+    pub finishes_at: Instant,
+    pub last_progress_at: Instant,
+}
+
+impl ActiveCraft {
+    pub fn progress(&self, now: Instant) -> f32 {
+        let total = self.finishes_at.saturating_duration_since(self.started_at).as_secs_f32();
+        if total <= 0.0 {
+            return 1.0;
+        }
+        (now.saturating_duration_since(self.started_at).as_secs_f32() / total).min(1.0)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RecipeDataFile {
+    #[serde(default)]
+    recipes: Vec<Recipe>,
+}
+
+#[derive(Debug, Default)]
+pub struct RecipeBook {
+    recipes: HashMap<RecipeId, Recipe>,
+}
+
+impl RecipeBook {
+    pub fn load(path: &str, items: &ItemDatabase) -> Self {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => {
+                println!("Recipe data not found at {}, crafting is disabled", path);
+                return Self::default();
+            }
+        };
+
+        let file: RecipeDataFile = match toml::from_str(&content) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("Failed to parse recipe data {}: {}", path, e);
+                return Self::default();
+            }
+        };
+
+        let mut recipes = HashMap::new();
+        for recipe in file.recipes {
+            let unknown = recipe
+                .inputs
+                .iter()
+                .chain(&recipe.outputs)
+                .find(|grant| items.get(grant.template_id).is_none());
+            if let Some(grant) = unknown {
+                eprintln!("Recipe {} ({}) uses unknown item {}, skipping", recipe.id, recipe.name, grant.template_id);
+                continue;
+            }
+            if recipe.outputs.is_empty() {
+                eprintln!("Recipe {} ({}) has no outputs, skipping", recipe.id, recipe.name);
+                continue;
+            }
+            if recipes.contains_key(&recipe.id) {
+                eprintln!("Duplicate recipe id {} in {}, skipping", recipe.id, path);
+                continue;
+            }
+            recipes.insert(recipe.id, recipe);
+        }
+
+        println!("⚒️ Loaded {} recipes", recipes.len());
+        Self { recipes }
+    }
+
+    pub fn get(&self, recipe_id: RecipeId) -> Option<&Recipe> {
+        self.recipes.get(&recipe_id)
+    }
+
+    // Рецепты, доступные на станции данного типа (None - рецепты без станции)
+    pub fn for_station(&self, station: Option<&str>) -> Vec<&Recipe> {
+        let mut recipes: Vec<&Recipe> = self
+            .recipes
+            .values()
+            .filter(|recipe| recipe.station.as_deref() == station)
+            .collect();
+        recipes.sort_by_key(|recipe| recipe.id);
+        recipes
+    }
+}
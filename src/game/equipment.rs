@@ -0,0 +1,43 @@
+use crate::protocol::{EquipmentSlot, EquippedItem, InventoryItem};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use super::inventory::ItemStack;
+
+// Надетые предметы игрока, в каждом слоте не больше одного предмета
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Equipment {
+    slots: HashMap<EquipmentSlot, ItemStack>,
+}
+
+impl Equipment {
+    pub fn get(&self, slot: EquipmentSlot) -> Option<&ItemStack> {
+        self.slots.get(&slot)
+    }
+
+    // Возвращает предмет, который был в слоте до этого
+    pub fn equip(&mut self, slot: EquipmentSlot, stack: ItemStack) -> Option<ItemStack> {
+        self.slots.insert(slot, stack)
+    }
+
+    pub fn unequip(&mut self, slot: EquipmentSlot) -> Option<ItemStack> {
+        self.slots.remove(&slot)
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &ItemStack> {
+        self.slots.values()
+    }
+
+    pub fn to_protocol(&self) -> Vec<EquippedItem> {
+        self.slots
+            .iter()
+            .map(|(slot, stack)| EquippedItem {
+                slot: *slot,
+                item: InventoryItem {
+                    item_id: stack.item_id,
+                    template_id: stack.template_id,
+                    quantity: stack.quantity,
+                },
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,462 @@
+use crate::config::GuildsConfig;
+use crate::protocol::{GuildMemberInfo, GuildPermission, GuildRankInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use std::sync::Arc;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+use super::storage::{self, Collection, Storage};
+
+pub type GuildId = Uuid;
+
+pub const INVITE_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_MOTD_LENGTH: usize = 200;
+
+// Ранг 0 - глава гильдии, у него все права
+const LEADER_RANK: usize = 0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildRank {
+    pub name: String,
+    pub permissions: Vec<GuildPermission>,
+}
+
+impl GuildRank {
+    fn new(name: &str, permissions: &[GuildPermission]) -> Self {
+        Self {
+            name: name.to_string(),
+            permissions: permissions.to_vec(),
+        }
+    }
+}
+
+fn default_ranks() -> Vec<GuildRank> {
+    use GuildPermission::*;
+    vec![
+        GuildRank::new("Guild Master", &[Invite, Kick, SetRank, SetMotd]),
+        GuildRank::new("Officer", &[Invite, Kick, SetRank, SetMotd]),
+        GuildRank::new("Veteran", &[Invite]),
+        GuildRank::new("Member", &[]),
+        GuildRank::new("Initiate", &[]),
+    ]
+}
+
+// Персонаж один на аккаунт и носит имя аккаунта, а команды гильдии и состав
+// адресуют игроков по имени, поэтому участники хранятся по имени персонажа
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildMember {
+    pub name: String,
+    pub rank: usize, // индекс в Guild::ranks, меньше - старше
+    pub joined_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Guild {
+    pub id: GuildId,
+    pub name: String,
+    pub motd: String,
+    pub ranks: Vec<GuildRank>,
+    pub members: Vec<GuildMember>,
+    pub created_at: i64,
+}
+
+impl Guild {
+    pub fn member(&self, name: &str) -> Option<&GuildMember> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    fn member_mut(&mut self, name: &str) -> Option<&mut GuildMember> {
+        self.members.iter_mut().find(|member| member.name == name)
+    }
+
+    pub fn has_permission(&self, name: &str, permission: GuildPermission) -> bool {
+        self.member(name).is_some_and(|member| {
+            member.rank == LEADER_RANK
+                || self.ranks.get(member.rank).is_some_and(|rank| rank.permissions.contains(&permission))
+        })
+    }
+
+    pub fn member_names(&self) -> Vec<String> {
+        self.members.iter().map(|member| member.name.clone()).collect()
+    }
+
+    pub fn rank_info(&self) -> Vec<GuildRankInfo> {
+        self.ranks
+            .iter()
+            .enumerate()
+            .map(|(index, rank)| GuildRankInfo {
+                rank: index as u32,
+                name: rank.name.clone(),
+                permissions: rank.permissions.clone(),
+            })
+            .collect()
+    }
+
+    // Состав гильдии; online - кто из участников сейчас в игре
+    pub fn roster(&self, online: &[String]) -> Vec<GuildMemberInfo> {
+        let mut members: Vec<GuildMemberInfo> = self
+            .members
+            .iter()
+            .map(|member| GuildMemberInfo {
+                name: member.name.clone(),
+                rank: member.rank as u32,
+                online: online.contains(&member.name),
+            })
+            .collect();
+        members.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.name.cmp(&b.name)));
+        members
+    }
+
+    // Действовать над участником могут только старшие по рангу с нужным правом
+    fn check_authority(&self, actor: &str, target: &str, permission: GuildPermission) -> Result<(), String> {
+        if !self.has_permission(actor, permission) {
+            return Err("You don't have permission to do that".to_string());
+        }
+        let actor_rank = self.member(actor).map(|member| member.rank).unwrap_or(usize::MAX);
+        let target_rank = self
+            .member(target)
+            .map(|member| member.rank)
+            .ok_or_else(|| "Player is not in your guild".to_string())?;
+        if target_rank <= actor_rank {
+            return Err("You can only manage members of a lower rank".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Что изменилось в гильдии: оставшиеся участники получат новый состав,
+// removed - кто из нее вышел (при роспуске - все)
+#[derive(Debug)]
+pub struct GuildChange {
+    pub guild: Option<Guild>,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct GuildInvite {
+    guild_id: GuildId,
+    inviter: String,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct GuildState {
+    guilds: HashMap<GuildId, Guild>,
+    membership: HashMap<String, GuildId>,
+    invites: HashMap<String, GuildInvite>, // по имени приглашенного
+}
+
+impl GuildState {
+    fn guild_of(&self, name: &str) -> Option<&Guild> {
+        self.membership.get(name).and_then(|guild_id| self.guilds.get(guild_id))
+    }
+
+    fn guild_of_mut(&mut self, name: &str) -> Result<&mut Guild, String> {
+        self.membership
+            .get(name)
+            .and_then(|guild_id| self.guilds.get_mut(guild_id))
+            .ok_or_else(|| "You are not in a guild".to_string())
+    }
+
+    fn disband(&mut self, guild_id: GuildId) -> Vec<String> {
+        let Some(guild) = self.guilds.remove(&guild_id) else {
+            return Vec::new();
+        };
+        let names = guild.member_names();
+        for name in &names {
+            self.membership.remove(name);
+        }
+        self.invites.retain(|_, invite| invite.guild_id != guild_id);
+        names
+    }
+}
+
+#[derive(Debug)]
+pub struct GuildManager {
+    state: RwLock<GuildState>,
+    storage: Arc<dyn Storage>,
+    max_members: usize,
+    save_lock: Mutex<()>,
+}
+
+impl GuildManager {
+    pub fn load(config: &GuildsConfig, storage: Arc<dyn Storage>) -> Self {
+        let mut state = GuildState::default();
+
+        for guild in storage::load::<Guild>(storage.as_ref(), Collection::Guilds) {
+            for member in &guild.members {
+                state.membership.insert(member.name.clone(), guild.id);
+            }
+            state.guilds.insert(guild.id, guild);
+        }
+
+        println!("🛡️ Loaded {} guilds", state.guilds.len());
+        Self {
+            state: RwLock::new(state),
+            storage,
+            max_members: config.max_members,
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    // Сохраняем измененную гильдию; если ее уже нет (распущена) - удаляем запись
+    async fn save(&self, guild_id: GuildId) {
+        let _guard = self.save_lock.lock().await;
+        let guild = {
+            let state = self.state.read().await;
+            state.guilds.get(&guild_id).cloned()
+        };
+        let result = match guild {
+            Some(guild) => storage::save(&self.storage, Collection::Guilds, &[guild]).await,
+            None => storage::remove(&self.storage, Collection::Guilds, vec![guild_id.to_string()]).await,
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    }
+
+    pub async fn get_guild(&self, name: &str) -> Option<Guild> {
+        let state = self.state.read().await;
+        state.guild_of(name).cloned()
+    }
+
+    pub async fn create(&self, founder: &str, guild_name: &str) -> Result<Guild, String> {
+        let guild_name = guild_name.trim();
+        if guild_name.len() < 3 || guild_name.len() > 24 {
+            return Err("Guild name must be 3-24 characters long".to_string());
+        }
+        if !guild_name.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ') {
+            return Err("Guild name may contain only letters, digits and spaces".to_string());
+        }
+
+        let guild = {
+            let mut state = self.state.write().await;
+            if state.membership.contains_key(founder) {
+                return Err("You are already in a guild".to_string());
+            }
+            if state.guilds.values().any(|guild| guild.name.eq_ignore_ascii_case(guild_name)) {
+                return Err("A guild with this name already exists".to_string());
+            }
+
+            let now = chrono::Utc::now().timestamp();
+            let guild = Guild {
+                id: Uuid::new_v4(),
+                name: guild_name.to_string(),
+                motd: String::new(),
+                ranks: default_ranks(),
+                members: vec![GuildMember {
+                    name: founder.to_string(),
+                    rank: LEADER_RANK,
+                    joined_at: now,
+                }],
+                created_at: now,
+            };
+            state.membership.insert(founder.to_string(), guild.id);
+            state.guilds.insert(guild.id, guild.clone());
+            guild
+        };
+
+        self.save(guild.id).await;
+        Ok(guild)
+    }
+
+    // Возвращает название гильдии для приглашения
+    pub async fn invite(&self, inviter: &str, invitee: &str) -> Result<String, String> {
+        let mut state = self.state.write().await;
+        let guild = state.guild_of(inviter).ok_or_else(|| "You are not in a guild".to_string())?;
+        if !guild.has_permission(inviter, GuildPermission::Invite) {
+            return Err("You don't have permission to invite".to_string());
+        }
+        if guild.members.len() >= self.max_members {
+            return Err("Your guild is full".to_string());
+        }
+        let (guild_id, guild_name) = (guild.id, guild.name.clone());
+
+        if state.membership.contains_key(invitee) {
+            return Err("Player is already in a guild".to_string());
+        }
+        let now = Instant::now();
+        if state.invites.get(invitee).is_some_and(|invite| invite.expires_at > now) {
+            return Err("Player already has a pending guild invite".to_string());
+        }
+
+        state.invites.insert(invitee.to_string(), GuildInvite {
+            guild_id,
+            inviter: inviter.to_string(),
+            expires_at: now + INVITE_TIMEOUT,
+        });
+        Ok(guild_name)
+    }
+
+    pub async fn accept(&self, invitee: &str) -> Result<Guild, String> {
+        let guild = {
+            let mut state = self.state.write().await;
+            let invite = state
+                .invites
+                .remove(invitee)
+                .ok_or_else(|| "You have no pending guild invite".to_string())?;
+            if invite.expires_at <= Instant::now() {
+                return Err("Guild invite has expired".to_string());
+            }
+            if state.membership.contains_key(invitee) {
+                return Err("You are already in a guild".to_string());
+            }
+
+            let guild = state
+                .guilds
+                .get_mut(&invite.guild_id)
+                .ok_or_else(|| "Guild no longer exists".to_string())?;
+            if guild.members.len() >= self.max_members {
+                return Err("Guild is full".to_string());
+            }
+            guild.members.push(GuildMember {
+                name: invitee.to_string(),
+                rank: guild.ranks.len().saturating_sub(1),
+                joined_at: chrono::Utc::now().timestamp(),
+            });
+            let guild = guild.clone();
+            state.membership.insert(invitee.to_string(), guild.id);
+            guild
+        };
+
+        self.save(guild.id).await;
+        Ok(guild)
+    }
+
+    // Возвращает имя пригласившего, чтобы сообщить ему об отказе
+    pub async fn decline(&self, invitee: &str) -> Result<String, String> {
+        let mut state = self.state.write().await;
+        state
+            .invites
+            .remove(invitee)
+            .map(|invite| invite.inviter)
+            .ok_or_else(|| "You have no pending guild invite".to_string())
+    }
+
+    // Глава может выйти, только если он последний участник - тогда гильдия распускается
+    pub async fn leave(&self, name: &str) -> Result<GuildChange, String> {
+        let (guild_id, change) = {
+            let mut state = self.state.write().await;
+            let guild = state.guild_of_mut(name)?;
+            let guild_id = guild.id;
+            let is_leader = guild.member(name).is_some_and(|member| member.rank == LEADER_RANK);
+
+            if guild.members.len() == 1 {
+                (guild_id, GuildChange { guild: None, removed: state.disband(guild_id) })
+            } else if is_leader {
+                return Err("Transfer leadership before leaving the guild".to_string());
+            } else {
+                guild.members.retain(|member| member.name != name);
+                let guild = guild.clone();
+                state.membership.remove(name);
+                (guild_id, GuildChange { guild: Some(guild), removed: vec![name.to_string()] })
+            }
+        };
+
+        self.save(guild_id).await;
+        Ok(change)
+    }
+
+    pub async fn kick(&self, actor: &str, target: &str) -> Result<GuildChange, String> {
+        let change = {
+            let mut state = self.state.write().await;
+            let guild = state.guild_of_mut(actor)?;
+            guild.check_authority(actor, target, GuildPermission::Kick)?;
+
+            guild.members.retain(|member| member.name != target);
+            let guild = guild.clone();
+            state.membership.remove(target);
+            GuildChange { guild: Some(guild), removed: vec![target.to_string()] }
+        };
+
+        if let Some(guild) = &change.guild {
+            self.save(guild.id).await;
+        }
+        Ok(change)
+    }
+
+    // Повышение/понижение: новый ранг должен быть ниже ранга того, кто меняет
+    pub async fn set_rank(&self, actor: &str, target: &str, rank: usize) -> Result<Guild, String> {
+        let guild = {
+            let mut state = self.state.write().await;
+            let guild = state.guild_of_mut(actor)?;
+            guild.check_authority(actor, target, GuildPermission::SetRank)?;
+
+            let actor_rank = guild.member(actor).map(|member| member.rank).unwrap_or(usize::MAX);
+            if rank >= guild.ranks.len() {
+                return Err("Unknown guild rank".to_string());
+            }
+            if rank <= actor_rank {
+                return Err("You can only assign ranks below your own".to_string());
+            }
+            if let Some(member) = guild.member_mut(target) {
+                member.rank = rank;
+            }
+            guild.clone()
+        };
+
+        self.save(guild.id).await;
+        Ok(guild)
+    }
+
+    // Передача главенства: прежний глава становится офицером
+    pub async fn transfer_leadership(&self, leader: &str, target: &str) -> Result<Guild, String> {
+        let guild = {
+            let mut state = self.state.write().await;
+            let guild = state.guild_of_mut(leader)?;
+            if guild.member(leader).is_none_or(|member| member.rank != LEADER_RANK) {
+                return Err("Only the guild master can transfer leadership".to_string());
+            }
+            if leader == target {
+                return Err("You are already the guild master".to_string());
+            }
+            let new_leader = guild
+                .member_mut(target)
+                .ok_or_else(|| "Player is not in your guild".to_string())?;
+            new_leader.rank = LEADER_RANK;
+            if let Some(old_leader) = guild.member_mut(leader) {
+                old_leader.rank = LEADER_RANK + 1;
+            }
+            guild.clone()
+        };
+
+        self.save(guild.id).await;
+        Ok(guild)
+    }
+
+    pub async fn set_motd(&self, actor: &str, motd: &str) -> Result<Guild, String> {
+        let motd = motd.trim();
+        if motd.chars().count() > MAX_MOTD_LENGTH {
+            return Err(format!("Message of the day is limited to {} characters", MAX_MOTD_LENGTH));
+        }
+
+        let guild = {
+            let mut state = self.state.write().await;
+            let guild = state.guild_of_mut(actor)?;
+            if !guild.has_permission(actor, GuildPermission::SetMotd) {
+                return Err("You don't have permission to change the message of the day".to_string());
+            }
+            guild.motd = motd.to_string();
+            guild.clone()
+        };
+
+        self.save(guild.id).await;
+        Ok(guild)
+    }
+
+    pub async fn disband(&self, leader: &str) -> Result<Vec<String>, String> {
+        let (guild_id, removed) = {
+            let mut state = self.state.write().await;
+            let guild = state.guild_of(leader).ok_or_else(|| "You are not in a guild".to_string())?;
+            if guild.member(leader).is_none_or(|member| member.rank != LEADER_RANK) {
+                return Err("Only the guild master can disband the guild".to_string());
+            }
+            let guild_id = guild.id;
+            (guild_id, state.disband(guild_id))
+        };
+
+        self.save(guild_id).await;
+        Ok(removed)
+    }
+}
@@ -0,0 +1,264 @@
+use crate::protocol::Vector3;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use super::moderation::format_duration;
+use super::objects::{ObjectKind, WorldObject};
+use super::party::PartyId;
+use super::session::PlayerId;
+use super::zones::{Zone, ZoneId};
+
+// Копии подземелий нумеруются не ниже этого id и всегда выше зон из файла
+pub const FIRST_INSTANCE_ID: ZoneId = 100_000;
+
+// Как часто проверять сброс и опустевшие копии
+pub const INSTANCE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// Выдает id новым копиям мира
+#[derive(Debug)]
+pub struct InstanceManager {
+    next_id: AtomicU32,
+}
+
+impl InstanceManager {
+    pub fn new(zones: &HashMap<ZoneId, Zone>) -> Self {
+        let above_zones = zones.keys().max().map_or(0, |max| max.saturating_add(1));
+        Self { next_id: AtomicU32::new(above_zones.max(FIRST_INSTANCE_ID)) }
+    }
+
+    fn allocate(&self, zones: &HashMap<ZoneId, Zone>) -> Result<ZoneId, String> {
+        loop {
+            let id = self
+                .next_id
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+                .map_err(|_| "No free instance ids left".to_string())?;
+            if !zones.contains_key(&id) {
+                return Ok(id);
+            }
+        }
+    }
+}
+
+// Чья копия: группы или одиночного игрока
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceOwner {
+    Party(PartyId),
+    Player(PlayerId),
+}
+
+#[derive(Debug)]
+pub struct Instance {
+    pub template: ZoneId,
+    pub owner: InstanceOwner,
+    pub resets_at: Instant,
+    pub empty_since: Option<Instant>,
+}
+
+// Привязка игрока к копии: до сброса в другую копию того же подземелья не пустит
+#[derive(Debug, Clone, Copy)]
+pub struct Lockout {
+    pub instance_id: ZoneId,
+    pub expires_at: Instant,
+}
+
+// Куда на самом деле попадет игрок. Для подземелья это копия, к которой он привязан,
+// или копия его группы; если ни той ни другой нет - создается новая
+pub fn resolve_zone(
+    instances: &InstanceManager,
+    zones: &mut HashMap<ZoneId, Zone>,
+    objects: &mut HashMap<Uuid, WorldObject>,
+    zone_id: ZoneId,
+    player_id: PlayerId,
+    owner: InstanceOwner,
+) -> Result<ZoneId, String> {
+    let now = Instant::now();
+    let template = zones.get_mut(&zone_id).ok_or_else(|| format!("Zone {} does not exist", zone_id))?;
+    if !template.is_instance_template() {
+        return Ok(zone_id);
+    }
+    template.lockouts.retain(|_, lockout| lockout.expires_at > now);
+    let lockout = template.lockouts.get(&player_id).copied();
+    let name = template.def.name.clone();
+
+    let group_instance = zones
+        .iter()
+        .find(|(_, zone)| {
+            zone.instance
+                .as_ref()
+                .is_some_and(|instance| instance.template == zone_id && instance.owner == owner)
+        })
+        .map(|(id, _)| *id);
+
+    match (lockout, group_instance) {
+        (Some(lockout), group) if zones.contains_key(&lockout.instance_id) => {
+            if group.is_some_and(|id| id != lockout.instance_id) {
+                return Err(format!("You are saved to another instance of {}", name));
+            }
+            Ok(lockout.instance_id)
+        }
+        (Some(lockout), _) => Err(format!(
+            "You are locked out of {} for {}",
+            name,
+            format_duration(lockout.expires_at - now)
+        )),
+        (None, Some(instance_id)) => Ok(instance_id),
+        (None, None) => create_instance(instances, zones, objects, zone_id, owner, now),
+    }
+}
+
+// Игрок вошел в копию - привязываем его к ней до ее сброса
+pub fn record_lockout(zones: &mut HashMap<ZoneId, Zone>, instance_id: ZoneId, player_id: PlayerId) {
+    let Some((template_id, resets_at)) = zones
+        .get(&instance_id)
+        .and_then(|zone| zone.instance.as_ref())
+        .map(|instance| (instance.template, instance.resets_at))
+    else {
+        return;
+    };
+    if let Some(template) = zones.get_mut(&template_id) {
+        template.lockouts.insert(player_id, Lockout { instance_id, expires_at: resets_at });
+    }
+}
+
+// Новая копия: определение зоны и все объекты шаблона с новыми id
+fn create_instance(
+    instances: &InstanceManager,
+    zones: &mut HashMap<ZoneId, Zone>,
+    objects: &mut HashMap<Uuid, WorldObject>,
+    template_id: ZoneId,
+    owner: InstanceOwner,
+    now: Instant,
+) -> Result<ZoneId, String> {
+    let instance_id = instances.allocate(zones)?;
+    let template = &zones[&template_id];
+    let mut zone = Zone::new(template.def.clone(), template.max_players);
+    zone.def.id = instance_id;
+    zone.instance = Some(Instance {
+        template: template_id,
+        owner,
+        resets_at: now + Duration::from_secs(template.def.reset_secs),
+        empty_since: None,
+    });
+
+    // Рычаги копии должны переключать объекты этой же копии
+    let copy_ids: HashMap<Uuid, Uuid> = objects
+        .values()
+        .filter(|object| object.zone_id == template_id)
+        .map(|object| (object.id, Uuid::new_v4()))
+        .collect();
+    for (template_object, copy_id) in &copy_ids {
+        let mut copy = objects[template_object].clone();
+        copy.id = *copy_id;
+        copy.zone_id = instance_id;
+        if let ObjectKind::Lever { targets, .. } = &mut copy.kind {
+            for target in targets.iter_mut() {
+                if let Some(id) = copy_ids.get(target) {
+                    *target = *id;
+                }
+            }
+        }
+        objects.insert(copy.id, copy);
+    }
+
+    println!("[{}] 🏰 Created instance {} of {} ({} objects)",
+        chrono::Local::now().format("%H:%M:%S"),
+        instance_id, zone.def.name, copy_ids.len()
+    );
+    zones.insert(instance_id, zone);
+    Ok(instance_id)
+}
+
+// Копии, которые пора убрать: наступил сброс или копия пустует дольше empty_timeout
+pub fn expired_instances(zones: &mut HashMap<ZoneId, Zone>, empty_timeout: Duration, now: Instant) -> Vec<ZoneId> {
+    let mut expired = Vec::new();
+    for (zone_id, zone) in zones.iter_mut() {
+        let Some(instance) = zone.instance.as_mut() else {
+            continue;
+        };
+        if zone.players.is_empty() {
+            instance.empty_since.get_or_insert(now);
+        } else {
+            instance.empty_since = None;
+        }
+        let abandoned = instance.empty_since.is_some_and(|since| now.duration_since(since) >= empty_timeout);
+        if abandoned || instance.resets_at <= now {
+            expired.push(*zone_id);
+        }
+    }
+    expired
+}
+
+// Куда выкинуть игроков из копии при сбросе: первый выход из подземелья, иначе стартовая зона
+pub fn eviction_point(zones: &HashMap<ZoneId, Zone>, instance_id: ZoneId, start_zone: ZoneId) -> (ZoneId, Vector3) {
+    let exit = zones.get(&instance_id).and_then(|zone| {
+        zone.def
+            .exits
+            .iter()
+            .find(|exit| zones.get(&exit.destination_zone).is_some_and(|zone| !zone.is_instance_template()))
+    });
+    match exit {
+        Some(exit) => (exit.destination_zone, exit.destination.clone()),
+        None => {
+            let spawn = zones
+                .get(&start_zone)
+                .map(|zone| zone.def.spawn.clone())
+                .unwrap_or(Vector3 { x: 0.0, y: 0.0, z: 0.0 });
+            (start_zone, spawn)
+        }
+    }
+}
+
+// Удаляем пустую копию вместе с ее объектами. Привязки игроков остаются до сброса
+pub fn destroy_instance(zones: &mut HashMap<ZoneId, Zone>, objects: &mut HashMap<Uuid, WorldObject>, instance_id: ZoneId) {
+    let Some(zone) = zones.remove(&instance_id) else {
+        return;
+    };
+    objects.retain(|_, object| object.zone_id != instance_id);
+    println!("[{}] 🏚️ Instance {} of {} torn down",
+        chrono::Local::now().format("%H:%M:%S"),
+        instance_id, zone.def.name
+    );
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::zones::ZoneDef;
+
+    fn zones(ids: &[ZoneId]) -> HashMap<ZoneId, Zone> {
+        ids.iter()
+            .map(|id| {
+                let def: ZoneDef = toml::from_str(&format!(
+                    "id = {}\nname = \"Zone {}\"\nspawn = {{ x = 0.0, y = 0.0, z = 0.0 }}",
+                    id, id
+                ))
+                .unwrap();
+                (*id, Zone::new(def, 10))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn instance_ids_start_above_configured_zones() {
+        let small = zones(&[1, 2, 3]);
+        assert_eq!(InstanceManager::new(&small).allocate(&small), Ok(FIRST_INSTANCE_ID));
+
+        let large = zones(&[1, 250_000]);
+        let instances = InstanceManager::new(&large);
+        assert_eq!(instances.allocate(&large), Ok(250_001));
+        assert_eq!(instances.allocate(&large), Ok(250_002));
+    }
+
+    #[test]
+    fn managers_are_independent_and_skip_taken_ids() {
+        let mut world = zones(&[1]);
+        let first = InstanceManager::new(&world);
+        let second = InstanceManager::new(&world);
+        assert_eq!(first.allocate(&world), second.allocate(&world));
+
+        world.extend(zones(&[FIRST_INSTANCE_ID + 1]));
+        assert_eq!(first.allocate(&world), Ok(FIRST_INSTANCE_ID + 2));
+    }
+}
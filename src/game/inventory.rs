@@ -0,0 +1,289 @@
+use crate::protocol::{InventoryItem, InventorySlot};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use super::items::{ItemTemplate, ItemTemplateId};
+
+// Стопка предметов в ячейке. item_id - идентификатор конкретной стопки,
+// именно его клиент присылает в UseItem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item_id: Uuid,
+    pub template_id: ItemTemplateId,
+    pub quantity: u32,
+}
+
+impl ItemStack {
+    fn to_protocol(&self) -> InventoryItem {
+        InventoryItem {
+            item_id: self.item_id,
+            template_id: self.template_id,
+            quantity: self.quantity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: vec![None; capacity],
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn find(&self, item_id: &Uuid) -> Option<(usize, &ItemStack)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .find_map(|(slot, stack)| stack.as_ref().filter(|s| s.item_id == *item_id).map(|s| (slot, s)))
+    }
+
+    pub fn count(&self, template_id: ItemTemplateId) -> u32 {
+        self.slots
+            .iter()
+            .flatten()
+            .filter(|stack| stack.template_id == template_id)
+            .map(|stack| stack.quantity)
+            .sum()
+    }
+
+    // Сколько штук предмета поместится (с учетом неполных стопок и пустых ячеек)
+    pub fn free_space_for(&self, template: &ItemTemplate) -> u32 {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Some(stack) if stack.template_id == template.id => template.max_stack.saturating_sub(stack.quantity),
+                Some(_) => 0,
+                None => template.max_stack,
+            })
+            .fold(0u32, |total, space| total.saturating_add(space))
+    }
+
+    // Сначала докладываем в существующие стопки, потом занимаем пустые ячейки.
+    // Либо кладется все количество, либо ничего.
+    pub fn add_item(&mut self, template: &ItemTemplate, quantity: u32) -> Result<Vec<usize>, String> {
+        if quantity == 0 {
+            return Ok(Vec::new());
+        }
+        if self.free_space_for(template) < quantity {
+            return Err("Inventory is full".to_string());
+        }
+
+        let mut remaining = quantity;
+        let mut changed = Vec::new();
+
+        for (slot, stack) in self.slots.iter_mut().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(stack) = stack
+                && stack.template_id == template.id
+                && stack.quantity < template.max_stack
+            {
+                let added = remaining.min(template.max_stack - stack.quantity);
+                stack.quantity += added;
+                remaining -= added;
+                changed.push(slot);
+            }
+        }
+
+        for (slot, stack) in self.slots.iter_mut().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+            if stack.is_none() {
+                let added = remaining.min(template.max_stack);
+                *stack = Some(ItemStack {
+                    item_id: Uuid::new_v4(),
+                    template_id: template.id,
+                    quantity: added,
+                });
+                remaining -= added;
+                changed.push(slot);
+            }
+        }
+
+        Ok(changed)
+    }
+
+    pub fn has_free_slot(&self) -> bool {
+        self.slots.iter().any(|s| s.is_none())
+    }
+
+    // Кладем готовую стопку в первую свободную ячейку, сохраняя ее item_id
+    pub fn insert_stack(&mut self, stack: ItemStack) -> Result<usize, String> {
+        let slot = self
+            .slots
+            .iter()
+            .position(|s| s.is_none())
+            .ok_or_else(|| "Inventory is full".to_string())?;
+        self.slots[slot] = Some(stack);
+        Ok(slot)
+    }
+
+    // Забираем стопку целиком
+    pub fn take_stack(&mut self, item_id: &Uuid) -> Option<(usize, ItemStack)> {
+        let (slot, _) = self.find(item_id)?;
+        self.slots[slot].take().map(|stack| (slot, stack))
+    }
+
+    // Забираем quantity штук из конкретной стопки
+    pub fn remove_from_stack(&mut self, item_id: &Uuid, quantity: u32) -> Result<usize, String> {
+        let (slot, stack) = self.find(item_id).ok_or_else(|| "You don't have this item".to_string())?;
+        if stack.quantity < quantity {
+            return Err("Not enough items".to_string());
+        }
+
+        let entry = &mut self.slots[slot];
+        if let Some(stack) = entry {
+            stack.quantity -= quantity;
+            if stack.quantity == 0 {
+                *entry = None;
+            }
+        }
+        Ok(slot)
+    }
+
+    // Забираем quantity штук шаблона из любых стопок, начиная с последних ячеек
+    pub fn remove_template(&mut self, template_id: ItemTemplateId, quantity: u32) -> Result<Vec<usize>, String> {
+        if self.count(template_id) < quantity {
+            return Err("Not enough items".to_string());
+        }
+
+        let mut remaining = quantity;
+        let mut changed = Vec::new();
+        for (slot, entry) in self.slots.iter_mut().enumerate().rev() {
+            if remaining == 0 {
+                break;
+            }
+            if let Some(stack) = entry
+                && stack.template_id == template_id
+            {
+                let removed = remaining.min(stack.quantity);
+                stack.quantity -= removed;
+                remaining -= removed;
+                if stack.quantity == 0 {
+                    *entry = None;
+                }
+                changed.push(slot);
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn slot_update(&self, slot: usize) -> InventorySlot {
+        InventorySlot {
+            slot: slot as u32,
+            item: self.slots.get(slot).and_then(|s| s.as_ref()).map(ItemStack::to_protocol),
+        }
+    }
+
+    // Занятые ячейки для полного снимка инвентаря
+    pub fn occupied_slots(&self) -> Vec<InventorySlot> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, stack)| stack.is_some())
+            .map(|(slot, _)| self.slot_update(slot))
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(id: ItemTemplateId, max_stack: u32) -> ItemTemplate {
+        ItemTemplate {
+            id,
+            name: format!("item {}", id),
+            rarity: Default::default(),
+            max_stack,
+            consumable: false,
+            effect: None,
+            slot: None,
+            stat_modifiers: Vec::new(),
+            classes: Vec::new(),
+        }
+    }
+
+    fn quantities(inventory: &Inventory) -> Vec<Option<(ItemTemplateId, u32)>> {
+        inventory.slots.iter().map(|slot| slot.as_ref().map(|stack| (stack.template_id, stack.quantity))).collect()
+    }
+
+    #[test]
+    fn add_fills_partial_stacks_before_empty_slots() {
+        let potion = template(1, 5);
+        let mut inventory = Inventory::new(4);
+        assert_eq!(inventory.add_item(&potion, 3).unwrap(), vec![0]);
+        assert_eq!(inventory.add_item(&template(2, 1), 1).unwrap(), vec![1]);
+
+        // 2 докладываются в первую стопку, остальное делится на новые стопки
+        assert_eq!(inventory.add_item(&potion, 9).unwrap(), vec![0, 2, 3]);
+        assert_eq!(quantities(&inventory), vec![Some((1, 5)), Some((2, 1)), Some((1, 5)), Some((1, 2))]);
+        assert_eq!(inventory.count(1), 12);
+    }
+
+    #[test]
+    fn add_is_all_or_nothing() {
+        let potion = template(1, 5);
+        let mut inventory = Inventory::new(2);
+        inventory.add_item(&potion, 4).unwrap();
+        assert_eq!(inventory.free_space_for(&potion), 6);
+
+        assert!(inventory.add_item(&potion, 7).is_err());
+        assert_eq!(quantities(&inventory), vec![Some((1, 4)), None]);
+        assert!(inventory.add_item(&potion, 6).is_ok());
+        assert!(!inventory.has_free_slot());
+    }
+
+    #[test]
+    fn remove_from_stack_splits_and_clears() {
+        let potion = template(1, 10);
+        let mut inventory = Inventory::new(2);
+        inventory.add_item(&potion, 6).unwrap();
+        let item_id = inventory.slots[0].as_ref().unwrap().item_id;
+
+        assert_eq!(inventory.remove_from_stack(&item_id, 4), Ok(0));
+        assert_eq!(inventory.find(&item_id).unwrap().1.quantity, 2);
+        assert!(inventory.remove_from_stack(&item_id, 3).is_err());
+        assert_eq!(inventory.remove_from_stack(&item_id, 2), Ok(0));
+        assert!(inventory.find(&item_id).is_none());
+        assert!(inventory.remove_from_stack(&item_id, 1).is_err());
+    }
+
+    #[test]
+    fn remove_template_takes_from_last_slots() {
+        let arrow = template(3, 10);
+        let mut inventory = Inventory::new(3);
+        inventory.add_item(&arrow, 25).unwrap();
+
+        assert_eq!(inventory.remove_template(3, 8).unwrap(), vec![2, 1]);
+        assert_eq!(quantities(&inventory), vec![Some((3, 10)), Some((3, 7)), None]);
+        assert!(inventory.remove_template(3, 18).is_err());
+        assert_eq!(inventory.count(3), 17);
+    }
+
+    #[test]
+    fn stacks_keep_their_id_when_moved() {
+        let mut inventory = Inventory::new(2);
+        inventory.add_item(&template(1, 1), 1).unwrap();
+        let item_id = inventory.slots[0].as_ref().unwrap().item_id;
+
+        let (slot, stack) = inventory.take_stack(&item_id).unwrap();
+        assert_eq!(slot, 0);
+        inventory.add_item(&template(2, 1), 1).unwrap();
+        assert_eq!(inventory.insert_stack(stack), Ok(1));
+        assert_eq!(inventory.find(&item_id).map(|(slot, _)| slot), Some(1));
+        assert!(inventory.insert_stack(ItemStack { item_id: Uuid::new_v4(), template_id: 1, quantity: 1 }).is_err());
+        assert_eq!(inventory.occupied_slots().len(), 2);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::protocol::{EquipmentSlot, PlayerClass, Vector3};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use super::stats::{StatKind, StatModifier};
+
+pub type ItemTemplateId = u32;
+
+// Эффект использования предмета, рассчитывается на сервере
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ItemEffect {
+    Heal { amount: i32 },
+    RestoreMana { amount: i32 },
+    Buff { stat: StatKind, amount: i32, duration_secs: u64 },
+    Teleport { zone_id: u32, position: Vector3 },
+    // Возврат к точке привязки, без нее - в стартовую зону
+    Hearth { cooldown_secs: u64 },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub enum ItemRarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemTemplate {
+    pub id: ItemTemplateId,
+    pub name: String,
+    #[serde(default)]
+    #[allow(dead_code)]
+    pub rarity: ItemRarity,
+    #[serde(default = "default_max_stack")]
+    pub max_stack: u32,
+    #[serde(default)]
+    pub consumable: bool,
+    pub effect: Option<ItemEffect>,
+    // Слот экипировки, None - предмет нельзя надеть
+    pub slot: Option<EquipmentSlot>,
+    #[serde(default)]
+    pub stat_modifiers: Vec<StatModifier>,
+    // Пустой список - без ограничений по классу
+    #[serde(default)]
+    pub classes: Vec<PlayerClass>,
+}
+
+impl ItemTemplate {
+    pub fn usable_by(&self, class: PlayerClass) -> bool {
+        self.classes.is_empty() || self.classes.contains(&class)
+    }
+}
+
+fn default_max_stack() -> u32 {
+    1
+}
+
+// Предмет и количество: стартовые предметы, содержимое сундуков и т.п.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemGrant {
+    pub template_id: ItemTemplateId,
+    pub quantity: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemDataFile {
+    #[serde(default)]
+    items: Vec<ItemTemplate>,
+    #[serde(default)]
+    starting_items: Vec<ItemGrant>,
+    #[serde(default)]
+    starting_gold: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ItemDatabase {
+    templates: HashMap<ItemTemplateId, ItemTemplate>,
+    starting_items: Vec<ItemGrant>,
+    starting_gold: u64,
+}
+
+impl ItemDatabase {
+    pub fn load(path: &str) -> Self {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => {
+                println!("Item data not found at {}, item database is empty", path);
+                return Self::default();
+            }
+        };
+
+        let file: ItemDataFile = match toml::from_str(&content) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("Failed to parse item data {}: {}", path, e);
+                return Self::default();
+            }
+        };
+
+        let mut templates = HashMap::new();
+        for template in file.items {
+            if template.max_stack == 0 {
+                eprintln!("Item {} ({}) has max_stack = 0, skipping", template.id, template.name);
+                continue;
+            }
+            if template.slot.is_some() && template.max_stack != 1 {
+                eprintln!("Equippable item {} ({}) must have max_stack = 1, skipping", template.id, template.name);
+                continue;
+            }
+            if templates.contains_key(&template.id) {
+                eprintln!("Duplicate item id {} in {}, skipping", template.id, path);
+                continue;
+            }
+            templates.insert(template.id, template);
+        }
+
+        let starting_items = file
+            .starting_items
+            .into_iter()
+            .filter(|item| {
+                let known = templates.contains_key(&item.template_id);
+                if !known {
+                    eprintln!("Unknown starting item template {}", item.template_id);
+                }
+                known
+            })
+            .collect();
+
+        println!("🎒 Loaded {} item templates", templates.len());
+        Self { templates, starting_items, starting_gold: file.starting_gold }
+    }
+
+    pub fn get(&self, template_id: ItemTemplateId) -> Option<&ItemTemplate> {
+        self.templates.get(&template_id)
+    }
+
+    pub fn starting_items(&self) -> &[ItemGrant] {
+        &self.starting_items
+    }
+
+    pub fn starting_gold(&self) -> u64 {
+        self.starting_gold
+    }
+}
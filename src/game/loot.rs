@@ -0,0 +1,263 @@
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::time::Duration;
+use super::items::{ItemDatabase, ItemGrant, ItemTemplateId};
+
+// Как часто проверять, не пора ли убрать лежащую добычу
+pub const LOOT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_quantity() -> u32 {
+    1
+}
+
+fn default_rolls() -> u32 {
+    1
+}
+
+// Вариант броска; template_id не задан - в этом броске ничего не выпадает
+#[derive(Debug, Clone, Deserialize)]
+pub struct LootEntry {
+    pub template_id: Option<ItemTemplateId>,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default = "default_quantity")]
+    pub min_quantity: u32,
+    #[serde(default = "default_quantity")]
+    pub max_quantity: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LootTable {
+    pub id: String,
+    // Сколько раз выбирается вариант из entries
+    #[serde(default = "default_rolls")]
+    pub rolls: u32,
+    #[serde(default)]
+    pub gold_min: u64,
+    #[serde(default)]
+    pub gold_max: u64,
+    #[serde(default)]
+    pub entries: Vec<LootEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LootDataFile {
+    #[serde(default)]
+    tables: Vec<LootTable>,
+}
+
+// Выпавшая добыча
+#[derive(Debug, Clone)]
+pub struct LootDrop {
+    pub items: Vec<ItemGrant>,
+    pub gold: u64,
+}
+
+impl LootDrop {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.gold == 0
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LootTables {
+    tables: HashMap<String, LootTable>,
+}
+
+impl LootTables {
+    pub fn load(path: &str, items: &ItemDatabase) -> Self {
+        let data = match fs::read_to_string(path) {
+            Ok(content) => match toml::from_str::<LootDataFile>(&content) {
+                Ok(file) => file.tables,
+                Err(e) => {
+                    eprintln!("Failed to parse loot tables {}: {}", path, e);
+                    Vec::new()
+                }
+            },
+            Err(_) => {
+                println!("Loot tables not found at {}, nothing will drop", path);
+                Vec::new()
+            }
+        };
+
+        let mut tables = HashMap::new();
+        for mut table in data {
+            if tables.contains_key(&table.id) {
+                eprintln!("Duplicate loot table {} in {}, skipping", table.id, path);
+                continue;
+            }
+            let table_id = table.id.clone();
+            table.entries.retain(|entry| {
+                let valid = entry.weight > 0
+                    && entry.min_quantity > 0
+                    && entry.min_quantity <= entry.max_quantity
+                    && entry.template_id.is_none_or(|id| items.get(id).is_some());
+                if !valid {
+                    eprintln!("Loot table {}: invalid entry {:?}, skipping", table_id, entry.template_id);
+                }
+                valid
+            });
+            if table.gold_min > table.gold_max {
+                eprintln!("Loot table {}: gold_min exceeds gold_max, dropping no gold", table_id);
+                table.gold_max = 0;
+                table.gold_min = 0;
+            }
+            tables.insert(table_id, table);
+        }
+
+        println!("💰 Loaded {} loot tables", tables.len());
+        Self { tables }
+    }
+
+    // Бросаем таблицу: rolls раз выбираем вариант по весам, одинаковые предметы складываем
+    pub fn roll(&self, table_id: &str) -> Option<LootDrop> {
+        let table = self.tables.get(table_id)?;
+        let mut rng = rand::rng();
+        let total: u32 = table.entries.iter().map(|entry| entry.weight).sum();
+
+        let mut items: BTreeMap<ItemTemplateId, u32> = BTreeMap::new();
+        if total > 0 {
+            for _ in 0..table.rolls {
+                let mut roll = rng.random_range(0..total);
+                let Some(entry) = table.entries.iter().find(|entry| {
+                    if roll < entry.weight {
+                        return true;
+                    }
+                    roll -= entry.weight;
+                    false
+                }) else {
+                    continue;
+                };
+                if let Some(template_id) = entry.template_id {
+                    *items.entry(template_id).or_default() += rng.random_range(entry.min_quantity..=entry.max_quantity);
+                }
+            }
+        }
+
+        Some(LootDrop {
+            items: items
+                .into_iter()
+                .map(|(template_id, quantity)| ItemGrant { template_id, quantity })
+                .collect(),
+            gold: rng.random_range(table.gold_min..=table.gold_max),
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITEMS: &str = r#"
+[[items]]
+id = 1
+name = "Wolf Pelt"
+max_stack = 20
+
+[[items]]
+id = 2
+name = "Fang"
+max_stack = 20
+"#;
+
+    const TABLES: &str = r#"
+[[tables]]
+id = "pelts"
+rolls = 3
+gold_min = 4
+gold_max = 9
+
+[[tables.entries]]
+template_id = 1
+min_quantity = 2
+max_quantity = 2
+
+[[tables]]
+id = "mostly_fangs"
+
+[[tables.entries]]
+template_id = 2
+weight = 9
+
+[[tables.entries]]
+weight = 1
+
+[[tables]]
+id = "broken"
+gold_min = 10
+gold_max = 1
+
+[[tables.entries]]
+template_id = 99
+
+[[tables.entries]]
+template_id = 1
+weight = 0
+
+[[tables.entries]]
+template_id = 1
+min_quantity = 3
+max_quantity = 2
+
+[[tables]]
+id = "pelts"
+"#;
+
+    // Таблицы грузятся тем же путем, что и на сервере: из файлов
+    fn tables() -> LootTables {
+        let dir = std::env::temp_dir().join(format!("loot-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let (items_path, loot_path) = (dir.join("items.toml"), dir.join("loot.toml"));
+        fs::write(&items_path, ITEMS).unwrap();
+        fs::write(&loot_path, TABLES).unwrap();
+
+        let items = ItemDatabase::load(&items_path.to_string_lossy());
+        let tables = LootTables::load(&loot_path.to_string_lossy(), &items);
+        fs::remove_dir_all(dir).ok();
+        tables
+    }
+
+    #[test]
+    fn load_drops_invalid_entries_and_duplicate_tables() {
+        let tables = tables();
+        assert_eq!(tables.tables.len(), 3);
+        assert_eq!(tables.tables["pelts"].rolls, 3);
+
+        let broken = &tables.tables["broken"];
+        assert!(broken.entries.is_empty());
+        assert_eq!((broken.gold_min, broken.gold_max), (0, 0));
+        assert!(tables.roll("broken").unwrap().is_empty());
+    }
+
+    #[test]
+    fn roll_stacks_items_from_every_roll() {
+        let tables = tables();
+        assert!(tables.roll("missing").is_none());
+
+        for _ in 0..20 {
+            let drop = tables.roll("pelts").unwrap();
+            assert_eq!(drop.items.len(), 1);
+            assert_eq!((drop.items[0].template_id, drop.items[0].quantity), (1, 6));
+            assert!((4..=9).contains(&drop.gold));
+        }
+    }
+
+    #[test]
+    fn roll_follows_weights() {
+        let tables = tables();
+        let rolls = 4000;
+        let fangs = (0..rolls)
+            .filter(|_| !tables.roll("mostly_fangs").unwrap().items.is_empty())
+            .count();
+        // Ожидается 90%, допуск много больше статистического разброса
+        let share = fangs as f64 / rolls as f64;
+        assert!((0.85..0.95).contains(&share), "fang share {}", share);
+    }
+}
@@ -0,0 +1,359 @@
+use crate::config::MailConfig;
+use crate::protocol::{ItemAmount, MailSummary};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+use uuid::Uuid;
+use super::storage::{self, Collection, Storage};
+
+pub type MailId = Uuid;
+
+const MAX_SUBJECT_LENGTH: usize = 64;
+const MAX_BODY_LENGTH: usize = 1000;
+
+// Как часто проверять истекшие письма
+pub const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailItem {
+    pub template_id: u32,
+    pub quantity: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mail {
+    pub id: MailId,
+    pub sender_id: Option<Uuid>, // None - системное письмо, не возвращается
+    pub sender: String,
+    pub recipient_id: Uuid,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub items: Vec<MailItem>,
+    pub gold: u64,
+    pub sent_at: i64,
+    pub expires_at: i64,
+    pub read: bool,
+    pub returned: bool, // возвращенное письмо второй раз не возвращается
+}
+
+impl Mail {
+    pub fn has_attachments(&self) -> bool {
+        !self.items.is_empty() || self.gold > 0
+    }
+
+    pub fn attachments(&self) -> Vec<ItemAmount> {
+        self.items
+            .iter()
+            .map(|item| ItemAmount { template_id: item.template_id, quantity: item.quantity })
+            .collect()
+    }
+
+    pub fn to_summary(&self) -> MailSummary {
+        MailSummary {
+            mail_id: self.id,
+            sender: self.sender.clone(),
+            subject: self.subject.clone(),
+            sent_at: self.sent_at,
+            expires_at: self.expires_at,
+            read: self.read,
+            item_count: self.items.len() as u32,
+            gold: self.gold,
+            returned: self.returned,
+        }
+    }
+}
+
+// Новое письмо до отправки
+#[derive(Debug, Clone)]
+pub struct MailDraft {
+    pub sender_id: Option<Uuid>,
+    pub sender: String,
+    pub recipient_id: Uuid,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub items: Vec<MailItem>,
+    pub gold: u64,
+}
+
+// Почтовые ящики по id аккаунта получателя
+#[derive(Debug)]
+pub struct MailManager {
+    mailboxes: RwLock<HashMap<Uuid, Vec<Mail>>>,
+    config: MailConfig,
+    storage: Arc<dyn Storage>,
+    save_lock: Mutex<()>,
+}
+
+impl MailManager {
+    pub fn load(config: &MailConfig, storage: Arc<dyn Storage>) -> Self {
+        let mut mailboxes: HashMap<Uuid, Vec<Mail>> = HashMap::new();
+        let mails = storage::load::<Mail>(storage.as_ref(), Collection::Mail);
+        let count = mails.len();
+        for mail in mails {
+            mailboxes.entry(mail.recipient_id).or_default().push(mail);
+        }
+
+        println!("📬 Loaded {} mails", count);
+        Self {
+            mailboxes: RwLock::new(mailboxes),
+            config: config.clone(),
+            storage,
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    // Пишем переданные письма; письма, которых уже нет в ящиках, удаляем из хранилища
+    async fn save(&self, mail_ids: Vec<MailId>) {
+        let _guard = self.save_lock.lock().await;
+        let (mails, removed) = {
+            let mailboxes = self.mailboxes.read().await;
+            let mut mails = Vec::new();
+            let mut removed = Vec::new();
+            for mail_id in mail_ids {
+                match mailboxes.values().flatten().find(|mail| mail.id == mail_id) {
+                    Some(mail) => mails.push(mail.clone()),
+                    None => removed.push(mail_id.to_string()),
+                }
+            }
+            (mails, removed)
+        };
+        if let Err(e) = storage::save(&self.storage, Collection::Mail, &mails).await {
+            eprintln!("{}", e);
+        }
+        if let Err(e) = storage::remove(&self.storage, Collection::Mail, removed).await {
+            eprintln!("{}", e);
+        }
+    }
+
+    // Проверка до того, как у отправителя заберут вложения
+    pub async fn validate(&self, recipient_id: Uuid, subject: &str, body: &str, attachments: usize) -> Result<(), String> {
+        let subject = subject.trim();
+        if subject.is_empty() {
+            return Err("Mail subject is empty".to_string());
+        }
+        if subject.chars().count() > MAX_SUBJECT_LENGTH {
+            return Err(format!("Mail subject is too long (max {} characters)", MAX_SUBJECT_LENGTH));
+        }
+        if body.chars().count() > MAX_BODY_LENGTH {
+            return Err(format!("Mail body is too long (max {} characters)", MAX_BODY_LENGTH));
+        }
+        if attachments > self.config.max_attachments {
+            return Err(format!("Too many attachments (max {})", self.config.max_attachments));
+        }
+
+        let mailboxes = self.mailboxes.read().await;
+        if mailboxes.get(&recipient_id).is_some_and(|mailbox| mailbox.len() >= self.config.max_mailbox_size) {
+            return Err("Recipient's mailbox is full".to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn send(&self, draft: MailDraft) -> Result<Mail, String> {
+        self.validate(draft.recipient_id, &draft.subject, &draft.body, draft.items.len()).await?;
+
+        let now = chrono::Utc::now().timestamp();
+        let mail = Mail {
+            id: Uuid::new_v4(),
+            sender_id: draft.sender_id,
+            sender: draft.sender,
+            recipient_id: draft.recipient_id,
+            recipient: draft.recipient,
+            subject: draft.subject.trim().to_string(),
+            body: draft.body,
+            items: draft.items,
+            gold: draft.gold,
+            sent_at: now,
+            expires_at: now + self.expiry_secs(),
+            read: false,
+            returned: false,
+        };
+
+        {
+            let mut mailboxes = self.mailboxes.write().await;
+            let mailbox = mailboxes.entry(mail.recipient_id).or_default();
+            if mailbox.len() >= self.config.max_mailbox_size {
+                return Err("Recipient's mailbox is full".to_string());
+            }
+            mailbox.push(mail.clone());
+        }
+
+        self.save(vec![mail.id]).await;
+        Ok(mail)
+    }
+
+    // Письмо не ушло, а вложения уже не вернуть в инвентарь - отправляем их обратно письмом.
+    // Кладется даже в переполненный ящик, иначе вложения пропадут
+    pub async fn return_undelivered(&self, draft: MailDraft) -> Option<Mail> {
+        let sender_id = draft.sender_id?;
+        let now = chrono::Utc::now().timestamp();
+        let mail = Mail {
+            id: Uuid::new_v4(),
+            sender_id: Some(draft.recipient_id),
+            sender: draft.recipient,
+            recipient_id: sender_id,
+            recipient: draft.sender,
+            subject: format!("Returned: {}", draft.subject.trim()),
+            body: draft.body,
+            items: draft.items,
+            gold: draft.gold,
+            sent_at: now,
+            expires_at: now + self.expiry_secs(),
+            read: false,
+            returned: true,
+        };
+
+        {
+            let mut mailboxes = self.mailboxes.write().await;
+            mailboxes.entry(sender_id).or_default().push(mail.clone());
+        }
+        self.save(vec![mail.id]).await;
+        Some(mail)
+    }
+
+    fn expiry_secs(&self) -> i64 {
+        self.config.expiry_days as i64 * 86400
+    }
+
+    // Письма получателя, новые сверху
+    pub async fn list(&self, recipient_id: Uuid) -> Vec<Mail> {
+        let mailboxes = self.mailboxes.read().await;
+        let mut mails = mailboxes.get(&recipient_id).cloned().unwrap_or_default();
+        mails.sort_by_key(|mail| std::cmp::Reverse(mail.sent_at));
+        mails
+    }
+
+    pub async fn unread_count(&self, recipient_id: Uuid) -> usize {
+        let mailboxes = self.mailboxes.read().await;
+        mailboxes
+            .get(&recipient_id)
+            .map(|mailbox| mailbox.iter().filter(|mail| !mail.read).count())
+            .unwrap_or(0)
+    }
+
+    pub async fn read(&self, recipient_id: Uuid, mail_id: MailId) -> Result<Mail, String> {
+        let (mail, changed) = {
+            let mut mailboxes = self.mailboxes.write().await;
+            let mail = find_mail(&mut mailboxes, recipient_id, mail_id)?;
+            let changed = !mail.read;
+            mail.read = true;
+            (mail.clone(), changed)
+        };
+
+        if changed {
+            self.save(vec![mail_id]).await;
+        }
+        Ok(mail)
+    }
+
+    // Забираем вложения из письма. Если положить их игроку не удалось,
+    // их нужно вернуть через restore_attachments
+    pub async fn take_attachments(&self, recipient_id: Uuid, mail_id: MailId) -> Result<(Vec<MailItem>, u64), String> {
+        let mut mailboxes = self.mailboxes.write().await;
+        let mail = find_mail(&mut mailboxes, recipient_id, mail_id)?;
+        if !mail.has_attachments() {
+            return Err("This mail has no attachments".to_string());
+        }
+        mail.read = true;
+        Ok((std::mem::take(&mut mail.items), std::mem::take(&mut mail.gold)))
+    }
+
+    pub async fn restore_attachments(&self, recipient_id: Uuid, mail_id: MailId, items: Vec<MailItem>, gold: u64) {
+        let mut mailboxes = self.mailboxes.write().await;
+        if let Ok(mail) = find_mail(&mut mailboxes, recipient_id, mail_id) {
+            mail.items = items;
+            mail.gold = gold;
+        }
+    }
+
+    // Сохраняем после успешной выдачи вложений
+    pub async fn commit(&self, mail_id: MailId) {
+        self.save(vec![mail_id]).await;
+    }
+
+    pub async fn delete(&self, recipient_id: Uuid, mail_id: MailId) -> Result<(), String> {
+        {
+            let mut mailboxes = self.mailboxes.write().await;
+            let mailbox = mailboxes.get_mut(&recipient_id).ok_or_else(|| "Mail not found".to_string())?;
+            let index = mailbox
+                .iter()
+                .position(|mail| mail.id == mail_id)
+                .ok_or_else(|| "Mail not found".to_string())?;
+            if mailbox[index].has_attachments() {
+                return Err("Take the attachments before deleting this mail".to_string());
+            }
+            mailbox.remove(index);
+        }
+
+        self.save(vec![mail_id]).await;
+        Ok(())
+    }
+
+    // Истекшие письма удаляем; невостребованные вложения возвращаем отправителю.
+    // Возвращает письма, пришедшие обратно, чтобы уведомить отправителей
+    pub async fn expire(&self) -> Vec<Mail> {
+        let now = chrono::Utc::now().timestamp();
+        let expiry = self.expiry_secs();
+        let mut returned = Vec::new();
+        let mut removed = Vec::new();
+
+        {
+            let mut mailboxes = self.mailboxes.write().await;
+            for mailbox in mailboxes.values_mut() {
+                mailbox.retain(|mail| {
+                    if mail.expires_at > now {
+                        return true;
+                    }
+                    removed.push(mail.id);
+                    if let Some(sender_id) = mail.sender_id
+                        && mail.has_attachments()
+                        && !mail.returned
+                    {
+                        returned.push(Mail {
+                            id: Uuid::new_v4(),
+                            sender_id: Some(mail.recipient_id),
+                            sender: mail.recipient.clone(),
+                            recipient_id: sender_id,
+                            recipient: mail.sender.clone(),
+                            subject: format!("Returned: {}", mail.subject),
+                            body: mail.body.clone(),
+                            items: mail.items.clone(),
+                            gold: mail.gold,
+                            sent_at: now,
+                            expires_at: now + expiry,
+                            read: false,
+                            returned: true,
+                        });
+                    }
+                    false
+                });
+            }
+            mailboxes.retain(|_, mailbox| !mailbox.is_empty());
+
+            // Возврат кладется даже в переполненный ящик, иначе вложения пропадут
+            for mail in &returned {
+                mailboxes.entry(mail.recipient_id).or_default().push(mail.clone());
+            }
+        }
+
+        if !removed.is_empty() {
+            println!("[{}] 📭 {} mails expired, {} returned to sender",
+                chrono::Local::now().format("%H:%M:%S"),
+                removed.len(), returned.len()
+            );
+            removed.extend(returned.iter().map(|mail| mail.id));
+            self.save(removed).await;
+        }
+        returned
+    }
+}
+
+fn find_mail(mailboxes: &mut HashMap<Uuid, Vec<Mail>>, recipient_id: Uuid, mail_id: MailId) -> Result<&mut Mail, String> {
+    mailboxes
+        .get_mut(&recipient_id)
+        .and_then(|mailbox| mailbox.iter_mut().find(|mail| mail.id == mail_id))
+        .ok_or_else(|| "Mail not found".to_string())
+}
@@ -1,7 +1,43 @@
 pub mod session;
 pub mod session_manager;
 pub mod world;
+pub mod zones;
+pub mod instances;
+pub mod navigation;
+pub mod collision;
+pub mod spatial;
+pub mod clock;
+pub mod weather;
+pub mod spawns;
+pub mod loot;
+pub mod stats;
+pub mod items;
+pub mod inventory;
+pub mod equipment;
+pub mod objects;
+pub mod crafting;
+pub mod animation;
+pub mod party;
+pub mod guild;
+pub mod moderation;
+pub mod roles;
+pub mod storage;
+pub mod accounts;
+pub mod bans;
+pub mod audit;
+pub mod mail;
+pub mod characters;
+pub mod world_state;
 
 pub use session::GameSession;
 pub use session_manager::SessionManager;
+pub use party::PartyManager;
+pub use guild::GuildManager;
+pub use moderation::ChatModerator;
+pub use accounts::AccountStore;
+pub use bans::BanList;
+pub use audit::AuditLog;
+pub use mail::MailManager;
+pub use characters::CharacterStore;
+pub use world_state::WorldStateStore;
 pub use world::GameWorld;  // Добавляем экспорт
\ No newline at end of file
@@ -0,0 +1,260 @@
+use crate::config::ChatConfig;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// История чата игрока для ограничения частоты и поиска спама
+#[derive(Debug, Default)]
+struct ChatHistory {
+    sent: VecDeque<Instant>,
+    last_message: String,
+    repeats: u32,
+    muted_until: Option<Instant>,
+}
+
+// Проверка сообщений чата: мут, длина, частота, повторы, фильтр слов.
+// Игроки хранятся по имени в нижнем регистре, чтобы мут переживал перезаход
+#[derive(Debug)]
+pub struct ChatModerator {
+    config: ChatConfig,
+    filtered_words: Vec<Vec<char>>,
+    players: Mutex<HashMap<String, ChatHistory>>,
+}
+
+impl ChatModerator {
+    pub fn new(config: &ChatConfig) -> Self {
+        let filtered_words = config
+            .filtered_words
+            .iter()
+            .map(|word| word.trim())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.chars().map(lower_char).collect())
+            .collect();
+
+        Self {
+            config: config.clone(),
+            filtered_words,
+            players: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Возвращает сообщение с замаскированными словами или причину отказа
+    pub fn check_message(&self, username: &str, message: &str) -> Result<String, String> {
+        let now = Instant::now();
+        let mut players = self.players.lock();
+        let history = players.entry(username.to_lowercase()).or_default();
+        Self::check_mute(history, now)?;
+
+        let message = message.trim();
+        if message.is_empty() {
+            return Err("Message is empty".to_string());
+        }
+        if message.chars().count() > self.config.max_message_length {
+            return Err(format!("Message is too long (max {} characters)", self.config.max_message_length));
+        }
+
+        self.check_rate(history, now)?;
+
+        let normalized = message.to_lowercase();
+        if normalized == history.last_message {
+            history.repeats += 1;
+        } else {
+            history.last_message = normalized;
+            history.repeats = 1;
+        }
+        if self.config.spam_repeat_limit > 0 && history.repeats > self.config.spam_repeat_limit {
+            let duration = Duration::from_secs(self.config.spam_mute_secs);
+            history.muted_until = Some(now + duration);
+            history.repeats = 0;
+            println!("[{}] 🔇 {} muted for spam",
+                chrono::Local::now().format("%H:%M:%S"),
+                username
+            );
+            return Err(format!("You have been muted for {} for spamming", format_duration(duration)));
+        }
+
+        history.sent.push_back(now);
+        Ok(self.mask_filtered(message))
+    }
+
+    // Команды, которые рассылают текст другим игрокам (/roll, /broadcast):
+    // тот же мут и общий с чатом лимит частоты
+    pub fn check_command(&self, username: &str) -> Result<(), String> {
+        let now = Instant::now();
+        let mut players = self.players.lock();
+        let history = players.entry(username.to_lowercase()).or_default();
+        Self::check_mute(history, now)?;
+        self.check_rate(history, now)?;
+        history.sent.push_back(now);
+        Ok(())
+    }
+
+    fn check_mute(history: &mut ChatHistory, now: Instant) -> Result<(), String> {
+        if let Some(until) = history.muted_until {
+            if until > now {
+                return Err(format!("You are muted for {}", format_duration(until - now)));
+            }
+            history.muted_until = None;
+        }
+        Ok(())
+    }
+
+    fn check_rate(&self, history: &mut ChatHistory, now: Instant) -> Result<(), String> {
+        let window = Duration::from_secs(self.config.rate_limit_window_secs);
+        while history.sent.front().is_some_and(|sent| now.duration_since(*sent) > window) {
+            history.sent.pop_front();
+        }
+        if history.sent.len() >= self.config.rate_limit_messages {
+            let wait = history.sent.front().map(|sent| window.saturating_sub(now.duration_since(*sent)));
+            return Err(format!(
+                "You are sending messages too fast, wait {}",
+                format_duration(wait.unwrap_or(window))
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn mute(&self, username: &str, duration: Duration) {
+        let mut players = self.players.lock();
+        players.entry(username.to_lowercase()).or_default().muted_until = Some(Instant::now() + duration);
+    }
+
+    pub fn unmute(&self, username: &str) -> bool {
+        let mut players = self.players.lock();
+        players
+            .get_mut(&username.to_lowercase())
+            .and_then(|history| history.muted_until.take())
+            .is_some_and(|until| until > Instant::now())
+    }
+
+    #[allow(dead_code)]
+    pub fn mute_remaining(&self, username: &str) -> Option<Duration> {
+        let players = self.players.lock();
+        let until = players.get(&username.to_lowercase())?.muted_until?;
+        until.checked_duration_since(Instant::now()).filter(|remaining| !remaining.is_zero())
+    }
+
+    // При выходе забываем историю, но не активный мут
+    pub fn remove_player(&self, username: &str) {
+        let mut players = self.players.lock();
+        let key = username.to_lowercase();
+        let muted = players
+            .get(&key)
+            .and_then(|history| history.muted_until)
+            .is_some_and(|until| until > Instant::now());
+        if !muted {
+            players.remove(&key);
+        }
+    }
+
+    // Заменяем запрещенные слова звездочками, только целые слова без учета регистра
+    fn mask_filtered(&self, message: &str) -> String {
+        let mut chars: Vec<char> = message.chars().collect();
+        let lower: Vec<char> = chars.iter().copied().map(lower_char).collect();
+
+        for word in &self.filtered_words {
+            if word.len() > lower.len() {
+                continue;
+            }
+            for start in 0..=lower.len() - word.len() {
+                let end = start + word.len();
+                let boundary_before = start == 0 || !lower[start - 1].is_alphanumeric();
+                let boundary_after = end == lower.len() || !lower[end].is_alphanumeric();
+                if boundary_before && boundary_after && lower[start..end] == word[..] {
+                    chars[start..end].fill('*');
+                }
+            }
+        }
+
+        chars.into_iter().collect()
+    }
+}
+
+// Посимвольный нижний регистр, чтобы не менялись позиции символов
+fn lower_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs().max(1);
+    if secs >= 3600 {
+        format!("{}h {}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 && secs.is_multiple_of(60) {
+        format!("{}m", secs / 60)
+    } else if secs >= 60 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moderator() -> ChatModerator {
+        ChatModerator::new(&ChatConfig {
+            say_radius: 30.0,
+            yell_radius: 120.0,
+            max_message_length: 20,
+            rate_limit_messages: 3,
+            rate_limit_window_secs: 60,
+            spam_repeat_limit: 2,
+            spam_mute_secs: 60,
+            filtered_words: vec!["darn".to_string(), " ".to_string()],
+        })
+    }
+
+    #[test]
+    fn masks_whole_words_ignoring_case() {
+        let moderator = moderator();
+        assert_eq!(moderator.check_message("alice", "Darn it, DARN!").unwrap(), "**** it, ****!");
+        assert_eq!(moderator.check_message("alice", "darning undarn").unwrap(), "darning undarn");
+    }
+
+    #[test]
+    fn rejects_empty_and_long_messages() {
+        let moderator = moderator();
+        assert_eq!(moderator.check_message("alice", "   ").unwrap_err(), "Message is empty");
+        assert!(moderator.check_message("alice", &"a".repeat(21)).is_err());
+        assert_eq!(moderator.check_message("alice", "  hi  ").unwrap(), "hi");
+    }
+
+    #[test]
+    fn rate_limit_is_per_player_and_shared_with_commands() {
+        let moderator = moderator();
+        moderator.check_message("alice", "one").unwrap();
+        moderator.check_command("alice").unwrap();
+        moderator.check_message("alice", "two").unwrap();
+
+        assert!(moderator.check_message("alice", "three").unwrap_err().starts_with("You are sending messages too fast"));
+        assert!(moderator.check_command("Alice").is_err());
+        assert!(moderator.check_message("bob", "hello").is_ok());
+    }
+
+    #[test]
+    fn repeating_a_message_mutes() {
+        let moderator = moderator();
+        moderator.check_message("alice", "buy gold").unwrap();
+        moderator.check_message("alice", "BUY GOLD").unwrap();
+        assert!(moderator.check_message("alice", "buy gold").unwrap_err().contains("muted"));
+
+        assert!(moderator.mute_remaining("alice").is_some());
+        assert!(moderator.check_command("alice").unwrap_err().starts_with("You are muted"));
+        // Мут переживает перезаход
+        moderator.remove_player("alice");
+        assert!(moderator.mute_remaining("alice").is_some());
+        assert!(moderator.unmute("alice"));
+        assert!(moderator.check_command("alice").is_ok());
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(Duration::from_millis(200)), "1s");
+        assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+        assert_eq!(format_duration(Duration::from_secs(120)), "2m");
+        assert_eq!(format_duration(Duration::from_secs(150)), "2m 30s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h 2m");
+    }
+}
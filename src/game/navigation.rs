@@ -0,0 +1,591 @@
+use crate::config::NavigationConfig;
+use crate::protocol::Vector3;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::sync::Arc;
+
+pub type PathRequestId = u64;
+
+// Клетка сетки: (столбец, строка)
+type Cell = (i32, i32);
+
+// Ключ кэша: зона + стартовая и целевая клетки. В кэше лежат только клетки пути,
+// концы пути у каждого запроса свои и достраиваются заново
+type PathKey = (u32, Cell, Cell);
+
+const STRAIGHT_COST: f32 = 1.0;
+const DIAGONAL_COST: f32 = std::f32::consts::SQRT_2;
+
+#[derive(Debug, Deserialize)]
+struct NavGridFile {
+    grids: Vec<NavGridData>,
+}
+
+// Формат сетки в файле данных: '#' - препятствие, любой другой символ - проходимая клетка
+#[derive(Debug, Deserialize)]
+struct NavGridData {
+    zone_id: u32,
+    origin: [f32; 2],
+    cell_size: f32,
+    rows: Vec<String>,
+}
+
+// Навигационная сетка зоны в плоскости XY (Z - высота, как в UE5)
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    pub zone_id: u32,
+    pub origin_x: f32,
+    pub origin_y: f32,
+    pub cell_size: f32,
+    pub width: i32,
+    pub height: i32,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    fn from_data(data: NavGridData) -> Result<Self, String> {
+        if data.cell_size <= 0.0 {
+            return Err(format!("zone {}: cell_size must be positive", data.zone_id));
+        }
+
+        let width = data.rows.first().map(|row| row.chars().count()).unwrap_or(0);
+        if width == 0 {
+            return Err(format!("zone {}: grid is empty", data.zone_id));
+        }
+
+        let mut blocked = Vec::with_capacity(width * data.rows.len());
+        for (index, row) in data.rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(format!("zone {}: row {} has wrong length", data.zone_id, index));
+            }
+            blocked.extend(row.chars().map(|c| c == '#'));
+        }
+
+        Ok(Self {
+            zone_id: data.zone_id,
+            origin_x: data.origin[0],
+            origin_y: data.origin[1],
+            cell_size: data.cell_size,
+            width: width as i32,
+            height: data.rows.len() as i32,
+            blocked,
+        })
+    }
+
+    fn cell_at(&self, position: &Vector3) -> Cell {
+        (
+            ((position.x - self.origin_x) / self.cell_size).floor() as i32,
+            ((position.y - self.origin_y) / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cell_center(&self, cell: Cell, z: f32) -> Vector3 {
+        Vector3 {
+            x: self.origin_x + (cell.0 as f32 + 0.5) * self.cell_size,
+            y: self.origin_y + (cell.1 as f32 + 0.5) * self.cell_size,
+            z,
+        }
+    }
+
+    fn in_bounds(&self, cell: Cell) -> bool {
+        cell.0 >= 0 && cell.1 >= 0 && cell.0 < self.width && cell.1 < self.height
+    }
+
+    fn is_cell_walkable(&self, cell: Cell) -> bool {
+        self.in_bounds(cell) && !self.blocked[(cell.1 * self.width + cell.0) as usize]
+    }
+
+    #[allow(dead_code)]
+    pub fn is_walkable(&self, position: &Vector3) -> bool {
+        self.is_cell_walkable(self.cell_at(position))
+    }
+
+    // Соседи по 8 направлениям, диагональ запрещена, если срезает угол препятствия
+    fn neighbors(&self, cell: Cell) -> impl Iterator<Item = (Cell, f32)> + '_ {
+        const DIRECTIONS: [(i32, i32); 8] = [
+            (1, 0), (-1, 0), (0, 1), (0, -1),
+            (1, 1), (1, -1), (-1, 1), (-1, -1),
+        ];
+
+        DIRECTIONS.iter().filter_map(move |&(dx, dy)| {
+            let next = (cell.0 + dx, cell.1 + dy);
+            if !self.is_cell_walkable(next) {
+                return None;
+            }
+            if dx != 0 && dy != 0 {
+                if !self.is_cell_walkable((cell.0 + dx, cell.1)) || !self.is_cell_walkable((cell.0, cell.1 + dy)) {
+                    return None;
+                }
+                Some((next, DIAGONAL_COST))
+            } else {
+                Some((next, STRAIGHT_COST))
+            }
+        })
+    }
+}
+
+// Октильная эвристика для движения в 8 направлениях
+fn heuristic(from: Cell, to: Cell) -> f32 {
+    let dx = (from.0 - to.0).abs() as f32;
+    let dy = (from.1 - to.1).abs() as f32;
+    STRAIGHT_COST * (dx + dy) + (DIAGONAL_COST - 2.0 * STRAIGHT_COST) * dx.min(dy)
+}
+
+pub fn load_nav_grids(path: &str) -> HashMap<u32, NavGrid> {
+    let mut grids = HashMap::new();
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => {
+            println!("Navigation data not found at {}, pathfinding will use straight lines", path);
+            return grids;
+        }
+    };
+
+    let file: NavGridFile = match toml::from_str(&content) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Failed to parse navigation data {}: {}", path, e);
+            return grids;
+        }
+    };
+
+    for data in file.grids {
+        match NavGrid::from_data(data) {
+            Ok(grid) => {
+                println!("🧭 Loaded nav grid for zone {} ({}x{})", grid.zone_id, grid.width, grid.height);
+                grids.insert(grid.zone_id, grid);
+            }
+            Err(e) => eprintln!("Invalid nav grid in {}: {}", path, e),
+        }
+    }
+
+    grids
+}
+
+// Запрашивать пути будут NPC, которых в мире пока нет
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub enum PathResult {
+    Found(Arc<Vec<Vector3>>),
+    NotFound,
+}
+
+#[derive(Debug)]
+struct PathRequest {
+    id: PathRequestId,
+    zone_id: u32,
+    start: Vector3,
+    goal: Vector3,
+}
+
+#[derive(Debug, PartialEq)]
+struct OpenNode {
+    f_score: f32,
+    cell: Cell,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    // BinaryHeap - max-heap, поэтому сравнение перевернуто
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f_score.total_cmp(&self.f_score).then_with(|| self.cell.cmp(&other.cell))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Поиск, который может продолжаться несколько тиков
+#[derive(Debug)]
+struct ActiveSearch {
+    request: PathRequest,
+    key: PathKey,
+    open: BinaryHeap<OpenNode>,
+    g_score: HashMap<Cell, f32>,
+    came_from: HashMap<Cell, Cell>,
+    closed: HashSet<Cell>,
+    expanded: usize,
+}
+
+enum SearchStep {
+    Done(Option<Vec<Cell>>),
+    OutOfBudget,
+}
+
+impl ActiveSearch {
+    fn new(request: PathRequest, key: PathKey) -> Self {
+        let (_, start, goal) = key;
+        let mut open = BinaryHeap::new();
+        open.push(OpenNode { f_score: heuristic(start, goal), cell: start });
+
+        let mut g_score = HashMap::new();
+        g_score.insert(start, 0.0);
+
+        Self {
+            request,
+            key,
+            open,
+            g_score,
+            came_from: HashMap::new(),
+            closed: HashSet::new(),
+            expanded: 0,
+        }
+    }
+
+    fn step(&mut self, grid: &NavGrid, budget: &mut usize, max_nodes: usize) -> SearchStep {
+        let (_, _, goal) = self.key;
+
+        while *budget > 0 {
+            let Some(OpenNode { cell, .. }) = self.open.pop() else {
+                return SearchStep::Done(None);
+            };
+
+            if cell == goal {
+                return SearchStep::Done(Some(self.reconstruct(goal)));
+            }
+            if !self.closed.insert(cell) {
+                continue;
+            }
+
+            *budget -= 1;
+            self.expanded += 1;
+            if self.expanded > max_nodes {
+                return SearchStep::Done(None);
+            }
+
+            let current_g = self.g_score[&cell];
+            for (next, cost) in grid.neighbors(cell) {
+                let tentative_g = current_g + cost;
+                if self.g_score.get(&next).is_none_or(|&g| tentative_g < g) {
+                    self.came_from.insert(next, cell);
+                    self.g_score.insert(next, tentative_g);
+                    self.open.push(OpenNode { f_score: tentative_g + heuristic(next, goal), cell: next });
+                }
+            }
+        }
+
+        SearchStep::OutOfBudget
+    }
+
+    fn reconstruct(&self, goal: Cell) -> Vec<Cell> {
+        let mut cells = vec![goal];
+        let mut current = goal;
+        while let Some(&previous) = self.came_from.get(&current) {
+            cells.push(previous);
+            current = previous;
+        }
+        cells.reverse();
+        cells
+    }
+}
+
+#[derive(Debug, Default)]
+struct PathfinderState {
+    next_request_id: PathRequestId,
+    queue: VecDeque<PathRequest>,
+    active: Option<ActiveSearch>,
+    results: HashMap<PathRequestId, PathResult>,
+    cache: HashMap<PathKey, Arc<Vec<Cell>>>,
+    cache_order: VecDeque<PathKey>,
+}
+
+// Сервис поиска пути (A*) для поведения NPC.
+// Запросы ставятся в очередь и обрабатываются в игровом цикле с ограничением
+// на число раскрытых узлов за тик, готовые пути кэшируются.
+#[derive(Debug)]
+pub struct Pathfinder {
+    grids: HashMap<u32, NavGrid>,
+    max_nodes_per_tick: usize,
+    max_nodes_per_search: usize,
+    cache_size: usize,
+    state: Mutex<PathfinderState>,
+}
+
+impl Pathfinder {
+    pub fn new(config: &NavigationConfig) -> Self {
+        Self {
+            grids: load_nav_grids(&config.grids_file),
+            max_nodes_per_tick: config.max_nodes_per_tick,
+            max_nodes_per_search: config.max_nodes_per_search,
+            cache_size: config.cache_size,
+            state: Mutex::new(PathfinderState::default()),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn grid(&self, zone_id: u32) -> Option<&NavGrid> {
+        self.grids.get(&zone_id)
+    }
+
+    // Ставим запрос в очередь; результат забирается через take_result
+    #[allow(dead_code)]
+    pub fn request_path(&self, zone_id: u32, start: &Vector3, goal: &Vector3) -> PathRequestId {
+        let mut state = self.state.lock();
+        let id = state.next_request_id;
+        state.next_request_id += 1;
+
+        // Зона без сетки считается открытой местностью
+        let Some(grid) = self.grids.get(&zone_id) else {
+            state.results.insert(id, PathResult::Found(Arc::new(vec![goal.clone()])));
+            return id;
+        };
+
+        let start_cell = grid.cell_at(start);
+        let goal_cell = grid.cell_at(goal);
+        if !grid.is_cell_walkable(start_cell) || !grid.is_cell_walkable(goal_cell) {
+            state.results.insert(id, PathResult::NotFound);
+            return id;
+        }
+
+        if let Some(cells) = state.cache.get(&(zone_id, start_cell, goal_cell)).cloned() {
+            let path = Self::build_path(grid, &cells, start, goal);
+            state.results.insert(id, PathResult::Found(Arc::new(path)));
+            return id;
+        }
+
+        state.queue.push_back(PathRequest {
+            id,
+            zone_id,
+            start: start.clone(),
+            goal: goal.clone(),
+        });
+        id
+    }
+
+    // None - путь еще считается
+    #[allow(dead_code)]
+    pub fn take_result(&self, id: PathRequestId) -> Option<PathResult> {
+        self.state.lock().results.remove(&id)
+    }
+
+    #[allow(dead_code)]
+    pub fn cancel(&self, id: PathRequestId) {
+        let mut state = self.state.lock();
+        state.queue.retain(|request| request.id != id);
+        if state.active.as_ref().is_some_and(|search| search.request.id == id) {
+            state.active = None;
+        }
+        state.results.remove(&id);
+    }
+
+    // Вызывается из игрового цикла, возвращает число раскрытых узлов
+    pub fn process_tick(&self) -> usize {
+        let mut state = self.state.lock();
+        let mut budget = self.max_nodes_per_tick;
+
+        while budget > 0 {
+            let mut search = match state.active.take() {
+                Some(search) => search,
+                None => match state.queue.pop_front() {
+                    Some(request) => match self.begin_search(&mut state, request) {
+                        Some(search) => search,
+                        None => continue,
+                    },
+                    None => break,
+                },
+            };
+
+            let grid = &self.grids[&search.request.zone_id];
+            match search.step(grid, &mut budget, self.max_nodes_per_search) {
+                SearchStep::Done(Some(cells)) => {
+                    let path = Self::build_path(grid, &cells, &search.request.start, &search.request.goal);
+                    self.cache_path(&mut state, search.key, Arc::new(cells));
+                    state.results.insert(search.request.id, PathResult::Found(Arc::new(path)));
+                }
+                SearchStep::Done(None) => {
+                    state.results.insert(search.request.id, PathResult::NotFound);
+                }
+                SearchStep::OutOfBudget => {
+                    state.active = Some(search);
+                }
+            }
+        }
+
+        self.max_nodes_per_tick - budget
+    }
+
+    // Пока запрос ждал в очереди, такой же путь мог попасть в кэш
+    fn begin_search(&self, state: &mut PathfinderState, request: PathRequest) -> Option<ActiveSearch> {
+        let grid = &self.grids[&request.zone_id];
+        let key = (request.zone_id, grid.cell_at(&request.start), grid.cell_at(&request.goal));
+
+        if let Some(cells) = state.cache.get(&key).cloned() {
+            let path = Self::build_path(grid, &cells, &request.start, &request.goal);
+            state.results.insert(request.id, PathResult::Found(Arc::new(path)));
+            return None;
+        }
+
+        Some(ActiveSearch::new(request, key))
+    }
+
+    fn cache_path(&self, state: &mut PathfinderState, key: PathKey, cells: Arc<Vec<Cell>>) {
+        if self.cache_size == 0 {
+            return;
+        }
+
+        if state.cache.insert(key, cells).is_none() {
+            state.cache_order.push_back(key);
+        }
+
+        while state.cache_order.len() > self.cache_size {
+            if let Some(oldest) = state.cache_order.pop_front() {
+                state.cache.remove(&oldest);
+            }
+        }
+    }
+
+    // Путевые точки в центрах клеток, промежуточные точки на прямых участках выбрасываем
+    fn build_path(grid: &NavGrid, cells: &[Cell], start: &Vector3, goal: &Vector3) -> Vec<Vector3> {
+        let mut waypoints: Vec<Vector3> = cells
+            .windows(3)
+            .filter(|w| (w[1].0 - w[0].0, w[1].1 - w[0].1) != (w[2].0 - w[1].0, w[2].1 - w[1].1))
+            .map(|w| grid.cell_center(w[1], start.z))
+            .collect();
+
+        waypoints.push(goal.clone());
+        waypoints
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32) -> Vector3 {
+        Vector3 { x, y, z: 0.0 }
+    }
+
+    fn same(a: &Vector3, b: &Vector3) -> bool {
+        (a.x, a.y, a.z) == (b.x, b.y, b.z)
+    }
+
+    fn pathfinder(rows: &[&str], max_nodes_per_tick: usize) -> Pathfinder {
+        let grid = NavGrid::from_data(NavGridData {
+            zone_id: 1,
+            origin: [0.0, 0.0],
+            cell_size: 1.0,
+            rows: rows.iter().map(|row| row.to_string()).collect(),
+        })
+        .unwrap();
+        Pathfinder {
+            grids: HashMap::from([(1, grid)]),
+            max_nodes_per_tick,
+            max_nodes_per_search: 1000,
+            cache_size: 8,
+            state: Mutex::new(PathfinderState::default()),
+        }
+    }
+
+    fn run(pathfinder: &Pathfinder, id: PathRequestId) -> PathResult {
+        for _ in 0..100 {
+            if let Some(result) = pathfinder.take_result(id) {
+                return result;
+            }
+            pathfinder.process_tick();
+        }
+        panic!("path {} was not finished", id);
+    }
+
+    fn found(result: PathResult) -> Vec<Vector3> {
+        match result {
+            PathResult::Found(path) => path.to_vec(),
+            PathResult::NotFound => panic!("path not found"),
+        }
+    }
+
+    const WALL: [&str; 5] = [
+        ".......",
+        ".......",
+        "#####..",
+        ".......",
+        ".......",
+    ];
+
+    #[test]
+    fn path_goes_around_wall() {
+        let pathfinder = pathfinder(&WALL, 1000);
+        let goal = point(0.5, 4.5);
+        let path = found(run(&pathfinder, pathfinder.request_path(1, &point(0.5, 0.5), &goal)));
+
+        assert!(same(path.last().unwrap(), &goal));
+        let grid = pathfinder.grid(1).unwrap();
+        assert!(path.iter().all(|waypoint| grid.is_walkable(waypoint)));
+        // Обход через проход справа от стены
+        assert!(path.iter().any(|waypoint| waypoint.x >= 5.0));
+    }
+
+    #[test]
+    fn unreachable_and_blocked_goals_are_not_found() {
+        let pathfinder = pathfinder(&["..#..", "..#..", "..#.."], 1000);
+        let start = point(0.5, 0.5);
+        assert!(matches!(run(&pathfinder, pathfinder.request_path(1, &start, &point(4.5, 0.5))), PathResult::NotFound));
+        assert!(matches!(run(&pathfinder, pathfinder.request_path(1, &start, &point(2.5, 0.5))), PathResult::NotFound));
+        assert!(matches!(run(&pathfinder, pathfinder.request_path(1, &start, &point(-3.0, 0.5))), PathResult::NotFound));
+    }
+
+    #[test]
+    fn diagonal_does_not_cut_corners() {
+        let grid = pathfinder(&["..", "#."], 1000).grids.remove(&1).unwrap();
+        let neighbors: Vec<Cell> = grid.neighbors((0, 0)).map(|(cell, _)| cell).collect();
+        assert!(neighbors.contains(&(1, 0)));
+        assert!(!neighbors.contains(&(1, 1)));
+    }
+
+    #[test]
+    fn search_is_spread_over_ticks() {
+        let pathfinder = pathfinder(&WALL, 2);
+        let id = pathfinder.request_path(1, &point(0.5, 0.5), &point(0.5, 4.5));
+        assert_eq!(pathfinder.process_tick(), 2);
+        assert!(pathfinder.take_result(id).is_none());
+        found(run(&pathfinder, id));
+    }
+
+    #[test]
+    fn zone_without_grid_walks_straight() {
+        let pathfinder = pathfinder(&WALL, 1000);
+        let goal = point(10.0, 10.0);
+        let path = found(run(&pathfinder, pathfinder.request_path(7, &point(0.0, 0.0), &goal)));
+        assert_eq!(path.len(), 1);
+        assert!(same(&path[0], &goal));
+    }
+
+    #[test]
+    fn cached_path_keeps_each_request_endpoints() {
+        let pathfinder = pathfinder(&WALL, 1000);
+        let first_goal = point(0.2, 4.2);
+        let first = found(run(&pathfinder, pathfinder.request_path(1, &point(0.5, 0.5), &first_goal)));
+        assert_eq!(pathfinder.state.lock().cache.len(), 1);
+
+        // Те же клетки, но другие точки внутри них: ответ сразу из кэша
+        let second_start = Vector3 { x: 0.8, y: 0.8, z: 3.0 };
+        let second_goal = point(0.9, 4.9);
+        let id = pathfinder.request_path(1, &second_start, &second_goal);
+        let second = found(pathfinder.take_result(id).expect("cache hit"));
+
+        assert!(same(first.last().unwrap(), &first_goal));
+        assert!(same(second.last().unwrap(), &second_goal));
+        assert_eq!(first.len(), second.len());
+        assert!(second[..second.len() - 1].iter().all(|waypoint| waypoint.z == 3.0));
+    }
+
+    #[test]
+    fn cache_evicts_oldest_paths() {
+        let mut pathfinder = pathfinder(&WALL, 1000);
+        pathfinder.cache_size = 2;
+        for x in 0..3 {
+            run(&pathfinder, pathfinder.request_path(1, &point(x as f32 + 0.5, 0.5), &point(6.5, 4.5)));
+        }
+        let state = pathfinder.state.lock();
+        assert_eq!(state.cache.len(), 2);
+        assert!(!state.cache.contains_key(&(1, (0, 0), (6, 4))));
+    }
+}
@@ -0,0 +1,278 @@
+use crate::protocol::{ObjectUpdate, Transform, Vector3};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+use super::items::ItemGrant;
+use super::session::PlayerId;
+
+const DEFAULT_INTERACTION_RANGE: f32 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoorState {
+    Closed,
+    Open,
+    Locked,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChestState {
+    Closed,
+    Looted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeverState {
+    Off,
+    On,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortalState {
+    Active,
+    Inactive,
+}
+
+// Тип объекта вместе с его состоянием
+#[derive(Debug, Clone)]
+pub enum ObjectKind {
+    Door {
+        state: DoorState,
+        key_item: Option<u32>,
+    },
+    Chest {
+        state: ChestState,
+        contents: Vec<ItemGrant>,
+        respawn: Duration,
+        respawn_at: Option<Instant>,
+    },
+    Lever {
+        state: LeverState,
+        targets: Vec<Uuid>,
+    },
+    Portal {
+        state: PortalState,
+        destination_zone: u32,
+        destination: Vector3,
+    },
+    // Станция крафта, состояния не имеет
+    Station {
+        station_type: String,
+    },
+    // Камень привязки: здесь игрок запоминает точку возвращения
+    BindStone,
+    // Выпавшая добыча: до free_at поднять могут только owners, в despawn_at исчезает
+    Loot {
+        items: Vec<ItemGrant>,
+        gold: u64,
+        owners: Vec<PlayerId>,
+        free_at: Instant,
+        despawn_at: Instant,
+    },
+}
+
+impl ObjectKind {
+    pub fn state_name(&self) -> &'static str {
+        match self {
+            ObjectKind::Door { state: DoorState::Closed, .. } => "closed",
+            ObjectKind::Door { state: DoorState::Open, .. } => "open",
+            ObjectKind::Door { state: DoorState::Locked, .. } => "locked",
+            ObjectKind::Chest { state: ChestState::Closed, .. } => "closed",
+            ObjectKind::Chest { state: ChestState::Looted, .. } => "looted",
+            ObjectKind::Lever { state: LeverState::Off, .. } => "off",
+            ObjectKind::Lever { state: LeverState::On, .. } => "on",
+            ObjectKind::Portal { state: PortalState::Active, .. } => "active",
+            ObjectKind::Portal { state: PortalState::Inactive, .. } => "inactive",
+            ObjectKind::Station { .. } => "ready",
+            ObjectKind::BindStone => "ready",
+            ObjectKind::Loot { .. } => "loot",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WorldObject {
+    pub id: Uuid,
+    pub name: String,
+    pub zone_id: u32,
+    pub transform: Transform,
+    pub interaction_range: f32,
+    pub kind: ObjectKind,
+}
+
+impl WorldObject {
+    // Объект, появившийся во время игры (добыча): в файле его нет
+    pub fn dropped(name: &str, zone_id: u32, position: Vector3, kind: ObjectKind) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            zone_id,
+            transform: Transform { position, ..Transform::default() },
+            interaction_range: DEFAULT_INTERACTION_RANGE,
+            kind,
+        }
+    }
+
+    pub fn to_update(&self) -> ObjectUpdate {
+        ObjectUpdate {
+            object_id: self.id,
+            transform: self.transform.clone(),
+            state: self.kind.state_name().to_string(),
+        }
+    }
+
+    pub fn in_range(&self, zone_id: u32, position: &Vector3) -> bool {
+        if zone_id != self.zone_id {
+            return false;
+        }
+        let p = &self.transform.position;
+        let (dx, dy, dz) = (p.x - position.x, p.y - position.y, p.z - position.z);
+        dx * dx + dy * dy + dz * dz <= self.interaction_range * self.interaction_range
+    }
+
+    // Переключение рычагом: дверь открывается/закрывается, портал включается/выключается
+    pub fn toggle_by_lever(&mut self) -> bool {
+        match &mut self.kind {
+            ObjectKind::Door { state, .. } => {
+                *state = match state {
+                    DoorState::Open => DoorState::Closed,
+                    DoorState::Closed | DoorState::Locked => DoorState::Open,
+                };
+                true
+            }
+            ObjectKind::Portal { state, .. } => {
+                *state = match state {
+                    PortalState::Active => PortalState::Inactive,
+                    PortalState::Inactive => PortalState::Active,
+                };
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ObjectKindData {
+    Door {
+        #[serde(default)]
+        locked: bool,
+        key_item: Option<u32>,
+    },
+    Chest {
+        contents: Vec<ItemGrant>,
+        respawn_secs: u64,
+    },
+    Lever {
+        #[serde(default)]
+        targets: Vec<String>,
+    },
+    Portal {
+        zone_id: u32,
+        position: Vector3,
+        #[serde(default)]
+        inactive: bool,
+    },
+    Station {
+        station: String,
+    },
+    BindStone,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObjectData {
+    key: String,
+    name: String,
+    zone_id: u32,
+    position: Vector3,
+    interaction_range: Option<f32>,
+    kind: ObjectKindData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObjectDataFile {
+    #[serde(default)]
+    objects: Vec<ObjectData>,
+}
+
+pub fn load_world_objects(path: &str) -> HashMap<Uuid, WorldObject> {
+    let mut objects = HashMap::new();
+
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) => {
+            println!("World object data not found at {}, no objects spawned", path);
+            return objects;
+        }
+    };
+
+    let file: ObjectDataFile = match toml::from_str(&content) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Failed to parse world object data {}: {}", path, e);
+            return objects;
+        }
+    };
+
+    // Рычаги ссылаются на объекты по key, поэтому id раздаем заранее
+    let ids: HashMap<String, Uuid> = file.objects.iter().map(|data| (data.key.clone(), Uuid::new_v4())).collect();
+
+    for data in file.objects {
+        let kind = match data.kind {
+            ObjectKindData::Door { locked, key_item } => ObjectKind::Door {
+                state: if locked { DoorState::Locked } else { DoorState::Closed },
+                key_item,
+            },
+            ObjectKindData::Chest { contents, respawn_secs } => ObjectKind::Chest {
+                state: ChestState::Closed,
+                contents,
+                respawn: Duration::from_secs(respawn_secs),
+                respawn_at: None,
+            },
+            ObjectKindData::Lever { targets } => ObjectKind::Lever {
+                state: LeverState::Off,
+                targets: targets
+                    .iter()
+                    .filter_map(|key| {
+                        let id = ids.get(key).copied();
+                        if id.is_none() {
+                            eprintln!("Lever {} targets unknown object {}", data.key, key);
+                        }
+                        id
+                    })
+                    .collect(),
+            },
+            ObjectKindData::Portal { zone_id, position, inactive } => ObjectKind::Portal {
+                state: if inactive { PortalState::Inactive } else { PortalState::Active },
+                destination_zone: zone_id,
+                destination: position,
+            },
+            ObjectKindData::Station { station } => ObjectKind::Station { station_type: station },
+            ObjectKindData::BindStone => ObjectKind::BindStone,
+        };
+
+        let id = ids[&data.key];
+        if objects.contains_key(&id) {
+            eprintln!("Duplicate object key {} in {}, skipping", data.key, path);
+            continue;
+        }
+        let transform = Transform {
+            position: data.position,
+            ..Transform::default()
+        };
+
+        objects.insert(id, WorldObject {
+            id,
+            name: data.name,
+            zone_id: data.zone_id,
+            transform,
+            interaction_range: data.interaction_range.unwrap_or(DEFAULT_INTERACTION_RANGE),
+            kind,
+        });
+    }
+
+    println!("🚪 Loaded {} world objects", objects.len());
+    objects
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+use super::session::PlayerId;
+
+pub type PartyId = Uuid;
+
+pub const MAX_PARTY_SIZE: usize = 5;
+pub const INVITE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Как часто рассылать участникам здоровье и позиции друг друга
+pub const MEMBER_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone)]
+pub struct Party {
+    pub id: PartyId,
+    pub leader: PlayerId,
+    pub members: Vec<PlayerId>, // в порядке вступления
+}
+
+#[derive(Debug, Clone)]
+struct PartyInvite {
+    inviter: PlayerId,
+    expires_at: Instant,
+}
+
+// Результат выхода/исключения: кто покинул группу и что от нее осталось
+#[derive(Debug)]
+pub struct PartyChange {
+    pub removed: Vec<PlayerId>,
+    pub party: Option<Party>, // None - группа распущена
+}
+
+#[derive(Debug, Default)]
+struct PartyState {
+    parties: HashMap<PartyId, Party>,
+    membership: HashMap<PlayerId, PartyId>,
+    invites: HashMap<PlayerId, PartyInvite>, // по приглашенному, одно приглашение за раз
+}
+
+impl PartyState {
+    fn party_of(&self, player_id: &PlayerId) -> Option<&Party> {
+        self.membership.get(player_id).and_then(|party_id| self.parties.get(party_id))
+    }
+
+    // Убираем участника; если в группе остался один человек - распускаем ее
+    fn remove_member(&mut self, party_id: PartyId, player_id: PlayerId) -> PartyChange {
+        let mut removed = vec![player_id];
+        self.membership.remove(&player_id);
+
+        let Some(party) = self.parties.get_mut(&party_id) else {
+            return PartyChange { removed, party: None };
+        };
+        party.members.retain(|id| *id != player_id);
+
+        if party.members.len() < 2 {
+            for member in &party.members {
+                self.membership.remove(member);
+            }
+            removed.append(&mut party.members);
+            self.parties.remove(&party_id);
+            return PartyChange { removed, party: None };
+        }
+
+        if party.leader == player_id {
+            party.leader = party.members[0];
+        }
+        PartyChange { removed, party: Some(party.clone()) }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PartyManager {
+    state: RwLock<PartyState>,
+}
+
+impl PartyManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn get_party(&self, player_id: &PlayerId) -> Option<Party> {
+        let state = self.state.read().await;
+        state.party_of(player_id).cloned()
+    }
+
+    pub async fn parties(&self) -> Vec<Party> {
+        let state = self.state.read().await;
+        state.parties.values().cloned().collect()
+    }
+
+    pub async fn invite(&self, inviter: PlayerId, invitee: PlayerId) -> Result<(), String> {
+        if inviter == invitee {
+            return Err("You can't invite yourself".to_string());
+        }
+
+        let mut state = self.state.write().await;
+        if let Some(party) = state.party_of(&inviter) {
+            if party.leader != inviter {
+                return Err("Only the party leader can invite".to_string());
+            }
+            if party.members.len() >= MAX_PARTY_SIZE {
+                return Err("Your party is full".to_string());
+            }
+        }
+        if state.membership.contains_key(&invitee) {
+            return Err("Player is already in a party".to_string());
+        }
+
+        let now = Instant::now();
+        if state.invites.get(&invitee).is_some_and(|invite| invite.expires_at > now) {
+            return Err("Player already has a pending invite".to_string());
+        }
+
+        state.invites.insert(invitee, PartyInvite {
+            inviter,
+            expires_at: now + INVITE_TIMEOUT,
+        });
+        Ok(())
+    }
+
+    // Принимаем приглашение; если у пригласившего еще нет группы - создаем ее
+    pub async fn accept(&self, invitee: PlayerId) -> Result<Party, String> {
+        let mut state = self.state.write().await;
+        let invite = state
+            .invites
+            .remove(&invitee)
+            .ok_or_else(|| "You have no pending party invite".to_string())?;
+        if invite.expires_at <= Instant::now() {
+            return Err("Party invite has expired".to_string());
+        }
+        if state.membership.contains_key(&invitee) {
+            return Err("You are already in a party".to_string());
+        }
+        // Пригласивший мог с тех пор вступить в чужую группу - тогда приглашение недействительно
+        if state.party_of(&invite.inviter).is_some_and(|party| party.leader != invite.inviter) {
+            return Err("Party invite is no longer valid".to_string());
+        }
+
+        let party_id = match state.membership.get(&invite.inviter) {
+            Some(party_id) => *party_id,
+            None => {
+                let party_id = Uuid::new_v4();
+                state.parties.insert(party_id, Party {
+                    id: party_id,
+                    leader: invite.inviter,
+                    members: vec![invite.inviter],
+                });
+                state.membership.insert(invite.inviter, party_id);
+                party_id
+            }
+        };
+
+        let party = state.parties.get_mut(&party_id).ok_or_else(|| "Party no longer exists".to_string())?;
+        if party.members.len() >= MAX_PARTY_SIZE {
+            return Err("Party is full".to_string());
+        }
+        party.members.push(invitee);
+        let party = party.clone();
+        state.membership.insert(invitee, party_id);
+
+        Ok(party)
+    }
+
+    // Возвращает пригласившего, чтобы сообщить ему об отказе
+    pub async fn decline(&self, invitee: PlayerId) -> Result<PlayerId, String> {
+        let mut state = self.state.write().await;
+        state
+            .invites
+            .remove(&invitee)
+            .map(|invite| invite.inviter)
+            .ok_or_else(|| "You have no pending party invite".to_string())
+    }
+
+    pub async fn leave(&self, player_id: PlayerId) -> Result<PartyChange, String> {
+        let mut state = self.state.write().await;
+        let party_id = *state
+            .membership
+            .get(&player_id)
+            .ok_or_else(|| "You are not in a party".to_string())?;
+        Ok(state.remove_member(party_id, player_id))
+    }
+
+    pub async fn kick(&self, leader: PlayerId, target: PlayerId) -> Result<PartyChange, String> {
+        let mut state = self.state.write().await;
+        let party = state.party_of(&leader).ok_or_else(|| "You are not in a party".to_string())?;
+        if party.leader != leader {
+            return Err("Only the party leader can kick".to_string());
+        }
+        if leader == target {
+            return Err("Use leave to quit the party".to_string());
+        }
+        if !party.members.contains(&target) {
+            return Err("Player is not in your party".to_string());
+        }
+
+        let party_id = party.id;
+        Ok(state.remove_member(party_id, target))
+    }
+
+    pub async fn promote(&self, leader: PlayerId, target: PlayerId) -> Result<Party, String> {
+        let mut state = self.state.write().await;
+        let party_id = *state
+            .membership
+            .get(&leader)
+            .ok_or_else(|| "You are not in a party".to_string())?;
+        let party = state.parties.get_mut(&party_id).ok_or_else(|| "You are not in a party".to_string())?;
+        if party.leader != leader {
+            return Err("Only the party leader can transfer leadership".to_string());
+        }
+        if leader == target {
+            return Err("You are already the party leader".to_string());
+        }
+        if !party.members.contains(&target) {
+            return Err("Player is not in your party".to_string());
+        }
+
+        party.leader = target;
+        Ok(party.clone())
+    }
+
+    // Отключение игрока: снимаем его приглашения и выводим из группы
+    pub async fn remove_player(&self, player_id: PlayerId) -> Option<PartyChange> {
+        let mut state = self.state.write().await;
+        state.invites.retain(|invitee, invite| *invitee != player_id && invite.inviter != player_id);
+
+        let party_id = *state.membership.get(&player_id)?;
+        Some(state.remove_member(party_id, player_id))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn invite_from_player_who_joined_another_party_is_rejected() {
+        let parties = PartyManager::new();
+        let (leader, inviter, invitee) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        parties.invite(inviter, invitee).await.unwrap();
+        parties.invite(leader, inviter).await.unwrap();
+        parties.accept(inviter).await.unwrap();
+
+        assert_eq!(parties.accept(invitee).await.unwrap_err(), "Party invite is no longer valid");
+        assert_eq!(parties.get_party(&leader).await.unwrap().members, vec![leader, inviter]);
+        assert!(parties.get_party(&invitee).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn accept_creates_party_led_by_inviter() {
+        let parties = PartyManager::new();
+        let (inviter, invitee) = (Uuid::new_v4(), Uuid::new_v4());
+
+        parties.invite(inviter, invitee).await.unwrap();
+        let party = parties.accept(invitee).await.unwrap();
+        assert_eq!(party.leader, inviter);
+        assert_eq!(party.members, vec![inviter, invitee]);
+    }
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+// Роли по возрастанию прав: старшая роль может все, что младшая
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    Player,
+    Moderator,
+    GameMaster,
+    Admin,
+}
+
+impl Role {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Player => "player",
+            Role::Moderator => "moderator",
+            Role::GameMaster => "game master",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Role> {
+        match name.to_lowercase().as_str() {
+            "player" => Some(Role::Player),
+            "moderator" | "mod" => Some(Role::Moderator),
+            "gamemaster" | "game_master" | "gm" => Some(Role::GameMaster),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
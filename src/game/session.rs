@@ -1,5 +1,7 @@
+use std::time::Instant;
 use tokio::sync::mpsc;
 use uuid::Uuid;
+use super::roles::Role;
 
 pub type PlayerId = Uuid;
 
@@ -8,7 +10,14 @@ pub struct GameSession {
     pub player_id: PlayerId,
     pub username: String,
     pub serialized_tx: mpsc::UnboundedSender<Vec<u8>>, // ✅ Канал для сериализованных данных
+    pub disconnect_tx: mpsc::UnboundedSender<String>, // принудительное отключение с причиной
+    // UDP-адрес появится вместе с UDP-транспортом
+    #[allow(dead_code)]
     pub udp_addr: Option<std::net::SocketAddr>,
+    pub peer_addr: std::net::SocketAddr, // адрес TCP-соединения, для банов по IP
+    pub last_whisper_from: Option<PlayerId>, // кому отвечать на /r
+    pub role: Role,
+    pub logged_in_at: Instant,
 }
 
 impl GameSession {
@@ -16,25 +25,33 @@ impl GameSession {
         player_id: PlayerId, 
         username: String, 
         serialized_tx: mpsc::UnboundedSender<Vec<u8>>,
+        disconnect_tx: mpsc::UnboundedSender<String>,
+        peer_addr: std::net::SocketAddr,
     ) -> Self {
         Self {
             player_id,
             username,
             serialized_tx,
+            disconnect_tx,
             udp_addr: None,
+            peer_addr,
+            last_whisper_from: None,
+            role: Role::Player,
+            logged_in_at: Instant::now(),
         }
     }
     
     // ✅ ОСНОВНОЙ метод - отправляет уже сериализованные данные
     pub fn send_serialized(&self, data: Vec<u8>) -> Result<(), mpsc::error::SendError<Vec<u8>>> {
-        let result = self.serialized_tx.send(data);
-        result
+        self.serialized_tx.send(data)
     }
     
+    #[allow(dead_code)]
     pub fn set_udp_addr(&mut self, addr: std::net::SocketAddr) {
         self.udp_addr = Some(addr);
     }
     
+    #[allow(dead_code)]
     pub fn get_udp_addr(&self) -> Option<std::net::SocketAddr> {
         self.udp_addr
     }
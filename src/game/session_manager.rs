@@ -1,29 +1,40 @@
 use std::collections::HashMap;
 use tokio::sync::RwLock;
 use crate::protocol::ServerMessage;
+use super::bans::IpNetwork;
+use super::roles::Role;
 use super::session::{GameSession, PlayerId};
 use bincode;
 
 #[derive(Debug)]
 pub struct SessionManager {
     sessions: RwLock<HashMap<PlayerId, GameSession>>,
+    // Имена игроков в сети в нижнем регистре. Блокируется всегда после sessions
+    names: RwLock<HashMap<String, PlayerId>>,
 }
 
 impl SessionManager {
     pub fn new() -> Self {
         Self {
             sessions: RwLock::new(HashMap::new()),
+            names: RwLock::new(HashMap::new()),
         }
     }
     
     pub async fn add_session(&self, session: GameSession) -> Result<(), String> {
         let mut sessions = self.sessions.write().await;
+        let mut names = self.names.write().await;
         
         if sessions.contains_key(&session.player_id) {
             return Err(format!("Session already exists for player_id: {}", session.player_id));
         }
+        let name_key = session.username.to_lowercase();
+        if names.contains_key(&name_key) {
+            return Err(format!("Player {} is already online", session.username));
+        }
         
         println!("New session created for player: {} ({})", session.username, session.player_id);
+        names.insert(name_key, session.player_id);
         sessions.insert(session.player_id, session);
         
         Ok(())
@@ -31,7 +42,10 @@ impl SessionManager {
     
     pub async fn remove_session(&self, player_id: &PlayerId) -> Option<GameSession> {
         let mut sessions = self.sessions.write().await;
-        sessions.remove(player_id)
+        let mut names = self.names.write().await;
+        let session = sessions.remove(player_id)?;
+        names.remove(&session.username.to_lowercase());
+        Some(session)
     }
     
     pub async fn get_session(&self, player_id: &PlayerId) -> Option<GameSession> {
@@ -39,6 +53,61 @@ impl SessionManager {
         sessions.get(player_id).cloned()
     }
     
+    // Поиск игрока в сети по имени без учета регистра
+    pub async fn find_by_name(&self, username: &str) -> Option<(PlayerId, String)> {
+        let sessions = self.sessions.read().await;
+        let names = self.names.read().await;
+        names
+            .get(&username.to_lowercase())
+            .and_then(|player_id| sessions.get(player_id))
+            .map(|session| (session.player_id, session.username.clone()))
+    }
+    
+    // Кто из перечисленных игроков сейчас в сети (имена сравниваются точно)
+    pub async fn find_online(&self, usernames: &[String]) -> Vec<(PlayerId, String)> {
+        let sessions = self.sessions.read().await;
+        let names = self.names.read().await;
+        usernames
+            .iter()
+            .filter_map(|username| {
+                let session = names.get(&username.to_lowercase()).and_then(|id| sessions.get(id))?;
+                (session.username == *username).then(|| (session.player_id, session.username.clone()))
+            })
+            .collect()
+    }
+    
+    pub async fn set_last_whisper_from(&self, player_id: &PlayerId, from: PlayerId) {
+        let mut sessions = self.sessions.write().await;
+        if let Some(session) = sessions.get_mut(player_id) {
+            session.last_whisper_from = Some(from);
+        }
+    }
+    
+    pub async fn set_role(&self, player_id: &PlayerId, role: Role) {
+        let mut sessions = self.sessions.write().await;
+        if let Some(session) = sessions.get_mut(player_id) {
+            session.role = role;
+        }
+    }
+    
+    // Игроки, подключенные с адресов из подсети (для бана по IP)
+    pub async fn find_by_address(&self, network: &IpNetwork) -> Vec<PlayerId> {
+        let sessions = self.sessions.read().await;
+        sessions
+            .values()
+            .filter(|session| network.contains(session.peer_addr.ip()))
+            .map(|session| session.player_id)
+            .collect()
+    }
+    
+    // Просим обработчик соединения закрыть его с указанной причиной
+    pub async fn disconnect(&self, player_id: &PlayerId, reason: &str) -> bool {
+        let sessions = self.sessions.read().await;
+        sessions
+            .get(player_id)
+            .is_some_and(|session| session.disconnect_tx.send(reason.to_string()).is_ok())
+    }
+    
     // ✅ ПРАВИЛЬНАЯ рассылка - сериализует один раз и отправляет всем
     pub async fn broadcast(&self, message: &ServerMessage) {
         let sessions = self.sessions.read().await;
@@ -78,10 +147,10 @@ impl SessionManager {
         
         let mut sent_count = 0;
         for (player_id, session) in sessions.iter() {
-            if player_id != exclude_player_id {
-                if session.send_serialized(serialized_data.clone()).is_ok() {
-                    sent_count += 1;
-                } 
+            if player_id != exclude_player_id
+                && session.send_serialized(serialized_data.clone()).is_ok()
+            {
+                sent_count += 1;
             }
         }
         if sent_count > 0 {
@@ -89,6 +158,24 @@ impl SessionManager {
         }
     }
     
+    // Рассылка выбранным игрокам - сериализуем один раз
+    pub async fn send_to_players(&self, player_ids: &[PlayerId], message: &ServerMessage) {
+        let sessions = self.sessions.read().await;
+        let serialized_data = match bincode::serialize(message) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Failed to serialize message for send_to_players: {}", e);
+                return;
+            }
+        };
+        
+        for player_id in player_ids {
+            if let Some(session) = sessions.get(player_id) {
+                session.send_serialized(serialized_data.clone()).ok();
+            }
+        }
+    }
+    
     // ✅ ПРАВИЛЬНАЯ отправка конкретному игроку
     pub async fn send_to_player(&self, player_id: &PlayerId, message: ServerMessage) -> Result<(), String> {
     
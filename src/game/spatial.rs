@@ -0,0 +1,282 @@
+use crate::protocol::Vector3;
+use uuid::Uuid;
+use super::collision::{self, Aabb, ZoneCollision, PLAYER_RADIUS};
+use super::session::PlayerId;
+
+// Рост игрока и высота глаз для проверок видимости
+pub const PLAYER_HEIGHT: f32 = 1.8;
+pub const EYE_HEIGHT: f32 = 1.6;
+// Объекты мира считаем коробкой 1x1x2 вокруг их позиции
+const OBJECT_HALF_EXTENT: f32 = 0.5;
+const OBJECT_HEIGHT: f32 = 2.0;
+
+// Сущность, найденная запросом. NPC в мире пока нет - появятся здесь же
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityRef {
+    Player(PlayerId),
+    Object(Uuid),
+}
+
+// Что учитывать в запросе; exclude - обычно сам спрашивающий
+#[derive(Debug, Clone, Copy)]
+pub struct QueryFilter {
+    pub players: bool,
+    pub objects: bool,
+    pub geometry: bool,
+    pub exclude: Option<EntityRef>,
+}
+
+impl QueryFilter {
+    #[allow(dead_code)]
+    pub fn all() -> Self {
+        Self { players: true, objects: true, geometry: true, exclude: None }
+    }
+
+    #[allow(dead_code)]
+    pub fn players() -> Self {
+        Self { players: true, objects: false, geometry: false, exclude: None }
+    }
+
+    #[allow(dead_code)]
+    pub fn objects() -> Self {
+        Self { players: false, objects: true, geometry: false, exclude: None }
+    }
+
+    // Только стены и земля - для проверки прямой видимости
+    #[allow(dead_code)]
+    pub fn geometry() -> Self {
+        Self { players: false, objects: false, geometry: true, exclude: None }
+    }
+
+    #[allow(dead_code)]
+    pub fn excluding(self, entity: EntityRef) -> Self {
+        Self { exclude: Some(entity), ..self }
+    }
+
+    pub fn accepts(&self, entity: &EntityRef) -> bool {
+        let kind = match entity {
+            EntityRef::Player(_) => self.players,
+            EntityRef::Object(_) => self.objects,
+        };
+        kind && self.exclude.as_ref() != Some(entity)
+    }
+}
+
+// Попадание луча; entity None - статическая геометрия зоны
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub struct RayHit {
+    pub entity: Option<EntityRef>,
+    pub point: Vector3,
+    pub distance: f32,
+}
+
+// Сущность зоны с позицией, собранная из игроков и объектов
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub entity: EntityRef,
+    pub position: Vector3,
+}
+
+impl Candidate {
+    pub fn bounds(&self) -> Aabb {
+        match self.entity {
+            EntityRef::Player(_) => Aabb::around(&self.position, PLAYER_RADIUS, PLAYER_HEIGHT),
+            EntityRef::Object(_) => Aabb::around(&self.position, OBJECT_HALF_EXTENT, OBJECT_HEIGHT),
+        }
+    }
+
+    // Точка, в которую смотрим при проверке видимости
+    pub fn visible_point(&self) -> Vector3 {
+        match self.entity {
+            EntityRef::Player(_) => eye_position(&self.position),
+            EntityRef::Object(_) => object_center(&self.position),
+        }
+    }
+}
+
+// Первое попадание отрезка from -> to в сущности и геометрию
+pub fn raycast(
+    collision: Option<&ZoneCollision>,
+    candidates: &[Candidate],
+    from: &Vector3,
+    to: &Vector3,
+    filter: &QueryFilter,
+) -> Option<RayHit> {
+    let geometry = collision
+        .filter(|_| filter.geometry)
+        .and_then(|collision| collision.raycast(from, to))
+        .map(|t| (t, None));
+    let entities = candidates
+        .iter()
+        .filter_map(|candidate| candidate.bounds().segment_entry(from, to).map(|t| (t, Some(candidate.entity))));
+
+    let (t, entity) = geometry.into_iter().chain(entities).min_by(|a, b| a.0.total_cmp(&b.0))?;
+    Some(RayHit {
+        entity,
+        point: collision::lerp(from, to, t),
+        distance: collision::distance(from, to) * t,
+    })
+}
+
+pub fn overlap_sphere(candidates: &[Candidate], center: &Vector3, radius: f32) -> Vec<EntityRef> {
+    candidates
+        .iter()
+        .filter(|candidate| candidate.bounds().distance_squared(center) <= radius * radius)
+        .map(|candidate| candidate.entity)
+        .collect()
+}
+
+pub fn overlap_box(candidates: &[Candidate], bounds: &Aabb) -> Vec<EntityRef> {
+    candidates
+        .iter()
+        .filter(|candidate| candidate.bounds().overlaps(bounds))
+        .map(|candidate| candidate.entity)
+        .collect()
+}
+
+// Прямая видимость: мешают только стены и земля
+pub fn line_of_sight(collision: Option<&ZoneCollision>, from: &Vector3, to: &Vector3) -> bool {
+    collision.is_none_or(|collision| collision.raycast(from, to).is_none())
+}
+
+// Ближайшая сущность по расстоянию до ее позиции, не дальше max_distance;
+// с filter.geometry - только те, что видны из точки
+pub fn nearest(
+    collision: Option<&ZoneCollision>,
+    candidates: &[Candidate],
+    point: &Vector3,
+    max_distance: f32,
+    filter: &QueryFilter,
+) -> Option<(EntityRef, f32)> {
+    candidates
+        .iter()
+        .filter(|candidate| !filter.geometry || line_of_sight(collision, point, &candidate.visible_point()))
+        .map(|candidate| (candidate.entity, collision::distance(point, &candidate.position)))
+        .filter(|(_, distance)| *distance <= max_distance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+// Точка на уровне глаз над позицией на земле
+pub fn eye_position(position: &Vector3) -> Vector3 {
+    Vector3 { x: position.x, y: position.y, z: position.z + EYE_HEIGHT }
+}
+
+// Середина объекта по высоте - в нее смотрим при проверке видимости
+pub fn object_center(position: &Vector3) -> Vector3 {
+    Vector3 { x: position.x, y: position.y, z: position.z + OBJECT_HEIGHT * 0.5 }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f32, y: f32, z: f32) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    fn near(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    fn player(n: u128) -> EntityRef {
+        EntityRef::Player(Uuid::from_u128(n))
+    }
+
+    fn object() -> EntityRef {
+        EntityRef::Object(Uuid::from_u128(100))
+    }
+
+    // Стена поперек оси X между первым игроком и объектом
+    fn walled() -> ZoneCollision {
+        ZoneCollision {
+            heightmap: None,
+            boxes: vec![Aabb { min: v(4.0, -5.0, 0.0), max: v(5.0, 5.0, 3.0) }],
+        }
+    }
+
+    // Игрок 1 перед стеной, объект и игрок 2 за ней
+    fn candidates(filter: &QueryFilter) -> Vec<Candidate> {
+        [(player(1), v(1.0, 0.0, 0.0)), (object(), v(6.0, 0.0, 0.0)), (player(2), v(8.0, 0.0, 0.0))]
+            .into_iter()
+            .map(|(entity, position)| Candidate { entity, position })
+            .filter(|candidate| filter.accepts(&candidate.entity))
+            .collect()
+    }
+
+    #[test]
+    fn filter_drops_disabled_kinds_and_excluded_entity() {
+        assert!(QueryFilter::players().accepts(&player(1)));
+        assert!(!QueryFilter::players().accepts(&object()));
+        assert!(QueryFilter::objects().accepts(&object()));
+        assert!(!QueryFilter::geometry().accepts(&player(1)));
+
+        let filter = QueryFilter::all().excluding(player(1));
+        assert!(!filter.accepts(&player(1)));
+        assert!(filter.accepts(&player(2)));
+        assert!(filter.accepts(&object()));
+    }
+
+    #[test]
+    fn raycast_returns_the_first_entity_or_wall_hit() {
+        let collision = walled();
+        let (from, to) = (v(0.0, 0.0, 1.0), v(10.0, 0.0, 1.0));
+
+        let filter = QueryFilter::all();
+        let hit = raycast(Some(&collision), &candidates(&filter), &from, &to, &filter).unwrap();
+        assert_eq!(hit.entity, Some(player(1)));
+        assert!(near(hit.distance, 0.6));
+
+        // Без первого игрока луч упирается в стену раньше, чем в объект
+        let filter = QueryFilter::all().excluding(player(1));
+        let hit = raycast(Some(&collision), &candidates(&filter), &from, &to, &filter).unwrap();
+        assert_eq!(hit.entity, None);
+        assert!(near(hit.distance, 4.0) && near(hit.point.x, 4.0));
+
+        let filter = QueryFilter::objects();
+        let hit = raycast(Some(&collision), &candidates(&filter), &from, &to, &filter).unwrap();
+        assert_eq!(hit.entity, Some(object()));
+        assert!(near(hit.distance, 5.5));
+
+        let filter = QueryFilter::players();
+        let hit = raycast(Some(&collision), &candidates(&filter), &to, &from, &filter).unwrap();
+        assert_eq!(hit.entity, Some(player(2)));
+        assert!(near(hit.distance, 1.6));
+
+        let filter = QueryFilter::geometry();
+        assert!(raycast(Some(&collision), &candidates(&filter), &v(0.0, 0.0, 4.0), &v(10.0, 0.0, 4.0), &filter).is_none());
+    }
+
+    #[test]
+    fn overlap_sphere_and_box_use_entity_bounds() {
+        let all = candidates(&QueryFilter::all());
+        assert_eq!(overlap_sphere(&all, &v(3.0, 0.0, 1.0), 2.0), vec![player(1)]);
+        assert_eq!(overlap_sphere(&all, &v(3.0, 0.0, 1.0), 3.0), vec![player(1), object()]);
+
+        let bounds = Aabb { min: v(5.0, -1.0, 0.0), max: v(9.0, 1.0, 1.0) };
+        assert_eq!(overlap_box(&all, &bounds), vec![object(), player(2)]);
+        let players = candidates(&QueryFilter::players());
+        assert_eq!(overlap_box(&players, &bounds), vec![player(2)]);
+    }
+
+    #[test]
+    fn nearest_skips_entities_behind_walls_with_geometry_filter() {
+        let collision = walled();
+        let point = v(3.8, 0.0, 1.0);
+        let filter = QueryFilter::all();
+        let all = candidates(&filter);
+
+        // Объект ближе, но закрыт стеной
+        let (entity, distance) = nearest(Some(&collision), &all, &point, 10.0, &filter).unwrap();
+        assert_eq!(entity, player(1));
+        assert!(near(distance, (2.8f32 * 2.8 + 1.0).sqrt()));
+        assert!(nearest(Some(&collision), &all, &point, 2.5, &filter).is_none());
+
+        let no_geometry = QueryFilter { geometry: false, ..filter };
+        assert_eq!(nearest(Some(&collision), &all, &point, 10.0, &no_geometry).unwrap().0, object());
+        assert_eq!(nearest(None, &all, &point, 10.0, &filter).unwrap().0, object());
+        assert!(line_of_sight(None, &point, &v(6.0, 0.0, 1.0)));
+        assert!(!line_of_sight(Some(&collision), &point, &v(6.0, 0.0, 1.0)));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::protocol::{PlayerClass, Vector3};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use super::collision::ZoneCollision;
+use super::zones::ZoneId;
+
+// Ближе этого к другому игроку не ставим
+const SPAWN_SPACING: f32 = 1.0;
+// Если точка занята - ищем место на кольцах вокруг нее
+const RING_STEP: f32 = 1.5;
+const RING_COUNT: usize = 3;
+const RING_DIRECTIONS: usize = 8;
+
+// Точка появления новых персонажей; classes пусто - для всех классов
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpawnPoint {
+    pub position: Vector3,
+    #[serde(default)]
+    pub classes: Vec<PlayerClass>,
+}
+
+// Точка привязки (камень возвращения), сохраняется с персонажем
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BindPoint {
+    pub zone_id: ZoneId,
+    pub position: Vector3,
+}
+
+// Точки для класса: сначала отмеченные этим классом, потом общие, иначе spawn зоны
+pub fn spawn_points(points: &[SpawnPoint], class: PlayerClass, fallback: &Vector3) -> Vec<Vector3> {
+    let for_class: Vec<Vector3> = points
+        .iter()
+        .filter(|point| point.classes.contains(&class))
+        .map(|point| point.position.clone())
+        .collect();
+    if !for_class.is_empty() {
+        return for_class;
+    }
+    let common: Vec<Vector3> = points
+        .iter()
+        .filter(|point| point.classes.is_empty())
+        .map(|point| point.position.clone())
+        .collect();
+    if !common.is_empty() {
+        return common;
+    }
+    vec![fallback.clone()]
+}
+
+// Сама точка и кольца вокруг нее, от ближнего к дальнему
+fn around(point: &Vector3) -> impl Iterator<Item = Vector3> + '_ {
+    let rings = (1..=RING_COUNT).flat_map(move |ring| {
+        (0..RING_DIRECTIONS).map(move |direction| {
+            let angle = direction as f32 * std::f32::consts::TAU / RING_DIRECTIONS as f32;
+            let distance = ring as f32 * RING_STEP;
+            Vector3 {
+                x: point.x + angle.cos() * distance,
+                y: point.y + angle.sin() * distance,
+                z: point.z,
+            }
+        })
+    });
+    std::iter::once(point.clone()).chain(rings)
+}
+
+// Свободное место возле одной из точек: не в стене и не вплотную к другому игроку.
+// Точки перебираются в случайном порядке, чтобы игроки распределялись между ними
+pub fn free_position(points: &[Vector3], occupied: &[Vector3], collision: Option<&ZoneCollision>) -> Vector3 {
+    let mut order: Vec<&Vector3> = points.iter().collect();
+    order.shuffle(&mut rand::rng());
+
+    for point in &order {
+        for candidate in around(point) {
+            // До места должно быть можно дойти от точки, заодно ставим на землю
+            let position = match collision {
+                Some(collision) => match collision.resolve_move(point, &candidate) {
+                    Some(position) => position,
+                    None => continue,
+                },
+                None => candidate,
+            };
+            let free = occupied.iter().all(|other| {
+                let (dx, dy) = (other.x - position.x, other.y - position.y);
+                dx * dx + dy * dy >= SPAWN_SPACING * SPAWN_SPACING
+            });
+            if free {
+                return position;
+            }
+        }
+    }
+
+    // Все занято - ставим прямо в точку
+    let point = order.first().map_or(Vector3 { x: 0.0, y: 0.0, z: 0.0 }, |point| (*point).clone());
+    match collision {
+        Some(collision) => collision.snap_to_ground(&point),
+        None => point,
+    }
+}
@@ -0,0 +1,66 @@
+use crate::protocol::PlayerStats;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatKind {
+    MaxHealth,
+    MaxMana,
+    Strength,
+    Agility,
+    Intelligence,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct StatModifier {
+    pub stat: StatKind,
+    pub amount: i32,
+}
+
+// Временный эффект от предмета
+#[derive(Debug, Clone)]
+pub struct ActiveBuff {
+    pub source_template_id: u32,
+    pub modifier: StatModifier,
+    pub expires_at: Instant,
+}
+
+// Стартовые характеристики нового персонажа
+pub fn starting_stats() -> PlayerStats {
+    PlayerStats {
+        health: 100,
+        max_health: 100,
+        mana: 50,
+        max_mana: 50,
+        strength: 10,
+        agility: 10,
+        intelligence: 10,
+    }
+}
+
+// Итоговые характеристики: база + модификаторы.
+// Текущие health/mana берутся из current и обрезаются по новым максимумам.
+pub fn apply_modifiers<'a>(
+    base: &PlayerStats,
+    current: &PlayerStats,
+    modifiers: impl Iterator<Item = &'a StatModifier>,
+) -> PlayerStats {
+    let mut stats = base.clone();
+
+    for modifier in modifiers {
+        match modifier.stat {
+            StatKind::MaxHealth => stats.max_health += modifier.amount,
+            StatKind::MaxMana => stats.max_mana += modifier.amount,
+            StatKind::Strength => stats.strength += modifier.amount,
+            StatKind::Agility => stats.agility += modifier.amount,
+            StatKind::Intelligence => stats.intelligence += modifier.amount,
+        }
+    }
+
+    stats.max_health = stats.max_health.max(1);
+    stats.max_mana = stats.max_mana.max(0);
+    stats.health = current.health.clamp(0, stats.max_health);
+    stats.mana = current.mana.clamp(0, stats.max_mana);
+    stats
+}
@@ -0,0 +1,121 @@
+use crate::config::ServerConfig;
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use super::{Collection, Storage};
+
+// JSON-файлы, пути из настроек game.*. Для разработки: файлы легко читать и править руками.
+// Файл - один JSON-массив, поэтому при любом изменении он переписывается целиком;
+// записи по ключам держим в памяти, чтобы не перечитывать файл
+#[derive(Debug)]
+pub struct FileStorage {
+    accounts_file: String,
+    bans_file: String,
+    characters_file: String,
+    guilds_file: String,
+    mail_file: String,
+    world_state_file: String,
+    records: Mutex<HashMap<Collection, BTreeMap<String, Value>>>,
+}
+
+impl FileStorage {
+    pub fn new(config: &ServerConfig) -> Self {
+        Self {
+            accounts_file: config.game.accounts.accounts_file.clone(),
+            bans_file: config.game.accounts.bans_file.clone(),
+            characters_file: config.game.world.characters_file.clone(),
+            guilds_file: config.game.guilds.guilds_file.clone(),
+            mail_file: config.game.mail.mail_file.clone(),
+            world_state_file: config.game.world.world_state_file.clone(),
+            records: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn path(&self, collection: Collection) -> &str {
+        match collection {
+            Collection::Accounts => &self.accounts_file,
+            Collection::Bans => &self.bans_file,
+            Collection::Characters => &self.characters_file,
+            Collection::Guilds => &self.guilds_file,
+            Collection::Mail => &self.mail_file,
+            Collection::WorldState => &self.world_state_file,
+        }
+    }
+
+    fn read(&self, collection: Collection) -> Result<Option<Vec<Value>>, String> {
+        let path = self.path(collection);
+        let Ok(content) = fs::read_to_string(path) else {
+            return Ok(None);
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+
+    // Меняет записи коллекции и переписывает ее файл
+    fn update(
+        &self,
+        collection: Collection,
+        change: impl FnOnce(&mut BTreeMap<String, Value>),
+    ) -> Result<(), String> {
+        let mut collections = self.records.lock();
+        let records = match collections.entry(collection) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                // Поврежденный файл не затираем: его нужно сначала починить
+                let loaded = self.read(collection)?.unwrap_or_default();
+                entry.insert(
+                    loaded
+                        .into_iter()
+                        .filter_map(|record| Some((collection.key_of(&record)?, record)))
+                        .collect(),
+                )
+            }
+        };
+        change(records);
+        self.write(collection, records.values())
+    }
+
+    // Пишем во временный файл и переименовываем, чтобы не оставить полузаписанный файл
+    fn write<'a>(&self, collection: Collection, records: impl Iterator<Item = &'a Value>) -> Result<(), String> {
+        let path = self.path(collection);
+        let records: Vec<&Value> = records.collect();
+        let json = serde_json::to_string_pretty(&records).map_err(|e| format!("Failed to serialize {}: {}", path, e))?;
+
+        let target = Path::new(path);
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory for {}: {}", path, e))?;
+        }
+        let tmp_path = target.with_extension("tmp");
+        fs::write(&tmp_path, json).map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, target).map_err(|e| format!("Failed to save {}: {}", path, e))
+    }
+}
+
+impl Storage for FileStorage {
+    fn describe(&self) -> String {
+        "file".to_string()
+    }
+
+    fn load_collection(&self, collection: Collection) -> Result<Option<Vec<Value>>, String> {
+        if let Some(records) = self.records.lock().get(&collection) {
+            return Ok(Some(records.values().cloned().collect()));
+        }
+        self.read(collection)
+    }
+
+    fn upsert(&self, collection: Collection, records: Vec<(String, Value)>) -> Result<(), String> {
+        self.update(collection, |existing| existing.extend(records))
+    }
+
+    fn remove(&self, collection: Collection, keys: Vec<String>) -> Result<(), String> {
+        self.update(collection, |existing| {
+            for key in &keys {
+                existing.remove(key);
+            }
+        })
+    }
+}
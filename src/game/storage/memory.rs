@@ -0,0 +1,40 @@
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use super::{Collection, Storage};
+
+// Ничего не пишет на диск: для тестов и временных серверов
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    collections: Mutex<HashMap<Collection, BTreeMap<String, Value>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn describe(&self) -> String {
+        "in-memory".to_string()
+    }
+
+    fn load_collection(&self, collection: Collection) -> Result<Option<Vec<Value>>, String> {
+        Ok(self.collections.lock().get(&collection).map(|records| records.values().cloned().collect()))
+    }
+
+    fn upsert(&self, collection: Collection, records: Vec<(String, Value)>) -> Result<(), String> {
+        self.collections.lock().entry(collection).or_default().extend(records);
+        Ok(())
+    }
+
+    fn remove(&self, collection: Collection, keys: Vec<String>) -> Result<(), String> {
+        let mut collections = self.collections.lock();
+        let records = collections.entry(collection).or_default();
+        for key in keys {
+            records.remove(&key);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use crate::config::{ServerConfig, StorageBackend};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+mod file;
+mod memory;
+mod sqlite;
+
+pub use file::FileStorage;
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
+
+const COLLECTIONS: [Collection; 6] = [
+    Collection::Accounts,
+    Collection::Bans,
+    Collection::Characters,
+    Collection::Guilds,
+    Collection::Mail,
+    Collection::WorldState,
+];
+
+// Что хранится: у каждой коллекции свой файл или таблица
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Collection {
+    Accounts,
+    Bans,
+    Characters,
+    Guilds,
+    Mail,
+    WorldState,
+}
+
+impl Collection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Collection::Accounts => "accounts",
+            Collection::Bans => "bans",
+            Collection::Characters => "characters",
+            Collection::Guilds => "guilds",
+            Collection::Mail => "mail",
+            Collection::WorldState => "world_state",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Collection::Accounts => "Account data",
+            Collection::Bans => "Ban list",
+            Collection::Characters => "Character data",
+            Collection::Guilds => "Guild data",
+            Collection::Mail => "Mail data",
+            Collection::WorldState => "World state",
+        }
+    }
+
+    // Ключ записи внутри коллекции. Бан ключуется целью: у цели может быть только один бан
+    pub fn key_of(&self, record: &Value) -> Option<String> {
+        let field = |value: &Value, name: &str| match value.get(name)? {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        };
+        match self {
+            Collection::Characters => field(record, "player_id"),
+            Collection::Bans => {
+                let target = record.get("target")?;
+                let kind = field(target, "type")?;
+                let id = field(target, "account_id").or_else(|| field(target, "network"))?;
+                Some(format!("{}:{}", kind, id))
+            }
+            _ => field(record, "id"),
+        }
+    }
+}
+
+// Хранилище сохранений. Коллекция читается целиком при старте,
+// дальше записи вставляются, заменяются и удаляются по ключу (Collection::key_of).
+// Методы блокирующие: из асинхронного кода вызываются через save() и remove()
+pub trait Storage: Debug + Send + Sync {
+    fn describe(&self) -> String;
+    // None - в коллекцию еще ни разу не писали
+    fn load_collection(&self, collection: Collection) -> Result<Option<Vec<Value>>, String>;
+    fn upsert(&self, collection: Collection, records: Vec<(String, Value)>) -> Result<(), String>;
+    fn remove(&self, collection: Collection, keys: Vec<String>) -> Result<(), String>;
+}
+
+// Хранилище из настроек [storage]. Если выбранное хранилище не открывается, сервер не стартует:
+// молча писать сохранения в другое место хуже, чем не запуститься
+pub fn open(config: &ServerConfig) -> Result<Arc<dyn Storage>, String> {
+    let storage: Arc<dyn Storage> = match config.storage.backend {
+        StorageBackend::File => Arc::new(FileStorage::new(config)),
+        StorageBackend::Sqlite => Arc::new(SqliteStorage::open(&config.storage.sqlite_file)?),
+        StorageBackend::Memory => Arc::new(MemoryStorage::new()),
+    };
+    println!("💾 Using {} storage", storage.describe());
+    Ok(storage)
+}
+
+// Загрузка коллекции; пустой список, если ее нет или она повреждена (ошибка уже выведена)
+pub fn load<T: DeserializeOwned>(storage: &dyn Storage, collection: Collection) -> Vec<T> {
+    let records = match storage.load_collection(collection) {
+        Ok(Some(records)) => records,
+        Ok(None) => {
+            println!("{} not found in {} storage, starting empty", collection.label(), storage.describe());
+            return Vec::new();
+        }
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", collection.label(), e);
+            return Vec::new();
+        }
+    };
+
+    records
+        .into_iter()
+        .filter_map(|record| match serde_json::from_value(record) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                eprintln!("Skipping broken record in {}: {}", collection.label(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+// Вставка или замена записей по ключу
+pub async fn save<T: Serialize>(storage: &Arc<dyn Storage>, collection: Collection, items: &[T]) -> Result<(), String> {
+    let mut records = Vec::with_capacity(items.len());
+    for item in items {
+        let record = serde_json::to_value(item).map_err(|e| format!("Failed to serialize {}: {}", collection.name(), e))?;
+        let key = collection
+            .key_of(&record)
+            .ok_or_else(|| format!("Record in {} has no key", collection.name()))?;
+        records.push((key, record));
+    }
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let storage = storage.clone();
+    tokio::task::spawn_blocking(move || storage.upsert(collection, records))
+        .await
+        .map_err(|e| format!("Failed to save {}: {}", collection.name(), e))?
+}
+
+pub async fn remove(storage: &Arc<dyn Storage>, collection: Collection, keys: Vec<String>) -> Result<(), String> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let storage = storage.clone();
+    tokio::task::spawn_blocking(move || storage.remove(collection, keys))
+        .await
+        .map_err(|e| format!("Failed to remove from {}: {}", collection.name(), e))?
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("storage-test-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file_config(dir: &std::path::Path) -> ServerConfig {
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        let mut config = ServerConfig::default();
+        config.game.accounts.accounts_file = path("accounts.json");
+        config.game.accounts.bans_file = path("bans.json");
+        config.game.world.characters_file = path("characters.json");
+        config.game.world.world_state_file = path("world_state.json");
+        config.game.guilds.guilds_file = path("guilds.json");
+        config.game.mail.mail_file = path("mail.json");
+        config
+    }
+
+    fn guild(id: &str, motd: &str) -> (String, Value) {
+        (id.to_string(), json!({ "id": id, "motd": motd }))
+    }
+
+    fn motd(storage: &dyn Storage, id: &str) -> Option<String> {
+        storage
+            .load_collection(Collection::Guilds)
+            .unwrap()?
+            .into_iter()
+            .find(|record| record["id"] == id)
+            .map(|record| record["motd"].as_str().unwrap().to_string())
+    }
+
+    fn count(storage: &dyn Storage, collection: Collection) -> Option<usize> {
+        storage.load_collection(collection).unwrap().map(|records| records.len())
+    }
+
+    // Общие проверки для всех хранилищ; в конце в гильдиях остается одна запись "a"
+    fn check_keyed_writes(storage: &dyn Storage) {
+        assert_eq!(count(storage, Collection::Guilds), None);
+
+        storage.upsert(Collection::Guilds, vec![guild("a", "first"), guild("b", "second")]).unwrap();
+        storage.upsert(Collection::Guilds, vec![guild("a", "updated")]).unwrap();
+        assert_eq!(count(storage, Collection::Guilds), Some(2));
+        assert_eq!(motd(storage, "a").as_deref(), Some("updated"));
+
+        storage.remove(Collection::Guilds, vec!["b".to_string(), "missing".to_string()]).unwrap();
+        assert_eq!(count(storage, Collection::Guilds), Some(1));
+        assert_eq!(motd(storage, "b"), None);
+
+        // Другие коллекции не затронуты
+        assert_eq!(count(storage, Collection::Mail), None);
+
+        storage.upsert(Collection::Mail, vec![guild("m", "")]).unwrap();
+        storage.remove(Collection::Mail, vec!["m".to_string()]).unwrap();
+        assert_eq!(count(storage, Collection::Mail), Some(0));
+    }
+
+    #[test]
+    fn memory_storage_writes_by_key() {
+        check_keyed_writes(&MemoryStorage::new());
+    }
+
+    #[test]
+    fn file_storage_writes_by_key_and_persists() {
+        let dir = temp_dir();
+        let config = file_config(&dir);
+        check_keyed_writes(&FileStorage::new(&config));
+
+        let reopened = FileStorage::new(&config);
+        assert_eq!(count(&reopened, Collection::Guilds), Some(1));
+        assert_eq!(motd(&reopened, "a").as_deref(), Some("updated"));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn sqlite_storage_writes_by_key_and_persists() {
+        let dir = temp_dir();
+        let path = dir.join("world.db").to_string_lossy().to_string();
+        check_keyed_writes(&SqliteStorage::open(&path).unwrap());
+
+        let reopened = SqliteStorage::open(&path).unwrap();
+        assert_eq!(count(&reopened, Collection::Guilds), Some(1));
+        assert_eq!(motd(&reopened, "a").as_deref(), Some("updated"));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn sqlite_open_failure_is_an_error() {
+        let dir = temp_dir();
+        let mut config = ServerConfig::default();
+        config.storage.backend = StorageBackend::Sqlite;
+        // Каталог вместо файла базы
+        config.storage.sqlite_file = dir.to_string_lossy().to_string();
+        assert!(open(&config).is_err());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn ban_records_are_keyed_by_target() {
+        let account = json!({ "target": { "type": "account", "account_id": "42", "username": "bob" } });
+        let address = json!({ "target": { "type": "address", "network": "10.0.0.0/24" } });
+        assert_eq!(Collection::Bans.key_of(&account).as_deref(), Some("account:42"));
+        assert_eq!(Collection::Bans.key_of(&address).as_deref(), Some("address:10.0.0.0/24"));
+        assert_eq!(Collection::Characters.key_of(&json!({ "player_id": "p" })).as_deref(), Some("p"));
+        assert_eq!(Collection::Guilds.key_of(&json!({ "name": "no id" })), None);
+    }
+
+    #[tokio::test]
+    async fn save_upserts_typed_records() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        save(&storage, Collection::Guilds, &[json!({ "id": "a", "motd": "x" })]).await.unwrap();
+        save(&storage, Collection::Guilds, &[json!({ "id": "a", "motd": "y" })]).await.unwrap();
+        assert_eq!(motd(storage.as_ref(), "a").as_deref(), Some("y"));
+
+        assert!(save(&storage, Collection::Guilds, &[json!({ "motd": "no key" })]).await.is_err());
+        remove(&storage, Collection::Guilds, vec!["a".to_string()]).await.unwrap();
+        assert_eq!(count(storage.as_ref(), Collection::Guilds), Some(0));
+    }
+}
@@ -0,0 +1,115 @@
+use parking_lot::Mutex;
+use rusqlite::{Connection, OptionalExtension, Transaction};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use super::{COLLECTIONS, Collection, Storage};
+
+// Встроенная база SQLite: таблица на коллекцию, строка - запись с ключом и JSON-документом.
+// Изменения пишутся построчно (вставка/замена по ключу, удаление) в одной транзакции
+#[derive(Debug)]
+pub struct SqliteStorage {
+    path: String,
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str) -> Result<Self, String> {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory for {}: {}", path, e))?;
+        }
+        let connection = Connection::open(path).map_err(|e| format!("Failed to open database {}: {}", path, e))?;
+        connection
+            .pragma_update(None, "journal_mode", "WAL")
+            .map_err(|e| format!("Failed to configure database {}: {}", path, e))?;
+
+        let mut schema = String::from("CREATE TABLE IF NOT EXISTS saved_collections (name TEXT PRIMARY KEY);");
+        for collection in COLLECTIONS {
+            schema.push_str(&format!(
+                "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, data TEXT NOT NULL);",
+                collection.name()
+            ));
+        }
+        connection
+            .execute_batch(&schema)
+            .map_err(|e| format!("Failed to create tables in {}: {}", path, e))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn write(
+        &self,
+        collection: Collection,
+        change: impl FnOnce(&Transaction) -> rusqlite::Result<()>,
+    ) -> Result<(), String> {
+        let mut connection = self.connection.lock();
+        let transaction = connection.transaction().map_err(|e| e.to_string())?;
+        change(&transaction)
+            .and_then(|_| {
+                transaction.execute("INSERT OR IGNORE INTO saved_collections (name) VALUES (?1)", [collection.name()])?;
+                transaction.commit()
+            })
+            .map_err(|e| format!("Failed to save {} to {}: {}", collection.name(), self.path, e))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn describe(&self) -> String {
+        format!("SQLite ({})", self.path)
+    }
+
+    fn load_collection(&self, collection: Collection) -> Result<Option<Vec<Value>>, String> {
+        let connection = self.connection.lock();
+        let saved = connection
+            .query_row(
+                "SELECT 1 FROM saved_collections WHERE name = ?1",
+                [collection.name()],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if saved.is_none() {
+            return Ok(None);
+        }
+
+        let mut statement = connection
+            .prepare(&format!("SELECT data FROM {} ORDER BY key", collection.name()))
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let data = row.map_err(|e| e.to_string())?;
+            records.push(serde_json::from_str(&data).map_err(|e| format!("{}: {}", collection.name(), e))?);
+        }
+        Ok(Some(records))
+    }
+
+    fn upsert(&self, collection: Collection, records: Vec<(String, Value)>) -> Result<(), String> {
+        self.write(collection, |transaction| {
+            let mut upsert = transaction.prepare(&format!(
+                "INSERT INTO {} (key, data) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET data = excluded.data",
+                collection.name()
+            ))?;
+            for (key, record) in &records {
+                upsert.execute([key, &record.to_string()])?;
+            }
+            Ok(())
+        })
+    }
+
+    fn remove(&self, collection: Collection, keys: Vec<String>) -> Result<(), String> {
+        self.write(collection, |transaction| {
+            let mut delete = transaction.prepare(&format!("DELETE FROM {} WHERE key = ?1", collection.name()))?;
+            for key in &keys {
+                delete.execute([key])?;
+            }
+            Ok(())
+        })
+    }
+}
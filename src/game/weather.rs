@@ -0,0 +1,242 @@
+use crate::protocol::{WeatherInfo, WeatherKind};
+use rand::Rng;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use super::zones::ZoneId;
+
+// Как часто проверять, не пора ли сменить погоду
+pub const WEATHER_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+fn default_weight() -> u32 {
+    1
+}
+
+// Состояние погоды зоны: вес при выборе следующего, сколько длится, куда может перейти
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeatherDef {
+    pub kind: WeatherKind,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    pub min_secs: u64,
+    pub max_secs: u64,
+    // Пусто - после этой погоды может начаться любая
+    #[serde(default)]
+    pub next: Vec<WeatherKind>,
+    // Если не заданы - значения по умолчанию для kind
+    pub movement_multiplier: Option<f32>,
+    pub visibility: Option<f32>,
+}
+
+impl WeatherDef {
+    pub fn info(&self) -> WeatherInfo {
+        let (movement, visibility) = default_effects(self.kind);
+        WeatherInfo {
+            kind: self.kind,
+            movement_multiplier: self.movement_multiplier.unwrap_or(movement),
+            visibility: self.visibility.unwrap_or(visibility),
+        }
+    }
+
+    fn roll_duration(&self) -> Duration {
+        Duration::from_secs(rand::rng().random_range(self.min_secs..=self.max_secs))
+    }
+}
+
+// (скорость движения, дальность видимости) относительно ясной погоды
+fn default_effects(kind: WeatherKind) -> (f32, f32) {
+    match kind {
+        WeatherKind::Clear => (1.0, 1.0),
+        WeatherKind::Rain => (0.95, 0.75),
+        WeatherKind::Storm => (0.85, 0.5),
+        WeatherKind::Snow => (0.8, 0.6),
+    }
+}
+
+pub fn clear_weather() -> WeatherInfo {
+    let (movement_multiplier, visibility) = default_effects(WeatherKind::Clear);
+    WeatherInfo { kind: WeatherKind::Clear, movement_multiplier, visibility }
+}
+
+pub fn parse_kind(text: &str) -> Option<WeatherKind> {
+    match text.to_lowercase().as_str() {
+        "clear" => Some(WeatherKind::Clear),
+        "rain" => Some(WeatherKind::Rain),
+        "storm" => Some(WeatherKind::Storm),
+        "snow" => Some(WeatherKind::Snow),
+        _ => None,
+    }
+}
+
+pub fn kind_name(kind: WeatherKind) -> &'static str {
+    match kind {
+        WeatherKind::Clear => "clear",
+        WeatherKind::Rain => "rain",
+        WeatherKind::Storm => "storm",
+        WeatherKind::Snow => "snow",
+    }
+}
+
+// Текущая погода зоны: индекс в списке состояний и до какого момента она держится
+#[derive(Debug)]
+pub struct ZoneWeather {
+    pub current: usize,
+    pub until: Instant,
+}
+
+impl ZoneWeather {
+    // Зона начинает с первого состояния из списка
+    pub fn start(defs: &[WeatherDef], now: Instant) -> Option<Self> {
+        let first = defs.first()?;
+        Some(Self { current: 0, until: now + first.roll_duration() })
+    }
+
+    // Переходим в состояние kind (команда GM); false - у зоны нет такой погоды
+    pub fn force(&mut self, defs: &[WeatherDef], kind: WeatherKind, now: Instant) -> bool {
+        let Some(index) = defs.iter().position(|def| def.kind == kind) else {
+            return false;
+        };
+        self.current = index;
+        self.until = now + defs[index].roll_duration();
+        true
+    }
+
+    // Время вышло - выбираем следующее состояние по весам. true - погода сменилась
+    pub fn advance(&mut self, defs: &[WeatherDef], now: Instant) -> bool {
+        if now < self.until {
+            return false;
+        }
+        let allowed = &defs[self.current].next;
+        let candidates: Vec<usize> = (0..defs.len())
+            .filter(|index| allowed.is_empty() || allowed.contains(&defs[*index].kind))
+            .collect();
+        let total: u32 = candidates.iter().map(|index| defs[*index].weight).sum();
+
+        let mut roll = rand::rng().random_range(0..total.max(1));
+        let mut next = self.current;
+        for index in candidates {
+            if roll < defs[index].weight {
+                next = index;
+                break;
+            }
+            roll -= defs[index].weight;
+        }
+
+        let changed = defs[next].kind != defs[self.current].kind;
+        self.current = next;
+        self.until = now + defs[next].roll_duration();
+        changed
+    }
+}
+
+// Отбрасываем некорректные состояния, чтобы выбор следующего всегда работал
+pub fn validate(zone_id: ZoneId, defs: &mut Vec<WeatherDef>) {
+    let mut seen = Vec::new();
+    defs.retain(|def| {
+        let valid = if seen.contains(&def.kind) {
+            eprintln!("Zone {}: duplicate weather {}, skipping", zone_id, kind_name(def.kind));
+            false
+        } else if def.weight == 0 || def.min_secs == 0 || def.min_secs > def.max_secs {
+            eprintln!("Zone {}: weather {} needs weight > 0 and 0 < min_secs <= max_secs, skipping",
+                zone_id, kind_name(def.kind)
+            );
+            false
+        } else if !(def.info().movement_multiplier > 0.0 && def.info().visibility > 0.0) {
+            eprintln!("Zone {}: weather {} needs positive movement_multiplier and visibility, skipping",
+                zone_id, kind_name(def.kind)
+            );
+            false
+        } else {
+            true
+        };
+        seen.push(def.kind);
+        valid
+    });
+
+    // Переходы только в погоду, которая есть у зоны
+    let kinds: Vec<WeatherKind> = defs.iter().map(|def| def.kind).collect();
+    for def in defs.iter_mut() {
+        def.next.retain(|kind| kinds.contains(kind));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(kind: WeatherKind, weight: u32, next: Vec<WeatherKind>) -> WeatherDef {
+        WeatherDef {
+            kind,
+            weight,
+            min_secs: 10,
+            max_secs: 20,
+            next,
+            movement_multiplier: None,
+            visibility: None,
+        }
+    }
+
+    #[test]
+    fn validate_drops_broken_states_and_transitions() {
+        let mut broken_effects = def(WeatherKind::Snow, 1, Vec::new());
+        broken_effects.visibility = Some(0.0);
+        let mut broken_duration = def(WeatherKind::Storm, 1, Vec::new());
+        broken_duration.min_secs = 30;
+
+        let mut defs = vec![
+            def(WeatherKind::Clear, 1, vec![WeatherKind::Rain, WeatherKind::Storm]),
+            def(WeatherKind::Clear, 1, Vec::new()),
+            def(WeatherKind::Rain, 0, Vec::new()),
+            broken_duration,
+            broken_effects,
+        ];
+        validate(1, &mut defs);
+
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].kind, WeatherKind::Clear);
+        assert!(defs[0].next.is_empty());
+    }
+
+    #[test]
+    fn advance_waits_and_follows_allowed_transitions() {
+        let defs = vec![
+            def(WeatherKind::Clear, 1, vec![WeatherKind::Storm]),
+            def(WeatherKind::Rain, 100, Vec::new()),
+            def(WeatherKind::Storm, 1, vec![WeatherKind::Clear]),
+        ];
+        let now = Instant::now();
+        let mut weather = ZoneWeather::start(&defs, now).unwrap();
+        assert_eq!(weather.current, 0);
+        assert!(!weather.advance(&defs, now));
+
+        // Rain весит больше всех, но из Clear разрешен только Storm, а из Storm - только Clear
+        for step in 0..20 {
+            let later = weather.until;
+            assert!(weather.advance(&defs, later));
+            let expected = if step % 2 == 0 { WeatherKind::Storm } else { WeatherKind::Clear };
+            assert_eq!(defs[weather.current].kind, expected);
+            assert!(weather.until >= later + Duration::from_secs(10));
+        }
+    }
+
+    #[test]
+    fn force_switches_only_to_configured_weather() {
+        let defs = vec![def(WeatherKind::Clear, 1, Vec::new()), def(WeatherKind::Rain, 1, Vec::new())];
+        let now = Instant::now();
+        let mut weather = ZoneWeather::start(&defs, now).unwrap();
+        assert!(weather.force(&defs, WeatherKind::Rain, now));
+        assert_eq!(defs[weather.current].kind, WeatherKind::Rain);
+        assert!(!weather.force(&defs, WeatherKind::Snow, now));
+        assert_eq!(defs[weather.current].kind, WeatherKind::Rain);
+        assert!(ZoneWeather::start(&[], now).is_none());
+    }
+
+    #[test]
+    fn effects_default_by_kind() {
+        let mut storm = def(WeatherKind::Storm, 1, Vec::new());
+        assert_eq!(storm.info().visibility, 0.5);
+        storm.movement_multiplier = Some(0.4);
+        assert_eq!(storm.info().movement_multiplier, 0.4);
+        assert_eq!(clear_weather().movement_multiplier, 1.0);
+    }
+}
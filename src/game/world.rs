@@ -1,53 +1,641 @@
-use crate::protocol::{PlayerId, Transform};
-use std::collections::HashMap;
+use crate::config::{GameConfig, ServerConfig};
+use crate::protocol::{
+    EquipmentSlot, EquippedItem, InventorySlot, ObjectUpdate, PartyMember, PlayerAction, PlayerClass, PlayerData,
+    PlayerId, PlayerStats, PlayerUpdate, RecipeInfo, Transform, Vector3, WeatherInfo, WeatherKind,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use uuid::Uuid;
+use super::animation::{self, AnimationState, Stance};
+use super::characters::CharacterRecord;
+use super::clock::WorldClock;
+use super::collision::{self, Aabb, ZoneCollision};
+use super::crafting::{self, ActiveCraft, RecipeBook, RecipeId};
+use super::equipment::Equipment;
+use super::instances::{self, InstanceManager, InstanceOwner};
+use super::inventory::Inventory;
+use super::items::{ItemDatabase, ItemEffect};
+use super::loot::LootTables;
+use super::mail::MailItem;
+use super::moderation::format_duration;
+use super::navigation::Pathfinder;
+use super::objects::{self, ChestState, DoorState, LeverState, ObjectKind, PortalState, WorldObject};
+use super::party::PartyId;
+use super::spawns::{self, BindPoint};
+use super::spatial::{self, Candidate, EntityRef, QueryFilter, RayHit};
+use super::stats::{self, ActiveBuff, StatModifier};
+use super::weather;
+use super::world_state::{WorldState, ZoneWeatherState};
+use super::zones::{self, Zone, ZoneId};
 
 #[derive(Debug, Clone)]
 pub struct PlayerState {
     pub username: String,
     pub transform: Transform,
-    pub zone_id: u32, // Простая система зон
+    pub zone_id: ZoneId,
+    pub zone_exit: Option<usize>, // переход зоны, в области которого стоит игрок
+    pub party_id: Option<PartyId>, // по группе выбирается копия подземелья
+    pub class: PlayerClass,
+    pub base_stats: PlayerStats,
+    pub stats: PlayerStats, // Итоговые характеристики, здесь же текущие health/mana
+    pub buffs: Vec<ActiveBuff>,
+    pub inventory: Inventory,
+    pub gold: u64,
+    pub equipment: Equipment,
+    pub crafting: Option<ActiveCraft>,
+    pub animation: AnimationState,
+    pub combat_until: Option<Instant>,
+    pub bind_point: Option<BindPoint>,
+    pub hearth_ready_at: Option<Instant>,
+    pub last_move_at: Option<Instant>, // для ограничения скорости; None - еще не двигался
 }
 
+impl PlayerState {
+    pub fn is_dead(&self) -> bool {
+        self.stats.health <= 0
+    }
+    
+    pub fn in_combat(&self, now: Instant) -> bool {
+        self.combat_until.is_some_and(|until| now < until)
+    }
+    
+    // Вызывается боевой системой при атаке и получении урона
+    #[allow(dead_code)]
+    pub fn enter_combat(&mut self) {
+        self.combat_until = Some(Instant::now() + animation::COMBAT_TIMEOUT);
+        self.animation.stance = Stance::Standing;
+    }
+    
+    pub fn to_update(&self, player_id: PlayerId) -> PlayerUpdate {
+        PlayerUpdate {
+            player_id,
+            transform: self.transform.clone(),
+            velocity: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+            animation: self.animation.animation(self.is_dead(), Instant::now()),
+            health: self.stats.health,
+        }
+    }
+    
+    // Уровней пока нет, все персонажи первого уровня
+    pub fn to_player_data(&self, player_id: PlayerId) -> PlayerData {
+        PlayerData {
+            id: player_id,
+            name: self.username.clone(),
+            level: 1,
+            class: self.class,
+            transform: self.transform.clone(),
+            stats: self.stats.clone(),
+        }
+    }
+    
+    // Снимок для сохранения; зону и позицию передает мир (из копии подземелья сохраняется выход)
+    pub fn to_record(&self, player_id: PlayerId, zone_id: ZoneId, position: Vector3) -> CharacterRecord {
+        CharacterRecord {
+            player_id,
+            username: self.username.clone(),
+            zone_id,
+            transform: Transform { position, ..self.transform.clone() },
+            class: self.class,
+            base_stats: self.base_stats.clone(),
+            stats: self.stats.clone(),
+            inventory: self.inventory.clone(),
+            equipment: self.equipment.clone(),
+            gold: self.gold,
+            bind_point: self.bind_point.clone(),
+            saved_at: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+    
+    pub fn to_party_member(&self, player_id: PlayerId) -> PartyMember {
+        PartyMember {
+            player_id,
+            name: self.username.clone(),
+            zone_id: self.zone_id,
+            position: self.transform.position.clone(),
+            health: self.stats.health,
+            max_health: self.stats.max_health,
+        }
+    }
+    
+    // Итоговые характеристики = база + экипировка + баффы
+    pub fn recompute_stats(&mut self, items: &ItemDatabase) {
+        let equipment_modifiers = self
+            .equipment
+            .items()
+            .filter_map(|stack| items.get(stack.template_id))
+            .flat_map(|template| template.stat_modifiers.iter());
+        let buff_modifiers = self.buffs.iter().map(|buff| &buff.modifier);
+        
+        self.stats = stats::apply_modifiers(
+            &self.base_stats,
+            &self.stats,
+            equipment_modifiers.chain(buff_modifiers),
+        );
+    }
+}
+
+// Результат использования предмета
+#[derive(Debug)]
+pub struct ItemUseOutcome {
+    pub changed_slots: Vec<InventorySlot>,
+    pub target_id: PlayerId,
+    pub target_stats: Option<PlayerStats>,
+    pub teleport: Option<ZoneTransfer>,
+}
+
+// Результат смены экипировки
+#[derive(Debug)]
+pub struct EquipOutcome {
+    pub changed_slots: Vec<InventorySlot>,
+    pub slot: EquipmentSlot,
+    pub template_id: Option<u32>,
+    pub stats: PlayerStats,
+    pub zone_id: u32,
+}
+
+// Результат взаимодействия с объектом мира
+#[derive(Debug)]
+pub struct InteractionOutcome {
+    pub object_updates: Vec<(u32, ObjectUpdate)>, // (зона, состояние объекта)
+    pub changed_slots: Vec<InventorySlot>,
+    pub teleport: Option<ZoneTransfer>,
+    pub notice: Option<String>, // сообщение игроку, например о новой точке привязки
+    pub removed_objects: Vec<(u32, Uuid)>, // (зона, объект) - подобранная целиком добыча
+    pub gold: Option<u64>, // новое количество золота, если оно изменилось
+}
+
+// Перемещение игрока телепортом или переходом; from_zone == to_zone - внутри зоны
+#[derive(Debug, Clone)]
+pub struct ZoneTransfer {
+    pub player_id: PlayerId,
+    pub from_zone: ZoneId,
+    pub to_zone: ZoneId,
+    pub transform: Transform,
+}
+
+// Переводим игрока в зону; подземелье заменяется копией группы.
+// Вызывается под блокировками players, objects и zones
+fn enter_zone(
+    instances: &InstanceManager,
+    zones: &mut HashMap<ZoneId, Zone>,
+    objects: &mut HashMap<Uuid, WorldObject>,
+    player_id: PlayerId,
+    player: &mut PlayerState,
+    (zone_id, position): (ZoneId, Vector3),
+    check_capacity: bool,
+) -> Result<ZoneTransfer, String> {
+    let from_zone = player.zone_id;
+    let owner = player.party_id.map_or(InstanceOwner::Player(player_id), InstanceOwner::Party);
+    let zone_id = instances::resolve_zone(instances, zones, objects, zone_id, player_id, owner)?;
+    let target = zones.get_mut(&zone_id).ok_or_else(|| format!("Zone {} does not exist", zone_id))?;
+    if from_zone != zone_id && check_capacity && target.is_full() {
+        return Err(format!("{} is full, try again later", target.def.name));
+    }
+    
+    target.players.insert(player_id);
+    player.zone_exit = target.exit_at(&position);
+    if from_zone != zone_id
+        && let Some(previous) = zones.get_mut(&from_zone)
+    {
+        previous.players.remove(&player_id);
+    }
+    
+    player.zone_id = zone_id;
+    player.transform.position = position;
+    instances::record_lockout(zones, zone_id, player_id);
+    Ok(ZoneTransfer {
+        player_id,
+        from_zone,
+        to_zone: zone_id,
+        transform: player.transform.clone(),
+    })
+}
+
+// Дальше этого предмет нельзя применить на другого игрока
+const ITEM_USE_RANGE: f32 = 10.0;
+
+// Скорость считается не больше чем за секунду: после паузы нельзя переместиться за весь простой
+const MOVE_WINDOW: Duration = Duration::from_secs(1);
+// Запас на неточность таймеров клиента
+const MOVE_TOLERANCE: f32 = 0.5;
+
+// Шаг длиннее max_distance укорачиваем в том же направлении; None - шаг допустим
+fn limit_step(from: &Vector3, to: &Vector3, max_distance: f32) -> Option<Vector3> {
+    let distance = collision::distance(from, to);
+    if distance <= max_distance {
+        return None;
+    }
+    Some(collision::lerp(from, to, max_distance / distance))
+}
+
+// Результат движения игрока
+#[derive(Debug)]
+pub enum MoveOutcome {
+    Accepted(Transform),
+    // Позиция принята, но высота у клиента разошлась с землей
+    Corrected(Transform),
+    // Путь проходит сквозь геометрию, игрок остается на месте
+    Rejected(Transform),
+}
+
+// События крафта для рассылки из игрового цикла
+#[derive(Debug)]
+pub enum CraftEvent {
+    Progress { player_id: PlayerId, recipe_id: RecipeId, progress: f32 },
+    Completed { player_id: PlayerId, recipe_id: RecipeId, changed_slots: Vec<InventorySlot> },
+    Cancelled { player_id: PlayerId, recipe_id: RecipeId, reason: String },
+}
+
+// Блокировки берутся в порядке players -> objects -> zones
 #[derive(Debug)]
 pub struct GameWorld {
     players: RwLock<HashMap<PlayerId, PlayerState>>,
-    next_zone_id: u32,
+    objects: RwLock<HashMap<Uuid, WorldObject>>,
+    zones: RwLock<HashMap<ZoneId, Zone>>,
+    instances: InstanceManager,
+    start_zone: ZoneId,
+    instance_empty_timeout: Duration,
+    max_move_speed: f32,
+    collision: HashMap<ZoneId, ZoneCollision>,
+    clock: WorldClock,
+    pathfinder: Pathfinder,
+    items: ItemDatabase,
+    recipes: RecipeBook,
+    loot_tables: LootTables,
+    loot_ownership: Duration,
+    loot_despawn: Duration,
+    inventory_slots: usize,
 }
 
 impl GameWorld {
-    pub fn new() -> Self {
+    pub fn new(config: &GameConfig) -> Self {
+        let items = ItemDatabase::load(&config.items.items_file);
+        let recipes = RecipeBook::load(&config.items.recipes_file, &items);
+        let loot_tables = LootTables::load(&config.loot.loot_file, &items);
+        let zones = zones::load_zones(
+            &config.world.zones_file,
+            config.world.max_players_per_zone,
+            config.world.start_zone,
+        );
+        
         Self {
             players: RwLock::new(HashMap::new()),
-            next_zone_id: 1,
+            objects: RwLock::new(objects::load_world_objects(&config.objects.objects_file)),
+            instances: InstanceManager::new(&zones),
+            zones: RwLock::new(zones),
+            start_zone: config.world.start_zone,
+            instance_empty_timeout: Duration::from_secs(config.world.instance_empty_secs),
+            max_move_speed: config.world.max_move_speed,
+            collision: collision::load_collision(&config.world.collision_file),
+            clock: WorldClock::new(&config.clock),
+            pathfinder: Pathfinder::new(&config.navigation),
+            items,
+            recipes,
+            loot_tables,
+            loot_ownership: Duration::from_secs(config.loot.ownership_secs),
+            loot_despawn: Duration::from_secs(config.loot.despawn_secs),
+            inventory_slots: config.items.inventory_slots,
         }
     }
     
-    // Добавляем игрока в мир
-    pub async fn add_player(&self, player_id: PlayerId, username: String, transform: Transform) {
+    #[allow(dead_code)]
+    pub fn items(&self) -> &ItemDatabase {
+        &self.items
+    }
+    
+    // Игровые часы; подписка на рассвет и закат - clock().subscribe()
+    pub fn clock(&self) -> &WorldClock {
+        &self.clock
+    }
+    
+    // Сервис поиска пути для NPC
+    #[allow(dead_code)]
+    pub fn pathfinder(&self) -> &Pathfinder {
+        &self.pathfinder
+    }
+    
+    // Один тик игрового цикла
+    pub async fn tick(&self) {
+        self.pathfinder.process_tick();
+        self.clock.update().await;
+    }
+    
+    // Добавляем игрока в мир: сохраненного - туда, где он вышел, нового - в точку
+    // появления стартовой зоны. Если зоны сохранения больше нет - тоже в стартовую
+    pub async fn add_player(&self, player_id: PlayerId, username: String, saved: Option<CharacterRecord>) -> Result<(), String> {
         let mut players = self.players.write().await;
+        let mut zones = self.zones.write().await;
+        let saved_zone = saved
+            .as_ref()
+            .map(|record| record.zone_id)
+            .filter(|zone_id| zones.get(zone_id).is_some_and(|zone| !zone.def.instanced));
+        let zone_id = saved_zone.unwrap_or(self.start_zone);
+        let class = saved.as_ref().map_or(PlayerClass::Warrior, |record| record.class); // Выбора класса при логине пока нет
+        let spawn = self.spawn_position(&zones, &players, player_id, zone_id, class);
+        let zone = zones.get_mut(&zone_id).ok_or_else(|| "Start zone does not exist".to_string())?;
+        if zone.is_full() {
+            return Err(format!("{} is full, try again later", zone.def.name));
+        }
+        
+        if let Some(record) = saved {
+            let mut transform = record.transform;
+            if saved_zone.is_none() {
+                transform.position = spawn;
+            }
+            let mut player_state = PlayerState {
+                username,
+                zone_exit: zone.exit_at(&transform.position),
+                party_id: None,
+                transform,
+                zone_id,
+                class: record.class,
+                base_stats: record.base_stats,
+                stats: record.stats,
+                buffs: Vec::new(),
+                inventory: record.inventory,
+                gold: record.gold,
+                equipment: record.equipment,
+                crafting: None,
+                animation: AnimationState::default(),
+                combat_until: None,
+                bind_point: record.bind_point,
+                hearth_ready_at: None,
+                last_move_at: None,
+            };
+            // Характеристики предметов могли поменяться с прошлого входа
+            player_state.recompute_stats(&self.items);
+            
+            zone.players.insert(player_id);
+            players.insert(player_id, player_state);
+            println!("Player {} restored to world (zone: {})", player_id, zone_id);
+            return Ok(());
+        }
+        
+        let transform = Transform {
+            position: spawn,
+            ..Transform::default()
+        };
+        
+        let mut inventory = Inventory::new(self.inventory_slots);
+        for starting_item in self.items.starting_items() {
+            if let Some(template) = self.items.get(starting_item.template_id)
+                && let Err(e) = inventory.add_item(template, starting_item.quantity)
+            {
+                eprintln!("Failed to give starting item {} to {}: {}", template.name, username, e);
+            }
+        }
         
         let player_state = PlayerState {
             username,
+            zone_exit: zone.exit_at(&transform.position),
+            party_id: None,
             transform,
-            zone_id: self.next_zone_id, // Пока все в одной зоне
+            zone_id: self.start_zone,
+            class,
+            base_stats: stats::starting_stats(),
+            stats: stats::starting_stats(),
+            buffs: Vec::new(),
+            inventory,
+            gold: self.items.starting_gold(),
+            equipment: Equipment::default(),
+            crafting: None,
+            animation: AnimationState::default(),
+            combat_until: None,
+            bind_point: None,
+            hearth_ready_at: None,
+            last_move_at: None,
         };
         
+        zone.players.insert(player_id);
         players.insert(player_id, player_state);
-        println!("Player {} added to world (zone: {})", player_id, self.next_zone_id);
+        println!("Player {} added to world (zone: {})", player_id, self.start_zone);
+        Ok(())
+    }
+    
+    // Место появления в зоне: точка для класса, свободная от стен и других игроков
+    fn spawn_position(
+        &self,
+        zones: &HashMap<ZoneId, Zone>,
+        players: &HashMap<PlayerId, PlayerState>,
+        player_id: PlayerId,
+        zone_id: ZoneId,
+        class: PlayerClass,
+    ) -> Vector3 {
+        let Some(zone) = zones.get(&zone_id) else {
+            return Vector3 { x: 0.0, y: 0.0, z: 0.0 };
+        };
+        let points = spawns::spawn_points(&zone.def.spawn_points, class, &zone.def.spawn);
+        self.free_position_near(zones, players, player_id, zone_id, &points)
+    }
+    
+    // Сам игрок место не занимает
+    fn free_position_near(
+        &self,
+        zones: &HashMap<ZoneId, Zone>,
+        players: &HashMap<PlayerId, PlayerState>,
+        player_id: PlayerId,
+        zone_id: ZoneId,
+        points: &[Vector3],
+    ) -> Vector3 {
+        let occupied: Vec<Vector3> = zones
+            .get(&zone_id)
+            .map(|zone| {
+                zone.players
+                    .iter()
+                    .filter(|id| **id != player_id)
+                    .filter_map(|id| players.get(id).map(|state| state.transform.position.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        spawns::free_position(points, &occupied, self.zone_collision(zones, zone_id))
+    }
+    
+    // Куда вернет камень возвращения: точка привязки, если ее зона еще есть, иначе стартовая зона
+    fn hearth_destination(
+        &self,
+        zones: &HashMap<ZoneId, Zone>,
+        players: &HashMap<PlayerId, PlayerState>,
+        player_id: PlayerId,
+    ) -> (ZoneId, Vector3) {
+        let Some(player) = players.get(&player_id) else {
+            return (self.start_zone, Vector3 { x: 0.0, y: 0.0, z: 0.0 });
+        };
+        match &player.bind_point {
+            Some(bind) if zones.get(&bind.zone_id).is_some_and(|zone| !zone.def.instanced) => {
+                let points = std::slice::from_ref(&bind.position);
+                (bind.zone_id, self.free_position_near(zones, players, player_id, bind.zone_id, points))
+            }
+            _ => (self.start_zone, self.spawn_position(zones, players, player_id, self.start_zone, player.class)),
+        }
+    }
+    
+    // Геометрия зоны; копии подземелий используют геометрию шаблона
+    fn zone_collision(&self, zones: &HashMap<ZoneId, Zone>, zone_id: ZoneId) -> Option<&ZoneCollision> {
+        let zone_id = zones
+            .get(&zone_id)
+            .and_then(|zone| zone.instance.as_ref())
+            .map_or(zone_id, |instance| instance.template);
+        self.collision.get(&zone_id)
     }
     
-    // Обновляем позицию игрока
-    pub async fn update_player_position(&self, player_id: PlayerId, transform: Transform) -> Option<Transform> {
+    // Обновляем позицию игрока: не быстрее, чем позволяет погода, ставим на землю и не пускаем сквозь стены
+    pub async fn update_player_position(&self, player_id: PlayerId, transform: Transform) -> Option<MoveOutcome> {
         let mut players = self.players.write().await;
+        let zones = self.zones.read().await;
+        let player_state = players.get_mut(&player_id)?;
+        let now = Instant::now();
         
-        if let Some(player_state) = players.get_mut(&player_id) {
-            let old_transform = player_state.transform.clone();
-            player_state.transform = transform;
-            Some(old_transform)
-        } else {
-            None
+        let mut target = transform.position.clone();
+        let mut limited = false;
+        if self.max_move_speed > 0.0 {
+            let multiplier = zones.get(&player_state.zone_id).map_or(1.0, |zone| zone.weather_info().movement_multiplier);
+            let elapsed = player_state.last_move_at.map_or(MOVE_WINDOW, |at| now.duration_since(at).min(MOVE_WINDOW));
+            let max_distance = self.max_move_speed * multiplier * elapsed.as_secs_f32() + MOVE_TOLERANCE;
+            if let Some(step) = limit_step(&player_state.transform.position, &target, max_distance) {
+                target = step;
+                limited = true;
+            }
         }
+        
+        let Some(collision) = self.zone_collision(&zones, player_state.zone_id) else {
+            player_state.transform = Transform { position: target, ..transform };
+            player_state.last_move_at = Some(now);
+            let transform = player_state.transform.clone();
+            return Some(if limited { MoveOutcome::Corrected(transform) } else { MoveOutcome::Accepted(transform) });
+        };
+        let Some(position) = collision.resolve_move(&player_state.transform.position, &target) else {
+            return Some(MoveOutcome::Rejected(player_state.transform.clone()));
+        };
+        
+        let corrected = limited || (position.z - transform.position.z).abs() > collision::GROUND_TOLERANCE;
+        player_state.transform = Transform { position, ..transform };
+        player_state.last_move_at = Some(now);
+        let transform = player_state.transform.clone();
+        Some(if corrected { MoveOutcome::Corrected(transform) } else { MoveOutcome::Accepted(transform) })
+    }
+    
+    // Телепорт по команде GM: zone_id None - остаться в текущей зоне, лимит зоны не действует
+    pub async fn teleport_player(&self, player_id: PlayerId, zone_id: Option<ZoneId>, position: Vector3) -> Result<ZoneTransfer, String> {
+        let mut players = self.players.write().await;
+        let mut objects = self.objects.write().await;
+        let mut zones = self.zones.write().await;
+        let player = players.get_mut(&player_id).ok_or_else(|| "Player is not in the world".to_string())?;
+        let zone_id = zone_id.unwrap_or(player.zone_id);
+        enter_zone(&self.instances, &mut zones, &mut objects, player_id, player, (zone_id, position), false)
+    }
+    
+    // После движения: вход в область автоматического перехода переносит в другую зону.
+    // Срабатывает один раз при входе в область, а не на каждом шаге внутри нее
+    pub async fn trigger_zone_exit(&self, player_id: PlayerId) -> Option<Result<ZoneTransfer, String>> {
+        {
+            let players = self.players.read().await;
+            let zones = self.zones.read().await;
+            let player = players.get(&player_id)?;
+            let exit = zones.get(&player.zone_id)?.exit_at(&player.transform.position);
+            if exit == player.zone_exit {
+                return None;
+            }
+        }
+        
+        let mut players = self.players.write().await;
+        let mut objects = self.objects.write().await;
+        let mut zones = self.zones.write().await;
+        let player = players.get_mut(&player_id)?;
+        let zone = zones.get(&player.zone_id)?;
+        let exit_index = zone.exit_at(&player.transform.position);
+        if exit_index == player.zone_exit {
+            return None;
+        }
+        player.zone_exit = exit_index;
+        
+        let exit = zone.def.exits.get(exit_index?)?.clone();
+        if !exit.auto || player.is_dead() {
+            return None;
+        }
+        Some(enter_zone(&self.instances, &mut zones, &mut objects, player_id, player, (exit.destination_zone, exit.destination), true))
+    }
+    
+    // Переход по запросу клиента: игрок должен стоять у перехода в эту зону
+    pub async fn request_zone_transfer(&self, player_id: PlayerId, zone_id: ZoneId) -> Result<ZoneTransfer, String> {
+        let mut players = self.players.write().await;
+        let mut objects = self.objects.write().await;
+        let mut zones = self.zones.write().await;
+        let player = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        if player.is_dead() {
+            return Err("You can't do that while dead".to_string());
+        }
+        
+        let exit = zones
+            .get(&player.zone_id)
+            .and_then(|zone| {
+                zone.def
+                    .exits
+                    .iter()
+                    .find(|exit| exit.destination_zone == zone_id && exit.contains(&player.transform.position))
+            })
+            .cloned()
+            .ok_or_else(|| "There is no way to that zone from here".to_string())?;
+        enter_zone(&self.instances, &mut zones, &mut objects, player_id, player, (exit.destination_zone, exit.destination), true)
+    }
+    
+    pub async fn zone_name(&self, zone_id: ZoneId) -> Option<String> {
+        let zones = self.zones.read().await;
+        zones.get(&zone_id).map(|zone| zone.def.name.clone())
+    }
+    
+    // Прыжок, сесть/встать, танец. Возвращает зону и новое состояние для рассылки
+    pub async fn perform_action(&self, player_id: PlayerId, action: &PlayerAction) -> Result<(u32, PlayerUpdate), String> {
+        let mut players = self.players.write().await;
+        let player = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        let now = Instant::now();
+        
+        if player.is_dead() {
+            return Err("You can't do that while dead".to_string());
+        }
+        
+        match action {
+            PlayerAction::Jump => {
+                if !player.animation.can_jump(now) {
+                    return Err("You can't jump yet".to_string());
+                }
+                player.animation.stance = Stance::Standing;
+                player.animation.jump_until = Some(now + animation::JUMP_DURATION);
+            }
+            PlayerAction::Sit => {
+                player.animation.stance = match player.animation.stance {
+                    Stance::Sitting => Stance::Standing,
+                    _ => Stance::Sitting,
+                };
+            }
+            PlayerAction::Dance => {
+                if player.in_combat(now) {
+                    return Err("You can't dance in combat".to_string());
+                }
+                player.animation.stance = match player.animation.stance {
+                    Stance::Dancing => Stance::Standing,
+                    _ => Stance::Dancing,
+                };
+            }
+            _ => return Err(format!("{:?} is not an emote", action)),
+        }
+        
+        Ok((player.zone_id, player.to_update(player_id)))
+    }
+    
+    // Движение поднимает сидящего и прерывает танец
+    pub async fn interrupt_stance(&self, player_id: PlayerId) -> Option<(u32, PlayerUpdate)> {
+        {
+            let players = self.players.read().await;
+            if players.get(&player_id)?.animation.stance == Stance::Standing {
+                return None;
+            }
+        }
+        
+        let mut players = self.players.write().await;
+        let player = players.get_mut(&player_id)?;
+        player.animation.stance = Stance::Standing;
+        Some((player.zone_id, player.to_update(player_id)))
     }
     
     // Получаем состояние игрока
@@ -56,22 +644,1022 @@ impl GameWorld {
         players.get(player_id).cloned()
     }
     
-    // Получаем всех игроков в зоне (пока все в одной зоне)
-    pub async fn get_players_in_zone(&self, zone_id: u32) -> Vec<(PlayerId, PlayerState)> {
+    // Сведения об участниках группы в порядке ids, отключившиеся пропускаются
+    pub async fn get_party_members(&self, ids: &[PlayerId]) -> Vec<PartyMember> {
+        let players = self.players.read().await;
+        ids.iter()
+            .filter_map(|id| players.get(id).map(|state| state.to_party_member(*id)))
+            .collect()
+    }
+    
+    // Полный снимок инвентаря: (вместимость, занятые ячейки)
+    pub async fn get_inventory(&self, player_id: &PlayerId) -> Option<(u32, Vec<InventorySlot>)> {
         let players = self.players.read().await;
         players
+            .get(player_id)
+            .map(|state| (state.inventory.capacity() as u32, state.inventory.occupied_slots()))
+    }
+    
+    // Использование предмета: проверяем, что стопка принадлежит игроку,
+    // применяем эффект и списываем расходуемый предмет под одной блокировкой
+    pub async fn use_item(
+        &self,
+        player_id: PlayerId,
+        item_id: Uuid,
+        target_id: Option<PlayerId>,
+    ) -> Result<ItemUseOutcome, String> {
+        let mut players = self.players.write().await;
+        
+        let user = players.get(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        let (_, stack) = user.inventory.find(&item_id).ok_or_else(|| "You don't have this item".to_string())?;
+        let template = self.items.get(stack.template_id).ok_or_else(|| "Unknown item".to_string())?;
+        let effect = template.effect.clone().ok_or_else(|| format!("{} can't be used", template.name))?;
+        
+        if user.stats.health <= 0 {
+            return Err("You can't use items while dead".to_string());
+        }
+        
+        // Телепорт действует только на самого игрока
+        let target_id = match effect {
+            ItemEffect::Teleport { .. } | ItemEffect::Hearth { .. } => player_id,
+            _ => target_id.unwrap_or(player_id),
+        };
+        
+        if target_id != player_id {
+            let target = players.get(&target_id).ok_or_else(|| "Target not found".to_string())?;
+            if target.zone_id != user.zone_id
+                || collision::distance(&user.transform.position, &target.transform.position) > ITEM_USE_RANGE
+            {
+                return Err("Target is too far away".to_string());
+            }
+            if target.stats.health <= 0 {
+                return Err("Target is dead".to_string());
+            }
+        }
+        
+        let template_id = template.id;
+        let template_name = template.name.clone();
+        let consumable = template.consumable;
+        
+        // Камень возвращения: перезарядка и точка назначения считаются до изменения игрока
+        let hearth = match effect {
+            ItemEffect::Hearth { cooldown_secs } => {
+                let now = Instant::now();
+                let user = players.get(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+                if let Some(ready_at) = user.hearth_ready_at
+                    && ready_at > now
+                {
+                    return Err(format!("{} is not ready yet ({} left)", template_name, format_duration(ready_at - now)));
+                }
+                let zones = self.zones.read().await;
+                let (zone_id, position) = self.hearth_destination(&zones, &players, player_id);
+                Some((zone_id, position, now + Duration::from_secs(cooldown_secs)))
+            }
+            _ => None,
+        };
+        
+        let target = players.get_mut(&target_id).ok_or_else(|| "Target not found".to_string())?;
+        let mut target_stats = None;
+        let mut teleport = None;
+        
+        match effect {
+            ItemEffect::Heal { amount } => {
+                target.stats.health = (target.stats.health + amount).min(target.stats.max_health);
+                target_stats = Some(target.stats.clone());
+            }
+            ItemEffect::RestoreMana { amount } => {
+                target.stats.mana = (target.stats.mana + amount).min(target.stats.max_mana);
+                target_stats = Some(target.stats.clone());
+            }
+            ItemEffect::Buff { stat, amount, duration_secs } => {
+                // Повторное использование обновляет длительность, а не складывает эффект
+                target.buffs.retain(|buff| buff.source_template_id != template_id);
+                target.buffs.push(ActiveBuff {
+                    source_template_id: template_id,
+                    modifier: StatModifier { stat, amount },
+                    expires_at: Instant::now() + Duration::from_secs(duration_secs),
+                });
+                target.recompute_stats(&self.items);
+                target_stats = Some(target.stats.clone());
+            }
+            ItemEffect::Teleport { zone_id, position } => {
+                let mut objects = self.objects.write().await;
+                let mut zones = self.zones.write().await;
+                teleport = Some(enter_zone(&self.instances, &mut zones, &mut objects, target_id, target, (zone_id, position), true)?);
+            }
+            ItemEffect::Hearth { .. } => {
+                if let Some((zone_id, position, ready_at)) = hearth {
+                    let mut objects = self.objects.write().await;
+                    let mut zones = self.zones.write().await;
+                    teleport = Some(enter_zone(&self.instances, &mut zones, &mut objects, target_id, target, (zone_id, position), true)?);
+                    target.hearth_ready_at = Some(ready_at);
+                }
+            }
+        }
+        
+        let mut changed_slots = Vec::new();
+        if consumable {
+            let user = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+            let slot = user.inventory.remove_from_stack(&item_id, 1)?;
+            changed_slots.push(user.inventory.slot_update(slot));
+        }
+        
+        println!("🧪 Player {} used {} on {}", player_id, template_name, target_id);
+        
+        Ok(ItemUseOutcome {
+            changed_slots,
+            target_id,
+            target_stats,
+            teleport,
+        })
+    }
+    
+    pub async fn get_gold(&self, player_id: &PlayerId) -> Option<u64> {
+        let players = self.players.read().await;
+        players.get(player_id).map(|state| state.gold)
+    }
+    
+    // Вложения письма: забираем у отправителя стопки целиком и золото.
+    // Возвращает предметы, измененные ячейки и оставшееся золото
+    pub async fn take_mail_attachments(
+        &self,
+        player_id: PlayerId,
+        item_ids: &[Uuid],
+        gold: u64,
+    ) -> Result<(Vec<MailItem>, Vec<InventorySlot>, u64), String> {
+        let mut players = self.players.write().await;
+        let player = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        
+        if player.gold < gold {
+            return Err("You don't have enough gold".to_string());
+        }
+        for (index, item_id) in item_ids.iter().enumerate() {
+            if item_ids[..index].contains(item_id) {
+                return Err("The same item is attached twice".to_string());
+            }
+            if player.inventory.find(item_id).is_none() {
+                return Err("You don't have this item".to_string());
+            }
+        }
+        
+        let mut items = Vec::new();
+        let mut changed_slots = Vec::new();
+        for item_id in item_ids {
+            if let Some((slot, stack)) = player.inventory.take_stack(item_id) {
+                items.push(MailItem { template_id: stack.template_id, quantity: stack.quantity });
+                changed_slots.push(player.inventory.slot_update(slot));
+            }
+        }
+        player.gold -= gold;
+        Ok((items, changed_slots, player.gold))
+    }
+    
+    // Выдаем вложения письма: либо все целиком, либо ничего
+    pub async fn give_mail_attachments(
+        &self,
+        player_id: PlayerId,
+        items: &[MailItem],
+        gold: u64,
+    ) -> Result<(Vec<InventorySlot>, u64), String> {
+        let mut players = self.players.write().await;
+        let player = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        
+        let new_gold = player.gold.checked_add(gold).ok_or_else(|| "You can't carry that much gold".to_string())?;
+        let mut inventory = player.inventory.clone();
+        let mut changed = BTreeSet::new();
+        for item in items {
+            let template = self
+                .items
+                .get(item.template_id)
+                .ok_or_else(|| format!("Unknown item {}", item.template_id))?;
+            changed.extend(inventory.add_item(template, item.quantity)?);
+        }
+        
+        player.inventory = inventory;
+        player.gold = new_gold;
+        let changed_slots = changed.into_iter().map(|slot| player.inventory.slot_update(slot)).collect();
+        Ok((changed_slots, player.gold))
+    }
+    
+    pub async fn get_equipment(&self, player_id: &PlayerId) -> Option<Vec<EquippedItem>> {
+        let players = self.players.read().await;
+        players.get(player_id).map(|state| state.equipment.to_protocol())
+    }
+    
+    // Надеваем предмет из инвентаря; то, что было в слоте, возвращается на место надетого
+    pub async fn equip_item(&self, player_id: PlayerId, item_id: Uuid) -> Result<EquipOutcome, String> {
+        let mut players = self.players.write().await;
+        let state = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        
+        let (_, stack) = state.inventory.find(&item_id).ok_or_else(|| "You don't have this item".to_string())?;
+        let template = self.items.get(stack.template_id).ok_or_else(|| "Unknown item".to_string())?;
+        let slot = template.slot.ok_or_else(|| format!("{} can't be equipped", template.name))?;
+        
+        if !template.usable_by(state.class) {
+            return Err(format!("{:?} can't equip {}", state.class, template.name));
+        }
+        if state.stats.health <= 0 {
+            return Err("You can't change equipment while dead".to_string());
+        }
+        
+        let template_id = template.id;
+        let (inventory_slot, stack) = state
+            .inventory
+            .take_stack(&item_id)
+            .ok_or_else(|| "You don't have this item".to_string())?;
+        
+        // Освободившаяся ячейка гарантирует место для снятого предмета
+        let mut changed = vec![inventory_slot];
+        if let Some(previous) = state.equipment.equip(slot, stack) {
+            let previous_slot = state.inventory.insert_stack(previous)?;
+            if previous_slot != inventory_slot {
+                changed.push(previous_slot);
+            }
+        }
+        
+        state.recompute_stats(&self.items);
+        
+        Ok(EquipOutcome {
+            changed_slots: changed.into_iter().map(|s| state.inventory.slot_update(s)).collect(),
+            slot,
+            template_id: Some(template_id),
+            stats: state.stats.clone(),
+            zone_id: state.zone_id,
+        })
+    }
+    
+    pub async fn unequip_item(&self, player_id: PlayerId, slot: EquipmentSlot) -> Result<EquipOutcome, String> {
+        let mut players = self.players.write().await;
+        let state = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        
+        if state.equipment.get(slot).is_none() {
+            return Err("Nothing equipped in this slot".to_string());
+        }
+        if !state.inventory.has_free_slot() {
+            return Err("Inventory is full".to_string());
+        }
+        
+        let stack = state.equipment.unequip(slot).ok_or_else(|| "Nothing equipped in this slot".to_string())?;
+        let inventory_slot = state.inventory.insert_stack(stack)?;
+        
+        state.recompute_stats(&self.items);
+        
+        Ok(EquipOutcome {
+            changed_slots: vec![state.inventory.slot_update(inventory_slot)],
+            slot,
+            template_id: None,
+            stats: state.stats.clone(),
+            zone_id: state.zone_id,
+        })
+    }
+    
+    // Снимок зоны для только что вошедшего игрока
+    pub async fn get_zone_snapshot(&self, zone_id: u32) -> (Vec<PlayerUpdate>, Vec<ObjectUpdate>) {
+        let players = self.players.read().await;
+        let objects = self.objects.read().await;
+        
+        let player_updates = players
             .iter()
             .filter(|(_, state)| state.zone_id == zone_id)
-            .map(|(id, state)| (*id, state.clone())) // Используем clone() для PlayerState
+            .map(|(id, state)| state.to_update(*id))
+            .collect();
+        
+        let object_updates = objects
+            .values()
+            .filter(|object| object.zone_id == zone_id)
+            .map(WorldObject::to_update)
+            .collect();
+        
+        (player_updates, object_updates)
+    }
+    
+    // Взаимодействие с дверью, сундуком, рычагом или порталом
+    pub async fn interact(&self, player_id: PlayerId, object_id: Uuid) -> Result<InteractionOutcome, String> {
+        let mut players = self.players.write().await;
+        let mut objects = self.objects.write().await;
+        
+        let player = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        if player.stats.health <= 0 {
+            return Err("You can't do that while dead".to_string());
+        }
+        
+        let object = objects.get_mut(&object_id).ok_or_else(|| "Object not found".to_string())?;
+        if !object.in_range(player.zone_id, &player.transform.position) {
+            return Err(format!("{} is too far away", object.name));
+        }
+        let eye = spatial::eye_position(&player.transform.position);
+        let target = spatial::object_center(&object.transform.position);
+        if !self.line_of_sight(player.zone_id, &eye, &target).await {
+            return Err(format!("You can't see {}", object.name));
+        }
+        let (zone_name, in_instance) = {
+            let zones = self.zones.read().await;
+            let zone = zones.get(&player.zone_id);
+            (
+                zone.map(|zone| zone.def.name.clone()).unwrap_or_default(),
+                zone.is_some_and(|zone| zone.instance.is_some()),
+            )
+        };
+        
+        let mut changed_slots = BTreeSet::new();
+        let mut notice = None;
+        let mut gold_update = None;
+        let mut loot_emptied = false;
+        let mut portal_destination = None;
+        let mut lever_targets = Vec::new();
+        
+        match &mut object.kind {
+            ObjectKind::Door { state, key_item } => {
+                *state = match *state {
+                    DoorState::Open => DoorState::Closed,
+                    DoorState::Closed => DoorState::Open,
+                    DoorState::Locked => match key_item {
+                        Some(key) if player.inventory.count(*key) > 0 => DoorState::Open,
+                        _ => return Err(format!("{} is locked", object.name)),
+                    },
+                };
+            }
+            ObjectKind::Chest { state, contents, respawn, respawn_at } => {
+                if *state == ChestState::Looted {
+                    return Err(format!("{} is empty", object.name));
+                }
+                
+                // Содержимое кладется целиком или не кладется вовсе
+                let mut inventory = player.inventory.clone();
+                for grant in contents.iter() {
+                    let template = self.items.get(grant.template_id).ok_or_else(|| "Unknown item".to_string())?;
+                    changed_slots.extend(inventory.add_item(template, grant.quantity)?);
+                }
+                player.inventory = inventory;
+                
+                *state = ChestState::Looted;
+                *respawn_at = Some(Instant::now() + *respawn);
+            }
+            ObjectKind::Lever { state, targets } => {
+                *state = match *state {
+                    LeverState::Off => LeverState::On,
+                    LeverState::On => LeverState::Off,
+                };
+                lever_targets = targets.clone();
+            }
+            ObjectKind::Portal { state, destination_zone, destination } => {
+                if *state == PortalState::Inactive {
+                    return Err(format!("{} is inactive", object.name));
+                }
+                portal_destination = Some((*destination_zone, destination.clone()));
+            }
+            ObjectKind::Station { .. } => {
+                return Err(format!("{} is a crafting station, use Craft on it", object.name));
+            }
+            ObjectKind::BindStone => {
+                if in_instance {
+                    return Err("You can't bind inside an instance".to_string());
+                }
+                player.bind_point = Some(BindPoint {
+                    zone_id: object.zone_id,
+                    position: player.transform.position.clone(),
+                });
+                notice = Some(format!("You are now bound to {}", zone_name));
+            }
+            ObjectKind::Loot { items, gold, owners, free_at, .. } => {
+                if Instant::now() < *free_at && !owners.contains(&player_id) {
+                    return Err(format!("{} belongs to someone else", object.name));
+                }
+                let new_gold = player.gold.checked_add(*gold).ok_or_else(|| "You can't carry that much gold".to_string())?;
+                
+                // Берем то, что помещается, остальное остается лежать
+                let mut inventory = player.inventory.clone();
+                let mut remaining = items.clone();
+                for grant in remaining.iter_mut() {
+                    let template = self.items.get(grant.template_id).ok_or_else(|| "Unknown item".to_string())?;
+                    let fits = inventory.free_space_for(template).min(grant.quantity);
+                    if fits > 0 {
+                        changed_slots.extend(inventory.add_item(template, fits)?);
+                        grant.quantity -= fits;
+                    }
+                }
+                remaining.retain(|grant| grant.quantity > 0);
+                if changed_slots.is_empty() && *gold == 0 {
+                    return Err("Your inventory is full".to_string());
+                }
+                
+                player.inventory = inventory;
+                *items = remaining;
+                if *gold > 0 {
+                    player.gold = new_gold;
+                    *gold = 0;
+                    gold_update = Some(player.gold);
+                }
+                loot_emptied = items.is_empty();
+            }
+        }
+        
+        println!("🖐️ Player {} interacted with {} ({})", player_id, object.name, object.kind.state_name());
+        
+        let mut object_updates = Vec::new();
+        let mut removed_objects = Vec::new();
+        if loot_emptied {
+            removed_objects.push((object.zone_id, object.id));
+        } else {
+            object_updates.push((object.zone_id, object.to_update()));
+        }
+        for (_, removed_id) in &removed_objects {
+            objects.remove(removed_id);
+        }
+        for target_id in lever_targets {
+            if let Some(target) = objects.get_mut(&target_id)
+                && target.toggle_by_lever()
+            {
+                object_updates.push((target.zone_id, target.to_update()));
+            }
+        }
+        
+        let mut teleport = None;
+        if let Some((zone_id, position)) = portal_destination {
+            let player = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+            let mut zones = self.zones.write().await;
+            teleport = Some(enter_zone(&self.instances, &mut zones, &mut objects, player_id, player, (zone_id, position), true)?);
+        }
+        
+        let player = players.get(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        Ok(InteractionOutcome {
+            object_updates,
+            changed_slots: changed_slots.into_iter().map(|slot| player.inventory.slot_update(slot)).collect(),
+            teleport,
+            notice,
+            removed_objects,
+            gold: gold_update,
+        })
+    }
+    
+    // Проверка станции для рецепта: тип совпадает и игрок рядом
+    fn check_station(
+        objects: &HashMap<Uuid, WorldObject>,
+        player: &PlayerState,
+        station: Option<&str>,
+        station_id: Option<Uuid>,
+    ) -> Result<(), String> {
+        let Some(required) = station else {
+            return Ok(());
+        };
+        
+        let object = station_id
+            .and_then(|id| objects.get(&id))
+            .ok_or_else(|| format!("This recipe requires station: {}", required))?;
+        match &object.kind {
+            ObjectKind::Station { station_type } if station_type == required => {}
+            _ => return Err(format!("This recipe requires station: {}", required)),
+        }
+        if !object.in_range(player.zone_id, &player.transform.position) {
+            return Err(format!("{} is too far away", object.name));
+        }
+        Ok(())
+    }
+    
+    // Список рецептов для станции (или без станции, если station_id не указан)
+    pub async fn get_recipes(&self, player_id: PlayerId, station_id: Option<Uuid>) -> Result<Vec<RecipeInfo>, String> {
+        let players = self.players.read().await;
+        let objects = self.objects.read().await;
+        let player = players.get(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        
+        let station = match station_id {
+            Some(id) => {
+                let object = objects.get(&id).ok_or_else(|| "Object not found".to_string())?;
+                let ObjectKind::Station { station_type } = &object.kind else {
+                    return Err(format!("{} is not a crafting station", object.name));
+                };
+                if !object.in_range(player.zone_id, &player.transform.position) {
+                    return Err(format!("{} is too far away", object.name));
+                }
+                Some(station_type.as_str())
+            }
+            None => None,
+        };
+        
+        Ok(self.recipes.for_station(station).into_iter().map(|recipe| recipe.to_info()).collect())
+    }
+    
+    // Начинаем крафт; ингредиенты списываются только по завершении
+    pub async fn start_craft(
+        &self,
+        player_id: PlayerId,
+        recipe_id: RecipeId,
+        station_id: Option<Uuid>,
+    ) -> Result<u64, String> {
+        let mut players = self.players.write().await;
+        let objects = self.objects.read().await;
+        let player = players.get_mut(&player_id).ok_or_else(|| "Player not in world".to_string())?;
+        let recipe = self.recipes.get(recipe_id).ok_or_else(|| "Unknown recipe".to_string())?;
+        
+        if player.stats.health <= 0 {
+            return Err("You can't craft while dead".to_string());
+        }
+        if player.crafting.is_some() {
+            return Err("You are already crafting".to_string());
+        }
+        Self::check_station(&objects, player, recipe.station.as_deref(), station_id)?;
+        if !recipe.has_inputs(&player.inventory) {
+            return Err(format!("Missing materials for {}", recipe.name));
+        }
+        // Заранее проверяем, что результат поместится
+        recipe.apply(&player.inventory, &self.items)?;
+        
+        let now = Instant::now();
+        player.crafting = Some(ActiveCraft {
+            recipe_id,
+            station_id,
+            started_at: now,
+            finishes_at: now + recipe.craft_time(),
+            last_progress_at: now,
+        });
+        
+        println!("⚒️ Player {} started crafting {}", player_id, recipe.name);
+        Ok(recipe.craft_time().as_millis() as u64)
+    }
+    
+    pub async fn cancel_craft(&self, player_id: PlayerId) -> Option<RecipeId> {
+        let mut players = self.players.write().await;
+        players
+            .get_mut(&player_id)
+            .and_then(|player| player.crafting.take())
+            .map(|craft| craft.recipe_id)
+    }
+    
+    // Прогресс, завершение и отмена крафта (игрок умер или отошел от станции)
+    pub async fn update_crafting(&self) -> Vec<CraftEvent> {
+        let now = Instant::now();
+        
+        {
+            let players = self.players.read().await;
+            if !players.values().any(|player| player.crafting.is_some()) {
+                return Vec::new();
+            }
+        }
+        
+        let mut players = self.players.write().await;
+        let objects = self.objects.read().await;
+        let mut events = Vec::new();
+        
+        for (player_id, player) in players.iter_mut() {
+            let Some(craft) = player.crafting.clone() else {
+                continue;
+            };
+            let Some(recipe) = self.recipes.get(craft.recipe_id) else {
+                player.crafting = None;
+                continue;
+            };
+            
+            let cancel_reason = if player.stats.health <= 0 {
+                Some("You died".to_string())
+            } else {
+                Self::check_station(&objects, player, recipe.station.as_deref(), craft.station_id).err()
+            };
+            if let Some(reason) = cancel_reason {
+                player.crafting = None;
+                events.push(CraftEvent::Cancelled { player_id: *player_id, recipe_id: recipe.id, reason });
+                continue;
+            }
+            
+            if now < craft.finishes_at {
+                if now.duration_since(craft.last_progress_at) >= crafting::PROGRESS_INTERVAL {
+                    if let Some(active) = player.crafting.as_mut() {
+                        active.last_progress_at = now;
+                    }
+                    events.push(CraftEvent::Progress {
+                        player_id: *player_id,
+                        recipe_id: recipe.id,
+                        progress: craft.progress(now),
+                    });
+                }
+                continue;
+            }
+            
+            player.crafting = None;
+            match recipe.apply(&player.inventory, &self.items) {
+                Ok((inventory, changed)) => {
+                    player.inventory = inventory;
+                    println!("⚒️ Player {} crafted {}", player_id, recipe.name);
+                    events.push(CraftEvent::Completed {
+                        player_id: *player_id,
+                        recipe_id: recipe.id,
+                        changed_slots: changed.into_iter().map(|slot| player.inventory.slot_update(slot)).collect(),
+                    });
+                }
+                Err(reason) => {
+                    events.push(CraftEvent::Cancelled { player_id: *player_id, recipe_id: recipe.id, reason });
+                }
+            }
+        }
+        
+        events
+    }
+    
+    // Возвращаем разграбленные сундуки по таймеру
+    pub async fn update_objects(&self) -> Vec<(u32, ObjectUpdate)> {
+        let now = Instant::now();
+        let is_due = |object: &WorldObject| {
+            matches!(object.kind, ObjectKind::Chest { respawn_at: Some(at), .. } if at <= now)
+        };
+        
+        {
+            let objects = self.objects.read().await;
+            if !objects.values().any(is_due) {
+                return Vec::new();
+            }
+        }
+        
+        let mut objects = self.objects.write().await;
+        let mut updates = Vec::new();
+        for object in objects.values_mut() {
+            if !is_due(object) {
+                continue;
+            }
+            if let ObjectKind::Chest { state, respawn_at, .. } = &mut object.kind {
+                *state = ChestState::Closed;
+                *respawn_at = None;
+            }
+            updates.push((object.zone_id, object.to_update()));
+        }
+        updates
+    }
+    
+    // Добыча из таблицы table_id ложится на землю объектом; вызывается при смерти NPC.
+    // Первые ownership_secs поднять ее могут только owners. None - ничего не выпало
+    pub async fn drop_loot(
+        &self,
+        zone_id: ZoneId,
+        position: &Vector3,
+        table_id: &str,
+        owners: Vec<PlayerId>,
+    ) -> Result<Option<ObjectUpdate>, String> {
+        let drop = self.loot_tables.roll(table_id).ok_or_else(|| format!("Unknown loot table '{}'", table_id))?;
+        if drop.is_empty() {
+            return Ok(None);
+        }
+        
+        let mut objects = self.objects.write().await;
+        let zones = self.zones.read().await;
+        if !zones.contains_key(&zone_id) {
+            return Err(format!("Zone {} does not exist", zone_id));
+        }
+        let position = match self.zone_collision(&zones, zone_id) {
+            Some(collision) => collision.snap_to_ground(position),
+            None => position.clone(),
+        };
+        
+        let now = Instant::now();
+        let kind = ObjectKind::Loot {
+            items: drop.items,
+            gold: drop.gold,
+            owners,
+            free_at: now + self.loot_ownership,
+            despawn_at: now + self.loot_despawn,
+        };
+        let object = WorldObject::dropped("Loot", zone_id, position, kind);
+        let update = object.to_update();
+        println!("[{}] 💰 Loot from table {} dropped in zone {}",
+            chrono::Local::now().format("%H:%M:%S"),
+            table_id, zone_id
+        );
+        objects.insert(object.id, object);
+        Ok(Some(update))
+    }
+    
+    // Истлевшая добыча убирается из мира. Возвращает (зона, объект) убранной
+    pub async fn update_loot(&self) -> Vec<(ZoneId, Uuid)> {
+        let now = Instant::now();
+        let is_expired = |object: &WorldObject| {
+            matches!(object.kind, ObjectKind::Loot { despawn_at, .. } if despawn_at <= now)
+        };
+        
+        {
+            let objects = self.objects.read().await;
+            if !objects.values().any(is_expired) {
+                return Vec::new();
+            }
+        }
+        
+        let mut objects = self.objects.write().await;
+        let expired: Vec<(ZoneId, Uuid)> = objects
+            .values()
+            .filter(|object| is_expired(object))
+            .map(|object| (object.zone_id, object.id))
+            .collect();
+        for (_, object_id) in &expired {
+            objects.remove(object_id);
+        }
+        expired
+    }
+    
+    // Сброс копий подземелий: игроков выводим наружу, пустые копии удаляем вместе с объектами
+    pub async fn update_instances(&self) -> Vec<ZoneTransfer> {
+        let now = Instant::now();
+        {
+            let zones = self.zones.read().await;
+            if !zones.values().any(|zone| zone.instance.is_some()) {
+                return Vec::new();
+            }
+        }
+        
+        let mut players = self.players.write().await;
+        let mut objects = self.objects.write().await;
+        let mut zones = self.zones.write().await;
+        let mut transfers = Vec::new();
+        
+        for instance_id in instances::expired_instances(&mut zones, self.instance_empty_timeout, now) {
+            let (zone_id, position) = instances::eviction_point(&zones, instance_id, self.start_zone);
+            let inside: Vec<PlayerId> = zones[&instance_id].players.iter().copied().collect();
+            for player_id in inside {
+                let Some(player) = players.get_mut(&player_id) else {
+                    continue;
+                };
+                match enter_zone(&self.instances, &mut zones, &mut objects, player_id, player, (zone_id, position.clone()), false) {
+                    Ok(transfer) => transfers.push(transfer),
+                    Err(e) => eprintln!("Failed to move player {} out of instance {}: {}", player_id, instance_id, e),
+                }
+            }
+            instances::destroy_instance(&mut zones, &mut objects, instance_id);
+        }
+        transfers
+    }
+    
+    // Куда сохранить игрока: из копии подземелья - к выходу из нее, копия не переживет выход
+    fn save_position(&self, zones: &HashMap<ZoneId, Zone>, state: &PlayerState) -> (ZoneId, Vector3) {
+        match zones.get(&state.zone_id) {
+            Some(zone) if zone.instance.is_some() => instances::eviction_point(zones, state.zone_id, self.start_zone),
+            _ => (state.zone_id, state.transform.position.clone()),
+        }
+    }
+    
+    // Смена погоды в зонах, у которых вышло время текущей. Возвращает зоны с новой погодой
+    pub async fn update_weather(&self) -> Vec<(ZoneId, WeatherInfo)> {
+        let mut zones = self.zones.write().await;
+        let now = Instant::now();
+        let mut changed = Vec::new();
+        for (zone_id, zone) in zones.iter_mut() {
+            let Some(state) = zone.weather.as_mut() else {
+                continue;
+            };
+            if state.advance(&zone.def.weather, now) {
+                let info = zone.weather_info();
+                println!("[{}] 🌦️ Weather in {} is now {}",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    zone.def.name, weather::kind_name(info.kind)
+                );
+                changed.push((*zone_id, info));
+            }
+        }
+        changed
+    }
+    
+    // Текущая погода зоны; без настроенной погоды - ясно
+    pub async fn zone_weather(&self, zone_id: ZoneId) -> WeatherInfo {
+        let zones = self.zones.read().await;
+        zones.get(&zone_id).map_or_else(weather::clear_weather, Zone::weather_info)
+    }
+    
+    // Сменить погоду зоны вручную (команда GM); дальше она меняется как обычно
+    pub async fn set_weather(&self, zone_id: ZoneId, kind: WeatherKind) -> Result<WeatherInfo, String> {
+        let mut zones = self.zones.write().await;
+        let zone = zones.get_mut(&zone_id).ok_or_else(|| format!("Zone {} does not exist", zone_id))?;
+        let state = zone.weather.as_mut().ok_or_else(|| format!("{} has no weather", zone.def.name))?;
+        if !state.force(&zone.def.weather, kind, Instant::now()) {
+            return Err(format!("{} never has {} weather", zone.def.name, weather::kind_name(kind)));
+        }
+        Ok(zone.weather_info())
+    }
+    
+    // Снимок времени суток и погоды постоянных зон для сохранения
+    pub async fn world_state(&self) -> WorldState {
+        let clock_hours = self.clock.elapsed_hours().await;
+        let zones = self.zones.read().await;
+        let mut weather: Vec<ZoneWeatherState> = zones
+            .iter()
+            .filter(|(_, zone)| zone.instance.is_none() && zone.weather.is_some())
+            .map(|(zone_id, zone)| ZoneWeatherState { zone_id: *zone_id, kind: zone.weather_info().kind })
+            .collect();
+        weather.sort_by_key(|state| state.zone_id);
+        WorldState::new(clock_hours, weather)
+    }
+    
+    // Продолжаем с сохраненного состояния. Погоду, которой у зоны больше нет в настройках, пропускаем
+    pub async fn restore_world_state(&self, state: &WorldState) {
+        self.clock.restore(state.clock_hours).await;
+        let mut zones = self.zones.write().await;
+        let now = Instant::now();
+        for saved in &state.weather {
+            let Some(zone) = zones.get_mut(&saved.zone_id) else {
+                continue;
+            };
+            if let Some(weather) = zone.weather.as_mut() {
+                weather.force(&zone.def.weather, saved.kind, now);
+            }
+        }
+        println!("🌍 Restored world state saved at {}",
+            chrono::DateTime::from_timestamp(state.saved_at, 0)
+                .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| state.saved_at.to_string())
+        );
+    }
+    
+    pub async fn character_record(&self, player_id: PlayerId, state: &PlayerState) -> CharacterRecord {
+        let zones = self.zones.read().await;
+        let (zone_id, position) = self.save_position(&zones, state);
+        state.to_record(player_id, zone_id, position)
+    }
+    
+    // Все игроки в мире - для периодического сохранения
+    pub async fn character_records(&self) -> Vec<CharacterRecord> {
+        let players = self.players.read().await;
+        let zones = self.zones.read().await;
+        players
+            .iter()
+            .map(|(player_id, state)| {
+                let (zone_id, position) = self.save_position(&zones, state);
+                state.to_record(*player_id, zone_id, position)
+            })
+            .collect()
+    }
+    
+    // Группа игроков изменилась - от нее зависит, в какую копию подземелья они попадут
+    pub async fn set_party(&self, player_ids: &[PlayerId], party_id: Option<PartyId>) {
+        let mut players = self.players.write().await;
+        for player_id in player_ids {
+            if let Some(player) = players.get_mut(player_id) {
+                player.party_id = party_id;
+            }
+        }
+    }
+    
+    // Снимаем истекшие баффы, возвращаем игроков с изменившимися характеристиками
+    pub async fn expire_buffs(&self) -> Vec<(PlayerId, PlayerStats)> {
+        let now = Instant::now();
+        
+        // Большую часть тиков истекать нечему - не берем блокировку на запись зря
+        {
+            let players = self.players.read().await;
+            if !players.values().any(|state| state.buffs.iter().any(|buff| buff.expires_at <= now)) {
+                return Vec::new();
+            }
+        }
+        
+        let mut players = self.players.write().await;
+        let mut changed = Vec::new();
+        
+        for (player_id, state) in players.iter_mut() {
+            let before = state.buffs.len();
+            state.buffs.retain(|buff| buff.expires_at > now);
+            if state.buffs.len() != before {
+                state.recompute_stats(&self.items);
+                changed.push((*player_id, state.stats.clone()));
+            }
+        }
+        
+        changed
+    }
+    
+    // Получаем всех игроков в зоне
+    #[allow(dead_code)]
+    pub async fn get_players_in_zone(&self, zone_id: ZoneId) -> Vec<(PlayerId, PlayerState)> {
+        let players = self.players.read().await;
+        let zones = self.zones.read().await;
+        let Some(zone) = zones.get(&zone_id) else {
+            return Vec::new();
+        };
+        zone.players
+            .iter()
+            .filter_map(|id| players.get(id).map(|state| (*id, state.clone())))
+            .collect()
+    }
+    
+    // Только id игроков зоны - для рассылок
+    pub async fn get_zone_player_ids(&self, zone_id: ZoneId) -> Vec<PlayerId> {
+        let zones = self.zones.read().await;
+        zones
+            .get(&zone_id)
+            .map(|zone| zone.players.iter().copied().collect())
+            .unwrap_or_default()
+    }
+    
+    // Все игроки в мире: (id, имя, зона)
+    pub async fn list_players(&self) -> Vec<(PlayerId, String, u32)> {
+        let players = self.players.read().await;
+        players
+            .iter()
+            .map(|(id, state)| (*id, state.username.clone(), state.zone_id))
             .collect()
     }
     
+    // Игроки зоны в радиусе от точки (для локального чата); в плохую погоду радиус меньше
+    pub async fn get_players_in_radius(&self, zone_id: ZoneId, center: &Vector3, radius: f32) -> Vec<PlayerId> {
+        let players = self.players.read().await;
+        let zones = self.zones.read().await;
+        let Some(zone) = zones.get(&zone_id) else {
+            return Vec::new();
+        };
+        let radius = radius * zone.weather_info().visibility;
+        zone.players
+            .iter()
+            .filter(|id| {
+                players.get(id).is_some_and(|state| {
+                    let p = &state.transform.position;
+                    let (dx, dy, dz) = (p.x - center.x, p.y - center.y, p.z - center.z);
+                    dx * dx + dy * dy + dz * dz <= radius * radius
+                })
+            })
+            .copied()
+            .collect()
+    }
+    
+    // Игроки и объекты зоны, подходящие под фильтр
+    fn query_candidates(
+        players: &HashMap<PlayerId, PlayerState>,
+        objects: &HashMap<Uuid, WorldObject>,
+        zones: &HashMap<ZoneId, Zone>,
+        zone_id: ZoneId,
+        filter: &QueryFilter,
+    ) -> Vec<Candidate> {
+        let mut candidates = Vec::new();
+        if filter.players && let Some(zone) = zones.get(&zone_id) {
+            candidates.extend(zone.players.iter().filter_map(|id| {
+                players.get(id).map(|state| Candidate {
+                    entity: EntityRef::Player(*id),
+                    position: state.transform.position.clone(),
+                })
+            }));
+        }
+        if filter.objects {
+            candidates.extend(objects.values().filter(|object| object.zone_id == zone_id).map(|object| Candidate {
+                entity: EntityRef::Object(object.id),
+                position: object.transform.position.clone(),
+            }));
+        }
+        candidates.retain(|candidate| filter.accepts(&candidate.entity));
+        candidates
+    }
+    
+    // Первое, во что упирается отрезок from -> to в зоне: игрок, объект или стена/земля
+    #[allow(dead_code)]
+    pub async fn raycast(&self, zone_id: ZoneId, from: &Vector3, to: &Vector3, filter: QueryFilter) -> Option<RayHit> {
+        let players = self.players.read().await;
+        let objects = self.objects.read().await;
+        let zones = self.zones.read().await;
+        let candidates = Self::query_candidates(&players, &objects, &zones, zone_id, &filter);
+        spatial::raycast(self.zone_collision(&zones, zone_id), &candidates, from, to, &filter)
+    }
+    
+    // Прямая видимость между точками: мешают только стены и земля
+    pub async fn line_of_sight(&self, zone_id: ZoneId, from: &Vector3, to: &Vector3) -> bool {
+        let zones = self.zones.read().await;
+        spatial::line_of_sight(self.zone_collision(&zones, zone_id), from, to)
+    }
+    
+    // Сущности, задевающие сферу
+    #[allow(dead_code)]
+    pub async fn overlap_sphere(&self, zone_id: ZoneId, center: &Vector3, radius: f32, filter: QueryFilter) -> Vec<EntityRef> {
+        let players = self.players.read().await;
+        let objects = self.objects.read().await;
+        let zones = self.zones.read().await;
+        let candidates = Self::query_candidates(&players, &objects, &zones, zone_id, &filter);
+        spatial::overlap_sphere(&candidates, center, radius)
+    }
+    
+    // Сущности, задевающие коробку
+    #[allow(dead_code)]
+    pub async fn overlap_box(&self, zone_id: ZoneId, bounds: &Aabb, filter: QueryFilter) -> Vec<EntityRef> {
+        let players = self.players.read().await;
+        let objects = self.objects.read().await;
+        let zones = self.zones.read().await;
+        let candidates = Self::query_candidates(&players, &objects, &zones, zone_id, &filter);
+        spatial::overlap_box(&candidates, bounds)
+    }
+    
+    // Ближайшая сущность к точке; с filter.geometry - только те, что видны из точки
+    #[allow(dead_code)]
+    pub async fn nearest_entity(
+        &self,
+        zone_id: ZoneId,
+        point: &Vector3,
+        max_distance: f32,
+        filter: QueryFilter,
+    ) -> Option<(EntityRef, f32)> {
+        let players = self.players.read().await;
+        let objects = self.objects.read().await;
+        let zones = self.zones.read().await;
+        let candidates = Self::query_candidates(&players, &objects, &zones, zone_id, &filter);
+        spatial::nearest(self.zone_collision(&zones, zone_id), &candidates, point, max_distance, &filter)
+    }
+    
     // Удаляем игрока из мира
     pub async fn remove_player(&self, player_id: &PlayerId) -> Option<PlayerState> {
         let mut players = self.players.write().await;
+        let mut zones = self.zones.write().await;
         let removed = players.remove(player_id);
         
-        if removed.is_some() {
+        if let Some(state) = &removed {
+            if let Some(zone) = zones.get_mut(&state.zone_id) {
+                zone.players.remove(player_id);
+            }
             println!("Player {} removed from world", player_id);
         }
         
@@ -79,6 +1667,7 @@ impl GameWorld {
     }
     
     // Получаем количество игроков в мире
+    #[allow(dead_code)]
     pub async fn player_count(&self) -> usize {
         let players = self.players.read().await;
         players.len()
@@ -87,6 +1676,26 @@ impl GameWorld {
 
 impl Default for GameWorld {
     fn default() -> Self {
-        Self::new()
+        Self::new(&ServerConfig::default().game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32) -> Vector3 {
+        Vector3 { x, y, z: 0.0 }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn limit_step_shortens_long_moves_along_the_same_line() {
+        assert!(limit_step(&point(0.0, 0.0), &point(3.0, 4.0), 5.0).is_none());
+
+        let step = limit_step(&point(0.0, 0.0), &point(30.0, 40.0), 5.0).unwrap();
+        assert!((step.x - 3.0).abs() < 1e-4 && (step.y - 4.0).abs() < 1e-4);
+
+        let step = limit_step(&point(10.0, 10.0), &point(10.0, 0.0), 2.5).unwrap();
+        assert!((step.x - 10.0).abs() < 1e-4 && (step.y - 7.5).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::protocol::WeatherKind;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use super::storage::{self, Collection, Storage};
+use super::zones::ZoneId;
+
+// У мира одна запись состояния
+const WORLD_STATE_ID: &str = "world";
+
+// Погода постоянной зоны; копии подземелий не сохраняются
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneWeatherState {
+    pub zone_id: ZoneId,
+    pub kind: WeatherKind,
+}
+
+// Состояние мира между перезапусками: время суток и погода по зонам
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldState {
+    pub id: String,
+    pub clock_hours: f64,
+    pub weather: Vec<ZoneWeatherState>,
+    pub saved_at: i64,
+}
+
+impl WorldState {
+    pub fn new(clock_hours: f64, weather: Vec<ZoneWeatherState>) -> Self {
+        Self {
+            id: WORLD_STATE_ID.to_string(),
+            clock_hours,
+            weather,
+            saved_at: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WorldStateStore {
+    storage: Arc<dyn Storage>,
+    save_lock: Mutex<()>,
+}
+
+impl WorldStateStore {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            storage,
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    // Сохраненное состояние; None - мир запускается впервые
+    pub fn load(&self) -> Option<WorldState> {
+        storage::load::<WorldState>(self.storage.as_ref(), Collection::WorldState)
+            .into_iter()
+            .find(|state| state.id == WORLD_STATE_ID)
+    }
+
+    pub async fn store(&self, state: WorldState) {
+        let _guard = self.save_lock.lock().await;
+        if let Err(e) = storage::save(&self.storage, Collection::WorldState, &[state]).await {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::storage::MemoryStorage;
+
+    #[tokio::test]
+    async fn state_round_trips_through_storage() {
+        let store = WorldStateStore::new(Arc::new(MemoryStorage::new()));
+        assert!(store.load().is_none());
+
+        let weather = vec![ZoneWeatherState { zone_id: 2, kind: WeatherKind::Storm }];
+        store.store(WorldState::new(30.5, weather)).await;
+        store.store(WorldState::new(31.0, Vec::new())).await;
+
+        let state = store.load().unwrap();
+        assert_eq!(state.clock_hours, 31.0);
+        assert!(state.weather.is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+use crate::protocol::{Vector3, WeatherInfo};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::time::Instant;
+use super::instances::{Instance, Lockout};
+use super::session::PlayerId;
+use super::spawns::SpawnPoint;
+use super::weather::{self, WeatherDef, ZoneWeather};
+
+pub type ZoneId = u32;
+
+const DEFAULT_EXIT_RADIUS: f32 = 3.0;
+const DEFAULT_RESET_SECS: u64 = 3600;
+
+// Переход в другую зону: область вокруг position
+#[derive(Debug, Clone, Deserialize)]
+pub struct ZoneExit {
+    pub position: Vector3,
+    #[serde(default = "default_exit_radius")]
+    pub radius: f32,
+    pub destination_zone: ZoneId,
+    pub destination: Vector3,
+    // true - срабатывает при входе в область, false - только по ZoneTransferRequest
+    #[serde(default = "default_auto")]
+    pub auto: bool,
+}
+
+fn default_exit_radius() -> f32 {
+    DEFAULT_EXIT_RADIUS
+}
+
+fn default_auto() -> bool {
+    true
+}
+
+fn default_reset_secs() -> u64 {
+    DEFAULT_RESET_SECS
+}
+
+impl ZoneExit {
+    pub fn contains(&self, position: &Vector3) -> bool {
+        let (dx, dy, dz) = (
+            position.x - self.position.x,
+            position.y - self.position.y,
+            position.z - self.position.z,
+        );
+        dx * dx + dy * dy + dz * dz <= self.radius * self.radius
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ZoneDef {
+    pub id: ZoneId,
+    pub name: String,
+    pub spawn: Vector3,
+    // Точки появления новых персонажей; без них - spawn
+    #[serde(default)]
+    pub spawn_points: Vec<SpawnPoint>,
+    // Если не задано - WorldConfig::max_players_per_zone
+    pub max_players: Option<usize>,
+    #[serde(default)]
+    pub exits: Vec<ZoneExit>,
+    // Подземелье: каждая группа получает свою копию зоны с объектами
+    #[serde(default)]
+    pub instanced: bool,
+    // Через сколько копия сбрасывается, на это же время игрок привязан к ней
+    #[serde(default = "default_reset_secs")]
+    pub reset_secs: u64,
+    // Состояния погоды; без них в зоне всегда ясно
+    #[serde(default)]
+    pub weather: Vec<WeatherDef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ZoneDataFile {
+    #[serde(default)]
+    zones: Vec<ZoneDef>,
+}
+
+// Зона вместе с игроками, которые сейчас в ней находятся
+#[derive(Debug)]
+pub struct Zone {
+    pub def: ZoneDef,
+    pub max_players: usize,
+    pub players: HashSet<PlayerId>,
+    pub instance: Option<Instance>, // Some - это копия подземелья
+    pub lockouts: HashMap<PlayerId, Lockout>, // у шаблона подземелья: кто к какой копии привязан
+    pub weather: Option<ZoneWeather>,
+}
+
+impl Zone {
+    pub fn new(def: ZoneDef, max_players: usize) -> Self {
+        let weather = ZoneWeather::start(&def.weather, Instant::now());
+        Self {
+            def,
+            max_players,
+            players: HashSet::new(),
+            instance: None,
+            lockouts: HashMap::new(),
+            weather,
+        }
+    }
+
+    pub fn weather_info(&self) -> WeatherInfo {
+        match &self.weather {
+            Some(state) => self.def.weather[state.current].info(),
+            None => weather::clear_weather(),
+        }
+    }
+
+    // Шаблон подземелья: в него самого не входят, только в копии
+    pub fn is_instance_template(&self) -> bool {
+        self.def.instanced && self.instance.is_none()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= self.max_players
+    }
+
+    // Индекс перехода, в области которого стоит игрок
+    pub fn exit_at(&self, position: &Vector3) -> Option<usize> {
+        self.def.exits.iter().position(|exit| exit.contains(position))
+    }
+}
+
+pub fn load_zones(path: &str, default_max_players: u32, start_zone: ZoneId) -> HashMap<ZoneId, Zone> {
+    let defs = match fs::read_to_string(path) {
+        Ok(content) => match toml::from_str::<ZoneDataFile>(&content) {
+            Ok(file) => file.zones,
+            Err(e) => {
+                eprintln!("Failed to parse zone data {}: {}", path, e);
+                Vec::new()
+            }
+        },
+        Err(_) => {
+            println!("Zone data not found at {}, using a single default zone", path);
+            Vec::new()
+        }
+    };
+
+    let mut zones = HashMap::new();
+    for mut def in defs {
+        weather::validate(def.id, &mut def.weather);
+        if zones.contains_key(&def.id) {
+            eprintln!("Duplicate zone id {} in {}, skipping", def.id, path);
+            continue;
+        }
+        let max_players = def.max_players.unwrap_or(default_max_players as usize);
+        zones.insert(def.id, Zone::new(def, max_players));
+    }
+
+    // Без стартовой зоны игрокам некуда заходить
+    zones.entry(start_zone).or_insert_with(|| {
+        eprintln!("Start zone {} is not defined, creating an empty one", start_zone);
+        let def = ZoneDef {
+            id: start_zone,
+            name: format!("Zone {}", start_zone),
+            spawn: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+            spawn_points: Vec::new(),
+            max_players: None,
+            exits: Vec::new(),
+            instanced: false,
+            reset_secs: DEFAULT_RESET_SECS,
+            weather: Vec::new(),
+        };
+        Zone::new(def, default_max_players as usize)
+    });
+    if let Some(zone) = zones.get_mut(&start_zone)
+        && zone.def.instanced
+    {
+        eprintln!("Start zone {} can't be instanced, ignoring the flag", start_zone);
+        zone.def.instanced = false;
+    }
+
+    // Переходы в несуществующие зоны отбрасываем
+    let ids: HashSet<ZoneId> = zones.keys().copied().collect();
+    for zone in zones.values_mut() {
+        let zone_id = zone.def.id;
+        zone.def.exits.retain(|exit| {
+            let known = ids.contains(&exit.destination_zone);
+            if !known {
+                eprintln!("Zone {} has an exit to unknown zone {}, skipping", zone_id, exit.destination_zone);
+            }
+            known
+        });
+    }
+
+    println!("🗺️ Loaded {} zones", zones.len());
+    zones
+}
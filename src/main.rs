@@ -9,7 +9,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Starting MMORPG Server...");
     
     let config = config::ServerConfig::load_config()?;
-    let mut server = network::GameServer::new(config);
+    let mut server = network::GameServer::new(config)?;
     server.start().await?;
     
     Ok(())
@@ -0,0 +1,214 @@
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+use crate::protocol::{ChatChannel, PlayerId, ServerMessage};
+use super::commands::execute_command;
+use super::context::ServerContext;
+
+// Маршрутизация сообщения чата по каналу - общая для обычного и ручного разбора
+pub async fn handle_chat_message(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    channel: ChatChannel,
+    message: String,
+    target_id: Option<Uuid>,
+) {
+    println!("[{}] 💬 {:?} chat from {}: {} (target: {:?})",
+        chrono::Local::now().format("%H:%M:%S"),
+        channel, player_id, message, target_id
+    );
+    
+    // Сообщения с '/' - серверные команды, в чат они не попадают
+    if let Some(command_line) = message.trim_start().strip_prefix('/') {
+        execute_command(ctx, message_tx, player_id, command_line).await;
+        return;
+    }
+    
+    let Some(player_state) = ctx.game_world.get_player_state(&player_id).await else {
+        println!("❌ Player state not found for {}", player_id);
+        return;
+    };
+    
+    let Some(message) = moderate(ctx, message_tx, &player_state.username, &message) else {
+        return;
+    };
+    
+    if let ChatChannel::Whisper = channel {
+        let Some(target_id) = target_id else {
+            message_tx.send(ServerMessage::ChatError {
+                reason: "Whisper requires a target player".to_string(),
+            }).ok();
+            return;
+        };
+        send_whisper(ctx, message_tx, player_id, WhisperTarget::Id(target_id), message).await;
+        return;
+    }
+    
+    let chat_message = ServerMessage::ChatMessage {
+        from_player: player_state.username.clone(),
+        channel: channel.clone(),
+        message,
+    };
+    
+    match channel {
+        ChatChannel::Global => {
+            ctx.session_manager.broadcast_except(&player_id, &chat_message).await;
+        }
+        ChatChannel::Local | ChatChannel::Yell => {
+            let radius = if matches!(channel, ChatChannel::Yell) {
+                ctx.chat_config.yell_radius
+            } else {
+                ctx.chat_config.say_radius
+            };
+            let recipients: Vec<PlayerId> = ctx
+                .game_world
+                .get_players_in_radius(player_state.zone_id, &player_state.transform.position, radius)
+                .await
+                .into_iter()
+                .filter(|id| *id != player_id)
+                .collect();
+            ctx.session_manager.send_to_players(&recipients, &chat_message).await;
+        }
+        ChatChannel::Party => {
+            let Some(party) = ctx.parties.get_party(&player_id).await else {
+                message_tx.send(ServerMessage::ChatError {
+                    reason: "You are not in a party".to_string(),
+                }).ok();
+                return;
+            };
+            let recipients: Vec<PlayerId> = party.members.into_iter().filter(|id| *id != player_id).collect();
+            ctx.session_manager.send_to_players(&recipients, &chat_message).await;
+        }
+        ChatChannel::Guild => {
+            let Some(guild) = ctx.guilds.get_guild(&player_state.username).await else {
+                message_tx.send(ServerMessage::ChatError {
+                    reason: "You are not in a guild".to_string(),
+                }).ok();
+                return;
+            };
+            let recipients: Vec<PlayerId> = ctx
+                .session_manager
+                .find_online(&guild.member_names())
+                .await
+                .into_iter()
+                .map(|(id, _)| id)
+                .filter(|id| *id != player_id)
+                .collect();
+            ctx.session_manager.send_to_players(&recipients, &chat_message).await;
+        }
+        ChatChannel::Whisper => {} // обработан выше
+    }
+}
+
+// Кому адресован шепот
+pub enum WhisperTarget {
+    Id(PlayerId),
+    Name(String),
+    Reply,
+}
+
+// Прогоняем сообщение через модерацию; None - сообщение отклонено, отправителю ушел ChatError
+fn moderate(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    username: &str,
+    message: &str,
+) -> Option<String> {
+    match ctx.moderation.check_message(username, message) {
+        Ok(message) => Some(message),
+        Err(reason) => {
+            println!("🚫 Chat message from {} rejected: {}", username, reason);
+            message_tx.send(ServerMessage::ChatError { reason }).ok();
+            None
+        }
+    }
+}
+
+pub async fn handle_whisper(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    target: WhisperTarget,
+    message: String,
+) {
+    let Some(session) = ctx.session_manager.get_session(&player_id).await else {
+        return;
+    };
+    if let Some(message) = moderate(ctx, message_tx, &session.username, &message) {
+        send_whisper(ctx, message_tx, player_id, target, message).await;
+    }
+}
+
+async fn send_whisper(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    target: WhisperTarget,
+    message: String,
+) {
+    match resolve_whisper_target(ctx, player_id, target).await {
+        Ok(target_id) => {
+            let Some(sender) = ctx.session_manager.get_session(&player_id).await else {
+                return;
+            };
+            let chat_message = ServerMessage::ChatMessage {
+                from_player: sender.username,
+                channel: ChatChannel::Whisper,
+                message,
+            };
+            
+            println!("🤫 Sending whisper to {}", target_id);
+            if ctx.session_manager.send_to_player(&target_id, chat_message.clone()).await.is_ok() {
+                ctx.session_manager.set_last_whisper_from(&target_id, player_id).await;
+                // Копия отправителю
+                message_tx.send(chat_message).ok();
+            } else {
+                message_tx.send(ServerMessage::ChatError {
+                    reason: "Player is not online".to_string(),
+                }).ok();
+            }
+        }
+        Err(reason) => {
+            println!("❌ Whisper failed: {}", reason);
+            message_tx.send(ServerMessage::ChatError { reason }).ok();
+        }
+    }
+}
+
+async fn resolve_whisper_target(ctx: &ServerContext, player_id: PlayerId, target: WhisperTarget) -> Result<PlayerId, String> {
+    let target_id = match target {
+        WhisperTarget::Id(target_id) => {
+            if ctx.session_manager.get_session(&target_id).await.is_none() {
+                return Err("Player not found".to_string());
+            }
+            target_id
+        }
+        WhisperTarget::Name(name) => {
+            let name = name.trim();
+            match ctx.session_manager.find_by_name(name).await {
+                Some((target_id, _)) => target_id,
+                None if ctx.accounts.find(name).await.is_some() => {
+                    return Err(format!("Player '{}' is offline", name));
+                }
+                None => return Err(format!("No such player '{}'", name)),
+            }
+        }
+        WhisperTarget::Reply => {
+            let last = ctx
+                .session_manager
+                .get_session(&player_id)
+                .await
+                .and_then(|session| session.last_whisper_from)
+                .ok_or_else(|| "Nobody has whispered to you yet".to_string())?;
+            if ctx.session_manager.get_session(&last).await.is_none() {
+                return Err("That player is no longer online".to_string());
+            }
+            last
+        }
+    };
+    
+    if target_id == player_id {
+        return Err("You can't whisper to yourself".to_string());
+    }
+    Ok(target_id)
+}
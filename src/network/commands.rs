@@ -0,0 +1,939 @@
+use rand::Rng;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use crate::game::GameSession;
+use crate::game::bans::{Ban, BanTarget, IpNetwork};
+use crate::game::clock::format_hour;
+use crate::game::moderation::format_duration;
+use crate::game::roles::Role;
+use crate::game::weather;
+use crate::protocol::{PlayerId, ServerMessage, Vector3};
+use super::context::ServerContext;
+use super::zones::{broadcast_to_zone, send_zone_transfer};
+
+const MAX_WHO_RESULTS: usize = 50;
+const MAX_ANNOUNCEMENT_LENGTH: usize = 512;
+
+#[derive(Debug, Clone, Copy)]
+enum CommandId {
+    Help,
+    Who,
+    Roll,
+    Played,
+    Location,
+    Time,
+    Weather,
+    Kick,
+    Mute,
+    Unmute,
+    Ban,
+    Unban,
+    BanIp,
+    UnbanIp,
+    Bans,
+    Teleport,
+    Goto,
+    Summon,
+    Broadcast,
+    SetRole,
+    SetTime,
+    SetWeather,
+    DropLoot,
+}
+
+// Описание серверной команды чата
+pub struct Command {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub role: Role, // минимальная роль для использования
+    id: CommandId,
+}
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        aliases: &["commands", "?"],
+        usage: "/help [command]",
+        help: "List available commands or show help for one command",
+        role: Role::Player,
+        id: CommandId::Help,
+    },
+    Command {
+        name: "who",
+        aliases: &["online"],
+        usage: "/who [name]",
+        help: "List online players, optionally filtered by name",
+        role: Role::Player,
+        id: CommandId::Who,
+    },
+    Command {
+        name: "roll",
+        aliases: &["random"],
+        usage: "/roll [max] or /roll <min>-<max>",
+        help: "Roll a random number (1-100 by default), nearby players see the result",
+        role: Role::Player,
+        id: CommandId::Roll,
+    },
+    Command {
+        name: "played",
+        aliases: &[],
+        usage: "/played",
+        help: "Show how long you have been playing this session",
+        role: Role::Player,
+        id: CommandId::Played,
+    },
+    Command {
+        name: "location",
+        aliases: &["loc", "pos"],
+        usage: "/location",
+        help: "Show your current zone and position",
+        role: Role::Player,
+        id: CommandId::Location,
+    },
+    Command {
+        name: "time",
+        aliases: &[],
+        usage: "/time",
+        help: "Show the in-game day and time",
+        role: Role::Player,
+        id: CommandId::Time,
+    },
+    Command {
+        name: "weather",
+        aliases: &[],
+        usage: "/weather",
+        help: "Show the weather in your zone",
+        role: Role::Player,
+        id: CommandId::Weather,
+    },
+    Command {
+        name: "kick",
+        aliases: &[],
+        usage: "/kick <player> [reason]",
+        help: "Disconnect a player",
+        role: Role::Moderator,
+        id: CommandId::Kick,
+    },
+    Command {
+        name: "mute",
+        aliases: &[],
+        usage: "/mute <player> <duration> [reason]",
+        help: "Mute a player in chat, duration like 30s, 10m, 2h, 1d",
+        role: Role::Moderator,
+        id: CommandId::Mute,
+    },
+    Command {
+        name: "unmute",
+        aliases: &[],
+        usage: "/unmute <player>",
+        help: "Remove a chat mute",
+        role: Role::Moderator,
+        id: CommandId::Unmute,
+    },
+    Command {
+        name: "ban",
+        aliases: &[],
+        usage: "/ban <player> <duration|perm> [reason]",
+        help: "Ban an account temporarily or permanently and disconnect it",
+        role: Role::Moderator,
+        id: CommandId::Ban,
+    },
+    Command {
+        name: "unban",
+        aliases: &[],
+        usage: "/unban <player>",
+        help: "Lift a ban",
+        role: Role::Moderator,
+        id: CommandId::Unban,
+    },
+    Command {
+        name: "banip",
+        aliases: &[],
+        usage: "/banip <ip|cidr|player> <duration|perm> [reason]",
+        help: "Ban an IP address or subnet (or the address of an online player)",
+        role: Role::GameMaster,
+        id: CommandId::BanIp,
+    },
+    Command {
+        name: "unbanip",
+        aliases: &[],
+        usage: "/unbanip <ip|cidr>",
+        help: "Lift an address ban",
+        role: Role::GameMaster,
+        id: CommandId::UnbanIp,
+    },
+    Command {
+        name: "bans",
+        aliases: &["banlist"],
+        usage: "/bans",
+        help: "List active bans",
+        role: Role::Moderator,
+        id: CommandId::Bans,
+    },
+    Command {
+        name: "teleport",
+        aliases: &["tp"],
+        usage: "/teleport [player] <x> <y> <z> [zone]",
+        help: "Teleport yourself or another player to a position",
+        role: Role::GameMaster,
+        id: CommandId::Teleport,
+    },
+    Command {
+        name: "goto",
+        aliases: &[],
+        usage: "/goto <player>",
+        help: "Teleport yourself to a player",
+        role: Role::GameMaster,
+        id: CommandId::Goto,
+    },
+    Command {
+        name: "summon",
+        aliases: &[],
+        usage: "/summon <player>",
+        help: "Teleport a player to you",
+        role: Role::GameMaster,
+        id: CommandId::Summon,
+    },
+    Command {
+        name: "broadcast",
+        aliases: &["announce"],
+        usage: "/broadcast <message>",
+        help: "Send a system announcement to every player",
+        role: Role::GameMaster,
+        id: CommandId::Broadcast,
+    },
+    Command {
+        name: "setrole",
+        aliases: &[],
+        usage: "/setrole <player> <player|moderator|gm|admin>",
+        help: "Change the role of an account",
+        role: Role::Admin,
+        id: CommandId::SetRole,
+    },
+    Command {
+        name: "settime",
+        aliases: &[],
+        usage: "/settime <hour|hh:mm>",
+        help: "Set the in-game time of the current day",
+        role: Role::GameMaster,
+        id: CommandId::SetTime,
+    },
+    Command {
+        name: "setweather",
+        aliases: &[],
+        usage: "/setweather <clear|rain|storm|snow>",
+        help: "Change the weather in your zone",
+        role: Role::GameMaster,
+        id: CommandId::SetWeather,
+    },
+    Command {
+        name: "droploot",
+        aliases: &[],
+        usage: "/droploot <table>",
+        help: "Roll a loot table and drop the loot at your feet for you or your party",
+        role: Role::GameMaster,
+        id: CommandId::DropLoot,
+    },
+];
+
+fn find_command(name: &str) -> Option<&'static Command> {
+    let name = name.to_lowercase();
+    COMMANDS
+        .iter()
+        .find(|command| command.name == name || command.aliases.contains(&name.as_str()))
+}
+
+// Разбор аргументов: разделитель - пробелы, "в кавычках" - один аргумент
+pub fn parse_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quote".to_string());
+    }
+    if has_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+// Вызов команды: кто вызвал и с какими аргументами (без имени команды)
+struct Invocation<'a> {
+    ctx: &'a ServerContext,
+    player_id: PlayerId,
+    session: GameSession,
+    command: &'static Command,
+    args: &'a [String],
+}
+
+impl Invocation<'_> {
+    fn usage_error(&self) -> String {
+        format!("Usage: {}", self.command.usage)
+    }
+
+    // Все аргументы начиная с index одной строкой (причина, текст объявления)
+    fn rest(&self, index: usize) -> String {
+        self.args.get(index..).map(|rest| rest.join(" ")).unwrap_or_default()
+    }
+
+    async fn online_target(&self, name: &str) -> Result<GameSession, String> {
+        let (player_id, _) = self
+            .ctx
+            .session_manager
+            .find_by_name(name)
+            .await
+            .ok_or_else(|| format!("Player '{}' is not online", name))?;
+        self.ctx
+            .session_manager
+            .get_session(&player_id)
+            .await
+            .ok_or_else(|| format!("Player '{}' is not online", name))
+    }
+
+    // Нельзя применять команды к себе и к игрокам с той же или старшей ролью
+    fn check_outranks(&self, username: &str, role: Role) -> Result<(), String> {
+        if username.eq_ignore_ascii_case(&self.session.username) {
+            return Err("You can't use this command on yourself".to_string());
+        }
+        if role >= self.session.role {
+            return Err(format!("{} has the same or higher role than you", username));
+        }
+        Ok(())
+    }
+}
+
+// line - сообщение чата без ведущего '/'
+pub async fn execute_command(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    line: &str,
+) {
+    let result = match parse_args(line) {
+        Ok(args) => dispatch(ctx, player_id, &args).await,
+        Err(reason) => Err(reason),
+    };
+
+    match result {
+        Ok(message) => {
+            message_tx.send(ServerMessage::SystemMessage { message }).ok();
+        }
+        Err(reason) => {
+            message_tx.send(ServerMessage::CommandError { reason }).ok();
+        }
+    }
+}
+
+async fn dispatch(ctx: &ServerContext, player_id: PlayerId, args: &[String]) -> Result<String, String> {
+    let Some((name, args)) = args.split_first() else {
+        return Err("Type /help for a list of commands".to_string());
+    };
+    let session = ctx
+        .session_manager
+        .get_session(&player_id)
+        .await
+        .ok_or_else(|| "You are not logged in".to_string())?;
+
+    // Недоступные по роли команды выглядят как несуществующие
+    let command = find_command(name)
+        .filter(|command| session.role >= command.role)
+        .ok_or_else(|| format!("Unknown command /{}. Type /help for a list of commands", name))?;
+
+    println!("[{}] ⌨️ {} used /{} {:?}",
+        chrono::Local::now().format("%H:%M:%S"),
+        session.username, command.name, args
+    );
+
+    let invocation = Invocation { ctx, player_id, session, command, args };
+    let result = match command.id {
+        CommandId::Help => help(&invocation),
+        CommandId::Who => who(&invocation).await,
+        CommandId::Roll => roll(&invocation).await,
+        CommandId::Played => played(&invocation),
+        CommandId::Location => location(&invocation).await,
+        CommandId::Time => time(&invocation).await,
+        CommandId::Weather => weather(&invocation).await,
+        CommandId::Kick => kick(&invocation).await,
+        CommandId::Mute => mute(&invocation).await,
+        CommandId::Unmute => unmute(&invocation),
+        CommandId::Ban => ban(&invocation).await,
+        CommandId::Unban => unban(&invocation).await,
+        CommandId::BanIp => ban_ip(&invocation).await,
+        CommandId::UnbanIp => unban_ip(&invocation).await,
+        CommandId::Bans => list_bans(&invocation).await,
+        CommandId::Teleport => teleport(&invocation).await,
+        CommandId::Goto => goto(&invocation).await,
+        CommandId::Summon => summon(&invocation).await,
+        CommandId::Broadcast => broadcast(&invocation).await,
+        CommandId::SetRole => set_role(&invocation).await,
+        CommandId::SetTime => set_time(&invocation).await,
+        CommandId::SetWeather => set_weather(&invocation).await,
+        CommandId::DropLoot => drop_loot(&invocation).await,
+    };
+
+    // Каждое использование привилегированной команды попадает в журнал
+    if command.role > Role::Player {
+        let session = &invocation.session;
+        ctx.audit.record(&session.username, session.role, command.name, args, &result).await;
+    }
+    result
+}
+
+fn help(inv: &Invocation<'_>) -> Result<String, String> {
+    if let Some(name) = inv.args.first() {
+        let command = find_command(name.trim_start_matches('/'))
+            .filter(|command| inv.session.role >= command.role)
+            .ok_or_else(|| format!("Unknown command /{}", name.trim_start_matches('/')))?;
+        let mut text = format!("{}\n{}", command.usage, command.help);
+        if !command.aliases.is_empty() {
+            let aliases: Vec<String> = command.aliases.iter().map(|alias| format!("/{}", alias)).collect();
+            text.push_str(&format!("\nAliases: {}", aliases.join(", ")));
+        }
+        return Ok(text);
+    }
+
+    let lines: Vec<String> = COMMANDS
+        .iter()
+        .filter(|command| inv.session.role >= command.role)
+        .map(|command| format!("{} - {}", command.usage, command.help))
+        .collect();
+    Ok(format!("Available commands:\n{}", lines.join("\n")))
+}
+
+async fn who(inv: &Invocation<'_>) -> Result<String, String> {
+    if inv.args.len() > 1 {
+        return Err(inv.usage_error());
+    }
+    let filter = inv.args.first().map(|name| name.to_lowercase());
+
+    let mut players: Vec<(String, u32)> = inv
+        .ctx
+        .game_world
+        .list_players()
+        .await
+        .into_iter()
+        .filter(|(_, name, _)| filter.as_ref().is_none_or(|filter| name.to_lowercase().contains(filter)))
+        .map(|(_, name, zone_id)| (name, zone_id))
+        .collect();
+    players.sort();
+
+    if players.is_empty() {
+        return Ok("No players found".to_string());
+    }
+    let total = players.len();
+    let mut lines: Vec<String> = players
+        .iter()
+        .take(MAX_WHO_RESULTS)
+        .map(|(name, zone_id)| format!("{} - zone {}", name, zone_id))
+        .collect();
+    if total > MAX_WHO_RESULTS {
+        lines.push(format!("...and {} more", total - MAX_WHO_RESULTS));
+    }
+    Ok(format!("Players online ({}):\n{}", total, lines.join("\n")))
+}
+
+async fn roll(inv: &Invocation<'_>) -> Result<String, String> {
+    let (min, max) = match inv.args {
+        [] => (1, 100),
+        [range] => match range.split_once('-') {
+            Some((min, max)) => (
+                min.trim().parse::<u32>().map_err(|_| inv.usage_error())?,
+                max.trim().parse::<u32>().map_err(|_| inv.usage_error())?,
+            ),
+            None => (1, range.parse::<u32>().map_err(|_| inv.usage_error())?),
+        },
+        _ => return Err(inv.usage_error()),
+    };
+    if min > max || max == 0 || max > 1_000_000 {
+        return Err("Roll range must be between 1 and 1000000".to_string());
+    }
+    inv.ctx.moderation.check_command(&inv.session.username)?;
+
+    let result = rand::rng().random_range(min..=max);
+    let text = format!("{} rolls {} ({}-{})", inv.session.username, result, min, max);
+
+    // Результат видят те, кто слышит обычный локальный чат
+    if let Some(state) = inv.ctx.game_world.get_player_state(&inv.player_id).await {
+        let nearby: Vec<PlayerId> = inv
+            .ctx
+            .game_world
+            .get_players_in_radius(state.zone_id, &state.transform.position, inv.ctx.chat_config.say_radius)
+            .await
+            .into_iter()
+            .filter(|id| *id != inv.player_id)
+            .collect();
+        let message = ServerMessage::SystemMessage { message: text.clone() };
+        inv.ctx.session_manager.send_to_players(&nearby, &message).await;
+    }
+    Ok(text)
+}
+
+fn played(inv: &Invocation<'_>) -> Result<String, String> {
+    Ok(format!("Time played this session: {}", format_duration(inv.session.logged_in_at.elapsed())))
+}
+
+async fn location(inv: &Invocation<'_>) -> Result<String, String> {
+    let state = inv
+        .ctx
+        .game_world
+        .get_player_state(&inv.player_id)
+        .await
+        .ok_or_else(|| "You are not in the world".to_string())?;
+    let zone_name = inv.ctx.game_world.zone_name(state.zone_id).await.unwrap_or_default();
+    let p = &state.transform.position;
+    let mut message = format!("{} (zone {}) at ({:.1}, {:.1}, {:.1})", zone_name, state.zone_id, p.x, p.y, p.z);
+    if let Some(bind) = &state.bind_point {
+        let bind_zone = inv.ctx.game_world.zone_name(bind.zone_id).await.unwrap_or_default();
+        message.push_str(&format!(", bound to {}", bind_zone));
+    }
+    Ok(message)
+}
+
+async fn time(inv: &Invocation<'_>) -> Result<String, String> {
+    let time = inv.ctx.game_world.clock().now().await;
+    Ok(format!("Day {}, {} ({})", time.day, format_hour(time.hour), if time.is_day { "day" } else { "night" }))
+}
+
+async fn weather(inv: &Invocation<'_>) -> Result<String, String> {
+    let state = inv
+        .ctx
+        .game_world
+        .get_player_state(&inv.player_id)
+        .await
+        .ok_or_else(|| "You are not in the world".to_string())?;
+    let zone_name = inv.ctx.game_world.zone_name(state.zone_id).await.unwrap_or_default();
+    let weather = inv.ctx.game_world.zone_weather(state.zone_id).await;
+    Ok(format!("Weather in {}: {} (movement {:.0}%, visibility {:.0}%)",
+        zone_name,
+        weather::kind_name(weather.kind),
+        weather.movement_multiplier * 100.0,
+        weather.visibility * 100.0
+    ))
+}
+
+// Длительность вида 30s, 10m, 2h, 7d; None - навсегда (perm)
+fn parse_duration(text: &str) -> Result<Option<Duration>, String> {
+    let text = text.to_lowercase();
+    if text == "perm" || text == "permanent" {
+        return Ok(None);
+    }
+    let invalid = || format!("Invalid duration '{}', use e.g. 30s, 10m, 2h, 7d or perm", text);
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (amount, unit) = text.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" | "" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(invalid()),
+    };
+    if amount == 0 {
+        return Err(invalid());
+    }
+    amount
+        .checked_mul(multiplier)
+        .map(|secs| Some(Duration::from_secs(secs)))
+        .ok_or_else(invalid)
+}
+
+async fn kick(inv: &Invocation<'_>) -> Result<String, String> {
+    let Some(name) = inv.args.first() else {
+        return Err(inv.usage_error());
+    };
+    let target = inv.online_target(name).await?;
+    inv.check_outranks(&target.username, target.role)?;
+
+    let reason = inv.rest(1);
+    let message = if reason.is_empty() {
+        format!("You have been kicked by {}", inv.session.username)
+    } else {
+        format!("You have been kicked by {}: {}", inv.session.username, reason)
+    };
+    inv.ctx.session_manager.disconnect(&target.player_id, &message).await;
+    Ok(format!("{} has been kicked", target.username))
+}
+
+async fn mute(inv: &Invocation<'_>) -> Result<String, String> {
+    let [name, duration, ..] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let duration = parse_duration(duration)?.ok_or_else(|| "Mutes can't be permanent, use /ban".to_string())?;
+    let target = inv.online_target(name).await?;
+    inv.check_outranks(&target.username, target.role)?;
+
+    inv.ctx.moderation.mute(&target.username, duration);
+    let reason = inv.rest(2);
+    let message = if reason.is_empty() {
+        format!("You have been muted for {}", format_duration(duration))
+    } else {
+        format!("You have been muted for {}: {}", format_duration(duration), reason)
+    };
+    inv.ctx.session_manager.send_to_player(&target.player_id, ServerMessage::SystemMessage { message }).await.ok();
+    Ok(format!("{} is muted for {}", target.username, format_duration(duration)))
+}
+
+fn unmute(inv: &Invocation<'_>) -> Result<String, String> {
+    let [name] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    if !inv.ctx.moderation.unmute(name) {
+        return Err(format!("{} is not muted", name));
+    }
+    Ok(format!("{} is no longer muted", name))
+}
+
+async fn ban(inv: &Invocation<'_>) -> Result<String, String> {
+    let [name, duration, ..] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let duration = parse_duration(duration)?;
+    let account = inv
+        .ctx
+        .accounts
+        .find(name)
+        .await
+        .ok_or_else(|| format!("Account '{}' not found", name))?;
+    inv.check_outranks(&account.username, account.role)?;
+
+    let target = BanTarget::Account { account_id: account.id, username: account.username.clone() };
+    let ban = new_ban(inv, target, duration);
+    let description = ban.describe();
+    inv.ctx.bans.ban(ban).await;
+    inv.ctx.session_manager.disconnect(&account.id, &format!("You have been {}", description)).await;
+    Ok(format!("{} has been {}", account.username, description))
+}
+
+async fn unban(inv: &Invocation<'_>) -> Result<String, String> {
+    let [name] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let account = inv
+        .ctx
+        .accounts
+        .find(name)
+        .await
+        .ok_or_else(|| format!("Account '{}' not found", name))?;
+    let target = BanTarget::Account { account_id: account.id, username: account.username.clone() };
+    if !inv.ctx.bans.unban(&target).await {
+        return Err(format!("{} is not banned", account.username));
+    }
+    Ok(format!("{} has been unbanned", account.username))
+}
+
+fn new_ban(inv: &Invocation<'_>, target: BanTarget, duration: Option<Duration>) -> Ban {
+    let now = chrono::Utc::now().timestamp();
+    Ban {
+        target,
+        reason: inv.rest(2),
+        banned_by: inv.session.username.clone(),
+        created_at: now,
+        expires_at: duration.map(|duration| now.saturating_add(duration.as_secs() as i64)),
+    }
+}
+
+async fn ban_ip(inv: &Invocation<'_>) -> Result<String, String> {
+    let [address, duration, ..] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let duration = parse_duration(duration)?;
+    // Вместо адреса можно указать имя игрока в сети - баним его адрес
+    let network = match IpNetwork::parse(address) {
+        Ok(network) => network,
+        Err(e) => {
+            let target = inv.online_target(address).await.map_err(|_| e)?;
+            inv.check_outranks(&target.username, target.role)?;
+            IpNetwork::host(target.peer_addr.ip())
+        }
+    };
+    if network.contains(inv.session.peer_addr.ip()) {
+        return Err("This ban would include your own address".to_string());
+    }
+
+    let ban = new_ban(inv, BanTarget::Address { network }, duration);
+    let description = ban.describe();
+    inv.ctx.bans.ban(ban).await;
+
+    let affected = inv.ctx.session_manager.find_by_address(&network).await;
+    for player_id in &affected {
+        inv.ctx.session_manager.disconnect(player_id, &format!("You have been {}", description)).await;
+    }
+    Ok(format!("{} has been {} ({} players disconnected)", network, description, affected.len()))
+}
+
+async fn unban_ip(inv: &Invocation<'_>) -> Result<String, String> {
+    let [address] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let network = IpNetwork::parse(address)?;
+    if !inv.ctx.bans.unban(&BanTarget::Address { network }).await {
+        return Err(format!("{} is not banned", network));
+    }
+    Ok(format!("{} has been unbanned", network))
+}
+
+async fn list_bans(inv: &Invocation<'_>) -> Result<String, String> {
+    if !inv.args.is_empty() {
+        return Err(inv.usage_error());
+    }
+    let bans = inv.ctx.bans.active_bans().await;
+    if bans.is_empty() {
+        return Ok("No active bans".to_string());
+    }
+    let lines: Vec<String> = bans
+        .iter()
+        .map(|ban| format!("{} - {} (by {})", ban.target, ban.describe(), ban.banned_by))
+        .collect();
+    Ok(format!("Active bans ({}):\n{}", bans.len(), lines.join("\n")))
+}
+
+// Перемещаем игрока и сообщаем об этом его зоне
+async fn move_player(inv: &Invocation<'_>, player_id: PlayerId, zone_id: Option<u32>, position: Vector3) -> Result<(), String> {
+    let transfer = inv.ctx.game_world.teleport_player(player_id, zone_id, position).await?;
+    send_zone_transfer(inv.ctx, &transfer).await;
+    Ok(())
+}
+
+async fn teleport(inv: &Invocation<'_>) -> Result<String, String> {
+    // Если первый аргумент не число - это имя игрока
+    let (target, coords) = match inv.args.first() {
+        Some(first) if first.parse::<f32>().is_err() => (Some(inv.online_target(first).await?), &inv.args[1..]),
+        _ => (None, inv.args),
+    };
+    if let Some(target) = &target
+        && target.player_id != inv.player_id
+    {
+        inv.check_outranks(&target.username, target.role)?;
+    }
+    if coords.len() != 3 && coords.len() != 4 {
+        return Err(inv.usage_error());
+    }
+    let parse = |value: &String| value.parse::<f32>().ok().filter(|value| value.is_finite());
+    let (Some(x), Some(y), Some(z)) = (parse(&coords[0]), parse(&coords[1]), parse(&coords[2])) else {
+        return Err(inv.usage_error());
+    };
+    let zone_id = match coords.get(3) {
+        Some(zone) => Some(zone.parse::<u32>().map_err(|_| inv.usage_error())?),
+        None => None,
+    };
+
+    let position = Vector3 { x, y, z };
+    match target {
+        Some(target) => {
+            move_player(inv, target.player_id, zone_id, position).await?;
+            let message = format!("You have been teleported by {}", inv.session.username);
+            inv.ctx.session_manager.send_to_player(&target.player_id, ServerMessage::SystemMessage { message }).await.ok();
+            Ok(format!("Teleported {} to ({:.1}, {:.1}, {:.1})", target.username, x, y, z))
+        }
+        None => {
+            move_player(inv, inv.player_id, zone_id, position).await?;
+            Ok(format!("Teleported to ({:.1}, {:.1}, {:.1})", x, y, z))
+        }
+    }
+}
+
+async fn goto(inv: &Invocation<'_>) -> Result<String, String> {
+    let [name] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let target = inv.online_target(name).await?;
+    inv.check_outranks(&target.username, target.role)?;
+    let state = inv
+        .ctx
+        .game_world
+        .get_player_state(&target.player_id)
+        .await
+        .ok_or_else(|| format!("{} is not in the world", target.username))?;
+    move_player(inv, inv.player_id, Some(state.zone_id), state.transform.position).await?;
+    Ok(format!("Teleported to {}", target.username))
+}
+
+async fn summon(inv: &Invocation<'_>) -> Result<String, String> {
+    let [name] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let target = inv.online_target(name).await?;
+    if target.player_id == inv.player_id {
+        return Err("You can't summon yourself".to_string());
+    }
+    inv.check_outranks(&target.username, target.role)?;
+    let state = inv
+        .ctx
+        .game_world
+        .get_player_state(&inv.player_id)
+        .await
+        .ok_or_else(|| "You are not in the world".to_string())?;
+    move_player(inv, target.player_id, Some(state.zone_id), state.transform.position).await?;
+
+    let message = format!("You have been summoned by {}", inv.session.username);
+    inv.ctx.session_manager.send_to_player(&target.player_id, ServerMessage::SystemMessage { message }).await.ok();
+    Ok(format!("Summoned {}", target.username))
+}
+
+async fn broadcast(inv: &Invocation<'_>) -> Result<String, String> {
+    let message = inv.rest(0);
+    if message.is_empty() {
+        return Err(inv.usage_error());
+    }
+    if message.chars().count() > MAX_ANNOUNCEMENT_LENGTH {
+        return Err(format!("Announcement is too long (max {} characters)", MAX_ANNOUNCEMENT_LENGTH));
+    }
+    inv.ctx.moderation.check_command(&inv.session.username)?;
+    inv.ctx.session_manager.broadcast(&ServerMessage::Announcement { message }).await;
+    Ok("Announcement sent".to_string())
+}
+
+async fn set_role(inv: &Invocation<'_>) -> Result<String, String> {
+    let [name, role] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let role = Role::parse(role).ok_or_else(|| format!("Unknown role '{}'", role))?;
+    let account = inv
+        .ctx
+        .accounts
+        .find(name)
+        .await
+        .ok_or_else(|| format!("Account '{}' not found", name))?;
+    if account.username.eq_ignore_ascii_case(&inv.session.username) {
+        return Err("You can't change your own role".to_string());
+    }
+    inv.check_outranks(&account.username, account.role)?;
+    if role > inv.session.role {
+        return Err("You can't grant a role higher than your own".to_string());
+    }
+
+    let account = inv.ctx.accounts.set_role(&account.username, role).await?;
+    inv.ctx.session_manager.set_role(&account.id, role).await;
+    let message = format!("Your role is now {}", role.name());
+    inv.ctx.session_manager.send_to_player(&account.id, ServerMessage::SystemMessage { message }).await.ok();
+    Ok(format!("{} is now {}", account.username, role.name()))
+}
+
+async fn set_time(inv: &Invocation<'_>) -> Result<String, String> {
+    let [time] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let invalid = || format!("Invalid time '{}', use e.g. 6, 18.5 or 21:30", time);
+    let hour = match time.split_once(':') {
+        Some((hours, minutes)) => {
+            let hours: u32 = hours.parse().map_err(|_| invalid())?;
+            let minutes: u32 = minutes.parse().map_err(|_| invalid())?;
+            if minutes >= 60 {
+                return Err(invalid());
+            }
+            hours as f32 + minutes as f32 / 60.0
+        }
+        None => time.parse::<f32>().map_err(|_| invalid())?,
+    };
+    let time = inv.ctx.game_world.clock().set_hour(hour).await?;
+    Ok(format!("Time set to {} of day {}", format_hour(time.hour), time.day))
+}
+
+async fn set_weather(inv: &Invocation<'_>) -> Result<String, String> {
+    let [kind] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let kind = weather::parse_kind(kind).ok_or_else(|| format!("Unknown weather '{}'", kind))?;
+    let state = inv
+        .ctx
+        .game_world
+        .get_player_state(&inv.player_id)
+        .await
+        .ok_or_else(|| "You are not in the world".to_string())?;
+    let weather = inv.ctx.game_world.set_weather(state.zone_id, kind).await?;
+    let message = ServerMessage::WeatherChanged { zone_id: state.zone_id, weather };
+    broadcast_to_zone(&inv.ctx.session_manager, &inv.ctx.game_world, state.zone_id, &message).await;
+    Ok(format!("Weather set to {}", weather::kind_name(kind)))
+}
+
+async fn drop_loot(inv: &Invocation<'_>) -> Result<String, String> {
+    let [table] = inv.args else {
+        return Err(inv.usage_error());
+    };
+    let state = inv
+        .ctx
+        .game_world
+        .get_player_state(&inv.player_id)
+        .await
+        .ok_or_else(|| "You are not in the world".to_string())?;
+    let owners = match inv.ctx.parties.get_party(&inv.player_id).await {
+        Some(party) => party.members,
+        None => vec![inv.player_id],
+    };
+
+    let world = &inv.ctx.game_world;
+    match world.drop_loot(state.zone_id, &state.transform.position, table, owners).await? {
+        Some(object) => {
+            let message = ServerMessage::ObjectChanged { object };
+            broadcast_to_zone(&inv.ctx.session_manager, world, state.zone_id, &message).await;
+            Ok(format!("Dropped loot from table {}", table))
+        }
+        None => Ok(format!("Table {} dropped nothing", table)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        parse_args(line).unwrap()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(args("  kick   bob\tnow "), vec!["kick", "bob", "now"]);
+        assert!(args("   ").is_empty());
+    }
+
+    #[test]
+    fn quotes_group_arguments() {
+        assert_eq!(args(r#"mail "Sir Bob" "hello there" x"#), vec!["mail", "Sir Bob", "hello there", "x"]);
+        assert_eq!(args(r#"a "" b"#), vec!["a", "", "b"]);
+        assert_eq!(args(r#"say"hi""#), vec!["sayhi"]);
+        assert_eq!(parse_args(r#"ban "bob"#).unwrap_err(), "Unterminated quote");
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30s"), Ok(Some(Duration::from_secs(30))));
+        assert_eq!(parse_duration("10"), Ok(Some(Duration::from_secs(600))));
+        assert_eq!(parse_duration("2H"), Ok(Some(Duration::from_secs(7200))));
+        assert_eq!(parse_duration("7d"), Ok(Some(Duration::from_secs(7 * 86400))));
+        assert_eq!(parse_duration("perm"), Ok(None));
+        assert_eq!(parse_duration("Permanent"), Ok(None));
+        for invalid in ["", "0m", "m", "5w", "1.5h", "-3s", "99999999999999999999d"] {
+            assert!(parse_duration(invalid).is_err(), "{} should be rejected", invalid);
+        }
+    }
+
+    #[test]
+    fn finds_commands_by_name_and_alias() {
+        assert_eq!(find_command("ROLL").map(|command| command.name), Some("roll"));
+        assert_eq!(find_command("random").map(|command| command.name), Some("roll"));
+        assert!(find_command("nosuchcommand").is_none());
+    }
+}
@@ -0,0 +1,30 @@
+use crate::config::ChatConfig;
+use crate::game::{AccountStore, AuditLog, BanList, CharacterStore, ChatModerator, GameWorld, GuildManager, MailManager, PartyManager, SessionManager, WorldStateStore};
+use crate::protocol::PlayerId;
+
+// Общие сервисы сервера: их получают обработчики соединений и игровой цикл
+pub struct ServerContext {
+    pub session_manager: SessionManager,
+    pub game_world: GameWorld,
+    pub parties: PartyManager,
+    pub guilds: GuildManager,
+    pub chat_config: ChatConfig,
+    pub moderation: ChatModerator,
+    pub accounts: AccountStore,
+    pub bans: BanList,
+    pub audit: AuditLog,
+    pub mail: MailManager,
+    pub characters: CharacterStore,
+    pub world_state: WorldStateStore,
+}
+
+impl ServerContext {
+    // Сохраняем персонажа сразу, не дожидаясь автосохранения: после того как
+    // предметы ушли в письмо или пришли из него, иначе падение сервера их удвоит или потеряет
+    pub async fn save_character(&self, player_id: PlayerId) {
+        if let Some(state) = self.game_world.get_player_state(&player_id).await {
+            let record = self.game_world.character_record(player_id, &state).await;
+            self.characters.store(vec![record]).await;
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use tokio::sync::mpsc::UnboundedSender;
+use crate::game::guild::{Guild, GuildChange};
+use crate::protocol::{ClientMessage, PlayerId, ServerMessage};
+use super::context::ServerContext;
+
+pub async fn handle_guild_message(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    message: ClientMessage,
+) {
+    let Some(session) = ctx.session_manager.get_session(&player_id).await else {
+        return;
+    };
+    let username = session.username.as_str();
+    
+    let result = match message {
+        ClientMessage::GuildCreate { name } => match ctx.guilds.create(username, &name).await {
+            Ok(guild) => {
+                println!("[{}] 🛡️ {} founded guild {}",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    username, guild.name
+                );
+                send_guild_info(ctx, &guild).await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::GuildInvite { player_name } => invite(ctx, username, &player_name).await,
+        ClientMessage::GuildInviteResponse { accept: true } => match ctx.guilds.accept(username).await {
+            Ok(guild) => {
+                send_guild_info(ctx, &guild).await;
+                if !guild.motd.is_empty() {
+                    message_tx.send(ServerMessage::GuildMotd { motd: guild.motd }).ok();
+                }
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::GuildInviteResponse { accept: false } => match ctx.guilds.decline(username).await {
+            Ok(inviter) => {
+                let declined = ServerMessage::GuildInviteDeclined { player_name: username.to_string() };
+                send_to_names(ctx, &[inviter], &declined).await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::GuildLeave => match ctx.guilds.leave(username).await {
+            Ok(change) => {
+                send_guild_change(ctx, change, "You left the guild").await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::GuildKick { player_name } => match ctx.guilds.kick(username, &player_name).await {
+            Ok(change) => {
+                send_guild_change(ctx, change, "You were removed from the guild").await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::GuildSetRank { player_name, rank } => {
+            match ctx.guilds.set_rank(username, &player_name, rank as usize).await {
+                Ok(guild) => {
+                    send_guild_info(ctx, &guild).await;
+                    Ok(())
+                }
+                Err(reason) => Err(reason),
+            }
+        }
+        ClientMessage::GuildTransferLeadership { player_name } => {
+            match ctx.guilds.transfer_leadership(username, &player_name).await {
+                Ok(guild) => {
+                    send_guild_info(ctx, &guild).await;
+                    Ok(())
+                }
+                Err(reason) => Err(reason),
+            }
+        }
+        ClientMessage::GuildSetMotd { motd } => match ctx.guilds.set_motd(username, &motd).await {
+            Ok(guild) => {
+                let update = ServerMessage::GuildMotd { motd: guild.motd.clone() };
+                send_to_names(ctx, &guild.member_names(), &update).await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::GuildDisband => match ctx.guilds.disband(username).await {
+            Ok(removed) => {
+                println!("[{}] 🛡️ Guild of {} disbanded",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    username
+                );
+                let left = ServerMessage::GuildLeft { reason: "Guild disbanded".to_string() };
+                send_to_names(ctx, &removed, &left).await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::GuildRosterRequest => match ctx.guilds.get_guild(username).await {
+            Some(guild) => {
+                message_tx.send(guild_info(ctx, &guild).await).ok();
+                Ok(())
+            }
+            None => Err("You are not in a guild".to_string()),
+        },
+        _ => Ok(()),
+    };
+    
+    if let Err(reason) = result {
+        message_tx.send(ServerMessage::GuildError { reason }).ok();
+    }
+}
+
+async fn invite(ctx: &ServerContext, inviter: &str, player_name: &str) -> Result<(), String> {
+    let Some((target_id, _)) = ctx.session_manager.find_online(&[player_name.to_string()]).await.pop() else {
+        return Err("Player not found".to_string());
+    };
+    
+    let guild_name = ctx.guilds.invite(inviter, player_name).await?;
+    let invite = ServerMessage::GuildInviteReceived {
+        guild_name,
+        inviter_name: inviter.to_string(),
+    };
+    ctx.session_manager.send_to_player(&target_id, invite).await
+}
+
+async fn send_to_names(ctx: &ServerContext, names: &[String], message: &ServerMessage) {
+    let player_ids: Vec<PlayerId> = ctx.session_manager.find_online(names).await.into_iter().map(|(id, _)| id).collect();
+    ctx.session_manager.send_to_players(&player_ids, message).await;
+}
+
+async fn guild_info(ctx: &ServerContext, guild: &Guild) -> ServerMessage {
+    let online: Vec<String> = ctx
+        .session_manager
+        .find_online(&guild.member_names())
+        .await
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect();
+    
+    ServerMessage::GuildInfo {
+        guild_id: guild.id,
+        name: guild.name.clone(),
+        motd: guild.motd.clone(),
+        ranks: guild.rank_info(),
+        members: guild.roster(&online),
+    }
+}
+
+// Состав гильдии - всем участникам в сети
+pub async fn send_guild_info(ctx: &ServerContext, guild: &Guild) {
+    let info = guild_info(ctx, guild).await;
+    send_to_names(ctx, &guild.member_names(), &info).await;
+}
+
+pub async fn send_guild_change(ctx: &ServerContext, change: GuildChange, reason: &str) {
+    let left = ServerMessage::GuildLeft { reason: reason.to_string() };
+    send_to_names(ctx, &change.removed, &left).await;
+    
+    if let Some(guild) = change.guild {
+        send_guild_info(ctx, &guild).await;
+    }
+}
+
+// При входе: сведения о гильдии и сообщение дня
+pub async fn send_guild_login(ctx: &ServerContext, message_tx: &UnboundedSender<ServerMessage>, username: &str) {
+    if let Some(guild) = ctx.guilds.get_guild(username).await {
+        message_tx.send(guild_info(ctx, &guild).await).ok();
+        if !guild.motd.is_empty() {
+            message_tx.send(ServerMessage::GuildMotd { motd: guild.motd }).ok();
+        }
+    }
+}
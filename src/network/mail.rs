@@ -0,0 +1,181 @@
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+use crate::game::mail::{Mail, MailDraft};
+use crate::protocol::{ClientMessage, PlayerId, ServerMessage};
+use super::context::ServerContext;
+
+pub async fn handle_mail_message(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    message: ClientMessage,
+) {
+    let Some(session) = ctx.session_manager.get_session(&player_id).await else {
+        return;
+    };
+    
+    let result = match message {
+        ClientMessage::MailSend { recipient, subject, body, item_ids, gold } => match ctx.accounts.find(&recipient).await {
+            Some(account) => {
+                let draft = MailDraft {
+                    sender_id: Some(player_id),
+                    sender: session.username.clone(),
+                    recipient_id: account.id,
+                    recipient: account.username,
+                    subject,
+                    body,
+                    items: Vec::new(),
+                    gold,
+                };
+                send_mail(ctx, message_tx, player_id, draft, &item_ids).await
+            }
+            None => Err(format!("Player '{}' not found", recipient)),
+        },
+        ClientMessage::MailListRequest => {
+            let mails = ctx.mail.list(player_id).await.iter().map(Mail::to_summary).collect();
+            message_tx.send(ServerMessage::MailList { mails }).ok();
+            Ok(())
+        }
+        ClientMessage::MailRead { mail_id } => match ctx.mail.read(player_id, mail_id).await {
+            Ok(mail) => {
+                message_tx.send(ServerMessage::MailContent {
+                    mail_id: mail.id,
+                    items: mail.attachments(),
+                    sender: mail.sender,
+                    subject: mail.subject,
+                    body: mail.body,
+                    gold: mail.gold,
+                }).ok();
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::MailTakeAttachments { mail_id } => take_attachments(ctx, message_tx, player_id, mail_id).await,
+        ClientMessage::MailDelete { mail_id } => match ctx.mail.delete(player_id, mail_id).await {
+            Ok(()) => {
+                message_tx.send(ServerMessage::MailDeleted { mail_id }).ok();
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        _ => Ok(()),
+    };
+    
+    if let Err(reason) = result {
+        message_tx.send(ServerMessage::MailError { reason }).ok();
+    }
+}
+
+// Вложения забираем до отправки и возвращаем, если письмо не ушло
+async fn send_mail(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    mut draft: MailDraft,
+    item_ids: &[Uuid],
+) -> Result<(), String> {
+    if draft.recipient_id == player_id {
+        return Err("You can't send mail to yourself".to_string());
+    }
+    ctx.mail.validate(draft.recipient_id, &draft.subject, &draft.body, item_ids.len()).await?;
+    
+    let gold = draft.gold;
+    let mut changed_slots = Vec::new();
+    if !item_ids.is_empty() || gold > 0 {
+        let (items, slots, remaining_gold) = ctx.game_world.take_mail_attachments(player_id, item_ids, gold).await?;
+        draft.items = items;
+        changed_slots = slots;
+        message_tx.send(ServerMessage::GoldUpdate { gold: remaining_gold }).ok();
+    }
+    let has_attachments = !draft.items.is_empty() || gold > 0;
+    
+    let mail = match ctx.mail.send(draft.clone()).await {
+        Ok(mail) => mail,
+        Err(reason) => {
+            if has_attachments {
+                match ctx.game_world.give_mail_attachments(player_id, &draft.items, gold).await {
+                    Ok((slots, gold)) => {
+                        message_tx.send(ServerMessage::InventoryChanged { slots }).ok();
+                        message_tx.send(ServerMessage::GoldUpdate { gold }).ok();
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to return mail attachments to {}: {}, returning them by mail", player_id, e);
+                        if let Some(returned) = ctx.mail.return_undelivered(draft).await {
+                            notify_recipient(ctx, &returned).await;
+                        }
+                    }
+                }
+            }
+            return Err(reason);
+        }
+    };
+    if has_attachments {
+        ctx.save_character(player_id).await;
+    }
+    
+    if !changed_slots.is_empty() {
+        message_tx.send(ServerMessage::InventoryChanged { slots: changed_slots }).ok();
+    }
+    message_tx.send(ServerMessage::MailSent { recipient: mail.recipient.clone() }).ok();
+    println!("[{}] 📨 {} sent mail to {} ({} items, {} gold)",
+        chrono::Local::now().format("%H:%M:%S"),
+        mail.sender, mail.recipient, mail.items.len(), mail.gold
+    );
+    notify_recipient(ctx, &mail).await;
+    Ok(())
+}
+
+async fn take_attachments(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    mail_id: Uuid,
+) -> Result<(), String> {
+    let (items, gold) = ctx.mail.take_attachments(player_id, mail_id).await?;
+    match ctx.game_world.give_mail_attachments(player_id, &items, gold).await {
+        Ok((slots, total_gold)) => {
+            ctx.mail.commit(mail_id).await;
+            ctx.save_character(player_id).await;
+            if !slots.is_empty() {
+                message_tx.send(ServerMessage::InventoryChanged { slots }).ok();
+            }
+            if gold > 0 {
+                message_tx.send(ServerMessage::GoldUpdate { gold: total_gold }).ok();
+            }
+            message_tx.send(ServerMessage::MailAttachmentsTaken { mail_id }).ok();
+            Ok(())
+        }
+        Err(reason) => {
+            ctx.mail.restore_attachments(player_id, mail_id, items, gold).await;
+            Err(reason)
+        }
+    }
+}
+
+// Получателю в сети - уведомление о новом письме
+async fn notify_recipient(ctx: &ServerContext, mail: &Mail) {
+    let notification = ServerMessage::MailReceived {
+        mail_id: mail.id,
+        sender: mail.sender.clone(),
+        subject: mail.subject.clone(),
+    };
+    ctx.session_manager.send_to_player(&mail.recipient_id, notification).await.ok();
+}
+
+// Из игрового цикла: удаляем истекшие письма и уведомляем о возвратах
+pub async fn expire_mail(ctx: &ServerContext) {
+    for mail in ctx.mail.expire().await {
+        notify_recipient(ctx, &mail).await;
+    }
+}
+
+// При входе: золото и число непрочитанных писем
+pub async fn send_mail_login(ctx: &ServerContext, message_tx: &UnboundedSender<ServerMessage>, player_id: PlayerId) {
+    if let Some(gold) = ctx.game_world.get_gold(&player_id).await {
+        message_tx.send(ServerMessage::GoldUpdate { gold }).ok();
+    }
+    let count = ctx.mail.unread_count(player_id).await;
+    if count > 0 {
+        message_tx.send(ServerMessage::UnreadMail { count: count as u32 }).ok();
+    }
+}
@@ -1,6 +1,12 @@
 mod server;
+mod context;
+mod chat;
+mod party;
+mod guild;
+mod commands;
+mod mail;
+mod zones;
 mod transport;
 pub mod udp_transport;
-pub use udp_transport::UdpTransport;
 
 pub use server::GameServer;
\ No newline at end of file
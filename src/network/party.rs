@@ -0,0 +1,150 @@
+use std::collections::{HashMap, HashSet};
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+use crate::game::party::{Party, PartyChange};
+use crate::protocol::{ClientMessage, PartyMember, PlayerId, ServerMessage};
+use super::context::ServerContext;
+
+pub async fn handle_party_message(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    message: ClientMessage,
+) {
+    let result = match message {
+        ClientMessage::PartyInvite { target_id } => invite(ctx, player_id, target_id).await,
+        ClientMessage::PartyInviteResponse { accept: true } => match ctx.parties.accept(player_id).await {
+            Ok(party) => {
+                send_party_update(ctx, &party).await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::PartyInviteResponse { accept: false } => match ctx.parties.decline(player_id).await {
+            Ok(inviter) => {
+                if let Some(state) = ctx.game_world.get_player_state(&player_id).await {
+                    let declined = ServerMessage::PartyInviteDeclined { player_name: state.username };
+                    ctx.session_manager.send_to_player(&inviter, declined).await.ok();
+                }
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::PartyLeave => match ctx.parties.leave(player_id).await {
+            Ok(change) => {
+                send_party_change(ctx, change, "You left the party").await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::PartyKick { target_id } => match ctx.parties.kick(player_id, target_id).await {
+            Ok(change) => {
+                send_party_change(ctx, change, "You were kicked from the party").await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        ClientMessage::PartyPromote { target_id } => match ctx.parties.promote(player_id, target_id).await {
+            Ok(party) => {
+                send_party_update(ctx, &party).await;
+                Ok(())
+            }
+            Err(reason) => Err(reason),
+        },
+        _ => Ok(()),
+    };
+    
+    if let Err(reason) = result {
+        message_tx.send(ServerMessage::PartyError { reason }).ok();
+    }
+}
+
+async fn invite(ctx: &ServerContext, player_id: PlayerId, target_id: Uuid) -> Result<(), String> {
+    if ctx.session_manager.get_session(&target_id).await.is_none() {
+        return Err("Player not found".to_string());
+    }
+    let inviter = ctx
+        .game_world
+        .get_player_state(&player_id)
+        .await
+        .ok_or_else(|| "Player not found".to_string())?;
+    
+    ctx.parties.invite(player_id, target_id).await?;
+    
+    println!("[{}] 👥 {} invited {} to a party",
+        chrono::Local::now().format("%H:%M:%S"),
+        inviter.username, target_id
+    );
+    
+    let invite = ServerMessage::PartyInviteReceived {
+        inviter_id: player_id,
+        inviter_name: inviter.username,
+    };
+    ctx.session_manager.send_to_player(&target_id, invite).await
+}
+
+// Полный состав группы - всем участникам. Заодно запоминаем группу в мире для подземелий
+pub async fn send_party_update(ctx: &ServerContext, party: &Party) {
+    ctx.game_world.set_party(&party.members, Some(party.id)).await;
+    let update = ServerMessage::PartyUpdate {
+        party_id: party.id,
+        leader_id: party.leader,
+        members: ctx.game_world.get_party_members(&party.members).await,
+    };
+    ctx.session_manager.send_to_players(&party.members, &update).await;
+}
+
+// Первый в removed - тот, кто вышел или был исключен, остальных выкинуло роспуском группы
+pub async fn send_party_change(ctx: &ServerContext, change: PartyChange, reason: &str) {
+    ctx.game_world.set_party(&change.removed, None).await;
+    for (index, player_id) in change.removed.iter().enumerate() {
+        let reason = if index == 0 { reason } else { "Party disbanded" };
+        let left = ServerMessage::PartyLeft { reason: reason.to_string() };
+        ctx.session_manager.send_to_player(player_id, left).await.ok();
+    }
+    
+    if let Some(party) = change.party {
+        send_party_update(ctx, &party).await;
+    }
+}
+
+fn member_changed(previous: &PartyMember, current: &PartyMember) -> bool {
+    let (dx, dy, dz) = (
+        previous.position.x - current.position.x,
+        previous.position.y - current.position.y,
+        previous.position.z - current.position.z,
+    );
+    previous.zone_id != current.zone_id
+        || previous.health != current.health
+        || previous.max_health != current.max_health
+        || dx * dx + dy * dy + dz * dz > 0.01
+}
+
+// Рассылка здоровья и позиций участников; last_sent - что уже отправлено, чтобы слать только изменения
+pub async fn send_member_updates(ctx: &ServerContext, last_sent: &mut HashMap<PlayerId, PartyMember>) {
+    let parties = ctx.parties.parties().await;
+    let mut in_party = HashSet::new();
+    
+    for party in parties {
+        in_party.extend(party.members.iter().copied());
+        
+        let changed: Vec<PartyMember> = ctx
+            .game_world
+            .get_party_members(&party.members)
+            .await
+            .into_iter()
+            .filter(|member| last_sent.get(&member.player_id).is_none_or(|previous| member_changed(previous, member)))
+            .collect();
+        if changed.is_empty() {
+            continue;
+        }
+        
+        for member in &changed {
+            last_sent.insert(member.player_id, member.clone());
+        }
+        let update = ServerMessage::PartyMemberUpdates { members: changed };
+        ctx.session_manager.send_to_players(&party.members, &update).await;
+    }
+    
+    last_sent.retain(|player_id, _| in_party.contains(player_id));
+}
@@ -1,27 +1,54 @@
 use tokio_tungstenite::{accept_async, tungstenite::Message};
 use futures_util::{StreamExt, SinkExt};
-use bincode;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
-use chrono;
+use tokio::sync::broadcast;
 
 use crate::config::ServerConfig;
-use crate::game::{SessionManager, GameWorld};
-use crate::protocol::{ClientMessage, ServerMessage, ChatChannel, Transform};
+use crate::game::{SessionManager, GameWorld, PartyManager, GuildManager, ChatModerator, AccountStore, BanList, AuditLog, MailManager, CharacterStore, WorldStateStore};
+use crate::game::instances::INSTANCE_CHECK_INTERVAL;
+use crate::game::loot::LOOT_CHECK_INTERVAL;
+use crate::game::mail::EXPIRY_CHECK_INTERVAL;
+use crate::game::party::MEMBER_UPDATE_INTERVAL;
+use crate::game::weather::WEATHER_CHECK_INTERVAL;
+use crate::game::storage;
+use crate::game::world::{CraftEvent, EquipOutcome, MoveOutcome};
+use crate::protocol::{ClientMessage, ServerMessage, ChatChannel, PlayerAction};
+use super::chat::{handle_chat_message, handle_whisper, WhisperTarget};
+use super::context::ServerContext;
+use super::guild::{handle_guild_message, send_guild_login};
+use super::mail::{expire_mail, handle_mail_message, send_mail_login};
+use super::party::{handle_party_message, send_member_updates, send_party_change};
+use super::zones::{broadcast_to_zone, broadcast_to_zone_except, handle_zone_transfer_request, send_zone_transfer};
 
 pub struct GameServer {
     pub config: ServerConfig,
-    session_manager: Arc<SessionManager>,
-    game_world: Arc<GameWorld>,
+    context: Arc<ServerContext>,
 }
 
 impl GameServer {
-    pub fn new(config: ServerConfig) -> Self {
-        Self {
+    // Не стартуем, если не открылось хранилище сохранений
+    pub fn new(config: ServerConfig) -> Result<Self, String> {
+        let storage = storage::open(&config)?;
+        Ok(Self {
+            context: Arc::new(ServerContext {
+                session_manager: SessionManager::new(),
+                game_world: GameWorld::new(&config.game),
+                parties: PartyManager::new(),
+                guilds: GuildManager::load(&config.game.guilds, storage.clone()),
+                chat_config: config.game.chat.clone(),
+                moderation: ChatModerator::new(&config.game.chat),
+                accounts: AccountStore::load(&config.game.accounts, storage.clone()),
+                bans: BanList::load(storage.clone()),
+                audit: AuditLog::new(&config.game.accounts.audit_log_file),
+                mail: MailManager::load(&config.game.mail, storage.clone()),
+                characters: CharacterStore::load(storage.clone()),
+                world_state: WorldStateStore::new(storage),
+            }),
             config,
-            session_manager: Arc::new(SessionManager::new()),
-            game_world: Arc::new(GameWorld::new()),
-        }
+        })
     }
     
     pub async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -31,18 +58,49 @@ impl GameServer {
         println!("🚀 GameServer started on {}", addr);
         println!("🌐 WebSocket server listening on ws://{}", addr);
         
-        while let Ok((stream, _)) = listener.accept().await {
+        if let Some(state) = self.context.world_state.load() {
+            self.context.game_world.restore_world_state(&state).await;
+        }
+        
+        tokio::spawn(run_game_loop(self.context.clone(), self.config.server.tick_rate));
+        tokio::spawn(run_day_phase_broadcast(self.context.clone()));
+        if self.config.game.world.save_interval > 0 {
+            tokio::spawn(run_save_loop(self.context.clone(), self.config.game.world.save_interval));
+        }
+        
+        loop {
+            // По Ctrl+C сохраняем всех, кто в игре, и останавливаемся
+            let stream = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(_) => break,
+                },
+                _ = tokio::signal::ctrl_c() => {
+                    println!("🛑 Shutting down");
+                    save_characters(&self.context).await;
+                    save_world_state(&self.context).await;
+                    break;
+                }
+            };
             let peer_addr = stream.peer_addr().unwrap();
             println!("New connection from: {}", peer_addr);
             
-            let session_manager = self.session_manager.clone();
-            let game_world = self.game_world.clone();
+            // Забаненные адреса отклоняем до рукопожатия WebSocket
+            if let Some(ban) = self.context.bans.address_ban(peer_addr.ip()).await {
+                println!("[{}] 🔨 Rejected connection from banned address {} ({})",
+                    chrono::Local::now().format("%H:%M:%S"),
+                    peer_addr, ban.describe()
+                );
+                continue;
+            }
+            
+            let context = self.context.clone();
             
             tokio::spawn(async move {
                 match accept_async(stream).await {
                     Ok(ws_stream) => {
                         println!("WebSocket connection established from: {}", peer_addr);
-                        handle_connection(ws_stream, session_manager, game_world).await;
+                        handle_connection(ws_stream, peer_addr, context).await;
                     }
                     Err(e) => {
                         eprintln!("Failed to establish WebSocket connection from {}: {}", peer_addr, e);
@@ -55,18 +113,147 @@ impl GameServer {
     }
 }
 
+// Сохраняем всех игроков в мире
+async fn save_characters(ctx: &ServerContext) {
+    let records = ctx.game_world.character_records().await;
+    let count = records.len();
+    ctx.characters.store(records).await;
+    if count > 0 {
+        println!("[{}] 💾 Saved {} characters",
+            chrono::Local::now().format("%H:%M:%S"),
+            count
+        );
+    }
+}
+
+async fn save_world_state(ctx: &ServerContext) {
+    let state = ctx.game_world.world_state().await;
+    ctx.world_state.store(state).await;
+}
+
+// Автосохранение раз в save_interval секунд, отдельно от игрового цикла
+async fn run_save_loop(ctx: Arc<ServerContext>, save_interval: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(save_interval));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    interval.tick().await;
+    
+    loop {
+        interval.tick().await;
+        save_characters(&ctx).await;
+        save_world_state(&ctx).await;
+    }
+}
+
+// Рассвет и закат рассылаются всем игрокам
+async fn run_day_phase_broadcast(ctx: Arc<ServerContext>) {
+    let mut events = ctx.game_world.clock().subscribe();
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                let message = ServerMessage::DayPhaseChanged { game_time: event.time().clone() };
+                ctx.session_manager.broadcast(&message).await;
+            }
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+// Игровой цикл с частотой tick_rate
+async fn run_game_loop(ctx: Arc<ServerContext>, tick_rate: u32) {
+    let session_manager = &ctx.session_manager;
+    let game_world = &ctx.game_world;
+    let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / tick_rate.max(1) as f64));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    
+    let mut last_party_update = Instant::now();
+    let mut party_members_sent = HashMap::new();
+    let mut last_mail_expiry = Instant::now();
+    let mut last_instance_check = Instant::now();
+    let mut last_weather_check = Instant::now();
+    let mut last_loot_check = Instant::now();
+    
+    loop {
+        interval.tick().await;
+        game_world.tick().await;
+        
+        for (zone_id, object) in game_world.update_objects().await {
+            broadcast_to_zone(session_manager, game_world, zone_id, &ServerMessage::ObjectChanged { object }).await;
+        }
+        
+        for event in game_world.update_crafting().await {
+            match event {
+                CraftEvent::Progress { player_id, recipe_id, progress } => {
+                    let message = ServerMessage::CraftingProgress { recipe_id, progress };
+                    session_manager.send_to_player(&player_id, message).await.ok();
+                }
+                CraftEvent::Completed { player_id, recipe_id, changed_slots } => {
+                    session_manager.send_to_player(&player_id, ServerMessage::InventoryChanged { slots: changed_slots }).await.ok();
+                    session_manager.send_to_player(&player_id, ServerMessage::CraftingCompleted { recipe_id }).await.ok();
+                }
+                CraftEvent::Cancelled { player_id, recipe_id, reason } => {
+                    let message = ServerMessage::CraftingCancelled { recipe_id, reason };
+                    session_manager.send_to_player(&player_id, message).await.ok();
+                }
+            }
+        }
+        
+        for (player_id, stats) in game_world.expire_buffs().await {
+            let update = ServerMessage::PlayerStatsUpdate { player_id, stats };
+            session_manager.send_to_player(&player_id, update).await.ok();
+        }
+        
+        if last_party_update.elapsed() >= MEMBER_UPDATE_INTERVAL {
+            last_party_update = Instant::now();
+            send_member_updates(&ctx, &mut party_members_sent).await;
+        }
+        
+        if last_mail_expiry.elapsed() >= EXPIRY_CHECK_INTERVAL {
+            last_mail_expiry = Instant::now();
+            expire_mail(&ctx).await;
+        }
+        
+        if last_instance_check.elapsed() >= INSTANCE_CHECK_INTERVAL {
+            last_instance_check = Instant::now();
+            for transfer in game_world.update_instances().await {
+                let notice = ServerMessage::SystemMessage { message: "The instance has been reset".to_string() };
+                session_manager.send_to_player(&transfer.player_id, notice).await.ok();
+                send_zone_transfer(&ctx, &transfer).await;
+            }
+        }
+        
+        if last_weather_check.elapsed() >= WEATHER_CHECK_INTERVAL {
+            last_weather_check = Instant::now();
+            for (zone_id, weather) in game_world.update_weather().await {
+                broadcast_to_zone(session_manager, game_world, zone_id, &ServerMessage::WeatherChanged { zone_id, weather }).await;
+            }
+        }
+        
+        if last_loot_check.elapsed() >= LOOT_CHECK_INTERVAL {
+            last_loot_check = Instant::now();
+            for (zone_id, object_id) in game_world.update_loot().await {
+                broadcast_to_zone(session_manager, game_world, zone_id, &ServerMessage::ObjectRemoved { object_id }).await;
+            }
+        }
+    }
+}
+
 async fn handle_connection(
     ws_stream: tokio_tungstenite::WebSocketStream<tokio::net::TcpStream>,
-    session_manager: std::sync::Arc<SessionManager>,
-    game_world: std::sync::Arc<GameWorld>,
+    peer_addr: std::net::SocketAddr,
+    ctx: Arc<ServerContext>,
 ) {
     use uuid::Uuid;
     
+    let session_manager = &ctx.session_manager;
+    let game_world = &ctx.game_world;
+    
     println!("🆕 NEW CONNECTION - handle_connection started");
     
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
     let (message_tx, mut message_rx) = tokio::sync::mpsc::unbounded_channel::<ServerMessage>();
     let (serialized_tx, mut serialized_rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+    let (disconnect_tx, mut disconnect_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
     
     let mut current_player_id: Option<Uuid> = None;
     
@@ -75,11 +262,11 @@ async fn handle_connection(
         loop {
             tokio::select! {
                 Some(message) = message_rx.recv() => {
-                    if let Ok(serialized) = bincode::serialize(&message) {
-                        if let Err(e) = ws_sender.send(Message::Binary(serialized.into())).await {
-                            eprintln!("Failed to send message: {}", e);
-                            break;
-                        } 
+                    if let Ok(serialized) = bincode::serialize(&message)
+                        && let Err(e) = ws_sender.send(Message::Binary(serialized.into())).await
+                    {
+                        eprintln!("Failed to send message: {}", e);
+                        break;
                     }
                 }
                 Some(data) = serialized_rx.recv() => {
@@ -91,10 +278,21 @@ async fn handle_connection(
                 else => break,
             }
         }
+        ws_sender.close().await.ok();
     });
     
-    // Обработка входящих сообщений
-    while let Some(message) = ws_receiver.next().await {
+    // Обработка входящих сообщений и команд принудительного отключения
+    loop {
+        let message = tokio::select! {
+            message = ws_receiver.next() => match message {
+                Some(message) => message,
+                None => break,
+            },
+            Some(reason) = disconnect_rx.recv() => {
+                message_tx.send(ServerMessage::Disconnected { reason }).ok();
+                break;
+            }
+        };
         match message {
             Ok(Message::Binary(data)) => {               
                 if let Ok(client_message) = bincode::deserialize::<ClientMessage>(&data) {
@@ -114,101 +312,337 @@ async fn handle_connection(
                                 username
                             );
                             
-                            let player_id = Uuid::new_v4();
-                            current_player_id = Some(player_id);
+                            if session_manager.find_by_name(&username).await.is_some() {
+                                message_tx.send(ServerMessage::LoginError {
+                                    reason: "This character is already online".to_string(),
+                                }).ok();
+                                continue;
+                            }
+                            
+                            let account = match ctx.accounts.login(&username, &auth_token).await {
+                                Ok(account) => account,
+                                Err(reason) => {
+                                    message_tx.send(ServerMessage::LoginError { reason }).ok();
+                                    continue;
+                                }
+                            };
+                            let ban = match ctx.bans.account_ban(account.id).await {
+                                Some(ban) => Some(ban),
+                                None => ctx.bans.address_ban(peer_addr.ip()).await,
+                            };
+                            if let Some(ban) = ban {
+                                println!("[{}] 🔨 {} from {} tried to log in while {}",
+                                    chrono::Local::now().format("%H:%M:%S"),
+                                    account.username, peer_addr, ban.describe()
+                                );
+                                message_tx.send(ServerMessage::LoginError {
+                                    reason: format!("You are {}", ban.describe()),
+                                }).ok();
+                                continue;
+                            }
+                            
+                            // Id игрока постоянный - это id аккаунта
+                            let player_id = account.id;
+                            let username = account.username;
                             
-                            // Добавляем игрока в мир
-                            let initial_transform = Transform::default();
-                            game_world.add_player(player_id, username.clone(), initial_transform.clone()).await;
+                            // Добавляем игрока в мир, сохраненного - с прошлым состоянием
+                            let saved = ctx.characters.get(&player_id).await;
+                            if let Err(reason) = game_world.add_player(player_id, username.clone(), saved).await {
+                                message_tx.send(ServerMessage::LoginError { reason }).ok();
+                                continue;
+                            }
+                            current_player_id = Some(player_id);
                             
                             // СОЗДАЕМ СЕССИЮ с каналом для сериализованных данных
-                            let session = crate::game::GameSession::new(
+                            let mut session = crate::game::GameSession::new(
                                 player_id, 
                                 username.clone(), 
                                 serialized_tx.clone(),
+                                disconnect_tx.clone(),
+                                peer_addr,
                             );
+                            session.role = account.role;
                             
                             if let Err(e) = session_manager.add_session(session).await {
                                 eprintln!("Failed to create session: {}", e);
+                                game_world.remove_player(&player_id).await;
+                                current_player_id = None;
+                                message_tx.send(ServerMessage::LoginError { reason: e }).ok();
                                 continue;
                             }
                             
                             let response = ServerMessage::LoginSuccess { 
                                 player_id,
                                 username: username.clone(),
+                                game_time: game_world.clock().now().await,
                             };
                             
                             if let Err(e) = message_tx.send(response) {
                                 eprintln!("Failed to send login response: {}", e);
                             }
                             
+                            if let Some(state) = game_world.get_player_state(&player_id).await {
+                                message_tx.send(ServerMessage::PlayerStatsUpdate {
+                                    player_id,
+                                    stats: state.stats.clone(),
+                                }).ok();
+                                message_tx.send(ServerMessage::ZoneChanged {
+                                    zone_id: state.zone_id,
+                                    zone_name: game_world.zone_name(state.zone_id).await.unwrap_or_default(),
+                                    transform: state.transform.clone(),
+                                }).ok();
+                                
+                                let (players, objects) = game_world.get_zone_snapshot(state.zone_id).await;
+                                message_tx.send(ServerMessage::WorldState {
+                                    players,
+                                    npcs: Vec::new(),
+                                    objects,
+                                    timestamp: chrono::Utc::now().timestamp_millis() as u64,
+                                    game_time: game_world.clock().now().await,
+                                }).ok();
+                                message_tx.send(ServerMessage::WeatherChanged {
+                                    zone_id: state.zone_id,
+                                    weather: game_world.zone_weather(state.zone_id).await,
+                                }).ok();
+                                
+                                let joined = ServerMessage::PlayerJoined { player_data: state.to_player_data(player_id) };
+                                broadcast_to_zone_except(&ctx, state.zone_id, &player_id, &joined).await;
+                            }
+                            if let Some((capacity, slots)) = game_world.get_inventory(&player_id).await {
+                                message_tx.send(ServerMessage::InventoryContents { capacity, slots }).ok();
+                            }
+                            if let Some(items) = game_world.get_equipment(&player_id).await {
+                                message_tx.send(ServerMessage::EquipmentContents { items }).ok();
+                            }
+                            send_guild_login(&ctx, &message_tx, &username).await;
+                            send_mail_login(&ctx, &message_tx, player_id).await;
+                            
                             println!("[{}] ✅ Player {} logged in (ID: {})",
                                 chrono::Local::now().format("%H:%M:%S"),
                                 username, player_id
                             );
                         }
-                        ClientMessage::PlayerMove { transform, velocity, timestamp } => {
-                            if let Some(player_id) = current_player_id {
-                                if let Some(_) = game_world.update_player_position(player_id, transform.clone()).await {
-                                    let update_message = ServerMessage::PlayerTransformUpdate {
-                                        player_id,
-                                        transform: transform.clone(),
-                                        velocity,
-                                    };
-                                    session_manager.broadcast_except(&player_id, &update_message).await;
+                        ClientMessage::PlayerMove { transform, velocity, timestamp: _ } => {
+                            if let Some(player_id) = current_player_id
+                                && let Some(outcome) = game_world.update_player_position(player_id, transform).await
+                            {
+                                // Сервер решает, где игрок: при расхождении клиент получает поправку
+                                let transform = match outcome {
+                                    MoveOutcome::Accepted(transform) => transform,
+                                    MoveOutcome::Corrected(transform) => {
+                                        message_tx.send(ServerMessage::PositionCorrection { transform: transform.clone() }).ok();
+                                        transform
+                                    }
+                                    MoveOutcome::Rejected(transform) => {
+                                        message_tx.send(ServerMessage::PositionCorrection { transform }).ok();
+                                        continue;
+                                    }
+                                };
+                                let update_message = ServerMessage::PlayerTransformUpdate {
+                                    player_id,
+                                    transform,
+                                    velocity,
+                                };
+                                if let Some(state) = game_world.get_player_state(&player_id).await {
+                                    broadcast_to_zone_except(&ctx, state.zone_id, &player_id, &update_message).await;
+                                }
+                                
+                                if let Some((zone_id, update)) = game_world.interrupt_stance(player_id).await {
+                                    let message = ServerMessage::PlayerStateChanged { update };
+                                    broadcast_to_zone(session_manager, game_world, zone_id, &message).await;
+                                }
+                                
+                                match game_world.trigger_zone_exit(player_id).await {
+                                    Some(Ok(transfer)) => send_zone_transfer(&ctx, &transfer).await,
+                                    Some(Err(reason)) => {
+                                        message_tx.send(ServerMessage::ZoneError { reason }).ok();
+                                    }
+                                    None => {}
                                 }
                             }
                         }
-                        ClientMessage::ChatMessage { channel, message, target_id } => {
-                            
+                        ClientMessage::UseItem { item_id, target_id } => {
                             if let Some(player_id) = current_player_id {
-                                println!("[{}] 💬 {:?} chat from {}: {} (target: {:?})",
-                                    chrono::Local::now().format("%H:%M:%S"),
-                                    channel, player_id, message, target_id
-                                );
-                                
-                                if let Some(player_state) = game_world.get_player_state(&player_id).await {
-                                    let username = player_state.username.clone();
-                                    
-                                    let chat_message = ServerMessage::ChatMessage {
-                                        from_player: username.clone(),
-                                        channel: channel.clone(),
-                                        message: message.clone(),
-                                    };
-                                    
-                                    println!("📨 Created ServerMessage: {:?}", chat_message);
-                                    
-                                    match channel {
-                                        ChatChannel::Global | ChatChannel::Local | ChatChannel::Party | ChatChannel::Guild => {
-                                            session_manager.broadcast_except(&player_id, &chat_message).await;
+                                match game_world.use_item(player_id, item_id, target_id).await {
+                                    Ok(outcome) => {
+                                        if !outcome.changed_slots.is_empty() {
+                                            message_tx.send(ServerMessage::InventoryChanged {
+                                                slots: outcome.changed_slots,
+                                            }).ok();
+                                        }
+                                        
+                                        if let Some(stats) = outcome.target_stats {
+                                            let update = ServerMessage::PlayerStatsUpdate {
+                                                player_id: outcome.target_id,
+                                                stats,
+                                            };
+                                            if outcome.target_id != player_id {
+                                                message_tx.send(update.clone()).ok();
+                                            }
+                                            session_manager.send_to_player(&outcome.target_id, update).await.ok();
+                                        }
+                                        
+                                        if let Some(transfer) = outcome.teleport {
+                                            send_zone_transfer(&ctx, &transfer).await;
                                         }
-                                        ChatChannel::Whisper => {
-                                            if let Some(target_id) = target_id {
-                                                println!("🤫 Sending whisper to {}", target_id);
-                                                if session_manager.send_to_player(&target_id, chat_message.clone()).await.is_ok() {
-                                                    message_tx.send(chat_message.clone()).ok();
-                                                } else {
-                                                    println!("❌ Whisper target not found");
-                                                    let error_msg = ServerMessage::ChatError {
-                                                        reason: "Player not found".to_string()
-                                                    };
-                                                    message_tx.send(error_msg).ok();
+                                    }
+                                    Err(reason) => {
+                                        message_tx.send(ServerMessage::ItemError { reason }).ok();
+                                    }
+                                }
+                            }
+                        }
+                        ClientMessage::PlayerAction { action_type, target_id, direction: _ } => {
+                            if let Some(player_id) = current_player_id {
+                                match action_type {
+                                    PlayerAction::Interact => {
+                                        let Some(object_id) = target_id else {
+                                            message_tx.send(ServerMessage::ActionError {
+                                                reason: "Interact requires a target object".to_string(),
+                                            }).ok();
+                                            continue;
+                                        };
+                                        
+                                        match game_world.interact(player_id, object_id).await {
+                                            Ok(outcome) => {
+                                                for (zone_id, object) in outcome.object_updates {
+                                                    let update = ServerMessage::ObjectChanged { object };
+                                                    broadcast_to_zone(session_manager, game_world, zone_id, &update).await;
                                                 }
-                                            } else {
-                                                let error_msg = ServerMessage::ChatError {
-                                                    reason: "Whisper requires target player ID".to_string()
-                                                };
-                                                message_tx.send(error_msg).ok();
+                                                for (zone_id, object_id) in outcome.removed_objects {
+                                                    let removed = ServerMessage::ObjectRemoved { object_id };
+                                                    broadcast_to_zone(session_manager, game_world, zone_id, &removed).await;
+                                                }
+                                                if let Some(gold) = outcome.gold {
+                                                    message_tx.send(ServerMessage::GoldUpdate { gold }).ok();
+                                                }
+                                                if !outcome.changed_slots.is_empty() {
+                                                    message_tx.send(ServerMessage::InventoryChanged {
+                                                        slots: outcome.changed_slots,
+                                                    }).ok();
+                                                }
+                                                if let Some(transfer) = outcome.teleport {
+                                                    send_zone_transfer(&ctx, &transfer).await;
+                                                }
+                                                if let Some(message) = outcome.notice {
+                                                    message_tx.send(ServerMessage::SystemMessage { message }).ok();
+                                                }
+                                            }
+                                            Err(reason) => {
+                                                message_tx.send(ServerMessage::ActionError { reason }).ok();
+                                            }
+                                        }
+                                    }
+                                    PlayerAction::Craft => {
+                                        let response = match game_world.get_recipes(player_id, target_id).await {
+                                            Ok(recipes) => ServerMessage::CraftingRecipes { station_id: target_id, recipes },
+                                            Err(reason) => ServerMessage::CraftingError { reason },
+                                        };
+                                        message_tx.send(response).ok();
+                                    }
+                                    PlayerAction::Jump | PlayerAction::Sit | PlayerAction::Dance => {
+                                        match game_world.perform_action(player_id, &action_type).await {
+                                            Ok((zone_id, update)) => {
+                                                let message = ServerMessage::PlayerStateChanged { update };
+                                                broadcast_to_zone(session_manager, game_world, zone_id, &message).await;
+                                            }
+                                            Err(reason) => {
+                                                message_tx.send(ServerMessage::ActionError { reason }).ok();
                                             }
                                         }
                                     }
-                                } else {
-                                    println!("❌ Player state not found for {}", player_id);
                                 }
+                            }
+                        }
+                        ClientMessage::CraftItem { recipe_id, station_id } => {
+                            if let Some(player_id) = current_player_id {
+                                let response = match game_world.start_craft(player_id, recipe_id, station_id).await {
+                                    Ok(craft_time_ms) => ServerMessage::CraftingStarted { recipe_id, craft_time_ms },
+                                    Err(reason) => ServerMessage::CraftingError { reason },
+                                };
+                                message_tx.send(response).ok();
+                            }
+                        }
+                        ClientMessage::CancelCraft => {
+                            if let Some(player_id) = current_player_id {
+                                let response = match game_world.cancel_craft(player_id).await {
+                                    Some(recipe_id) => ServerMessage::CraftingCancelled {
+                                        recipe_id,
+                                        reason: "Cancelled".to_string(),
+                                    },
+                                    None => ServerMessage::CraftingError {
+                                        reason: "You are not crafting anything".to_string(),
+                                    },
+                                };
+                                message_tx.send(response).ok();
+                            }
+                        }
+                        ClientMessage::EquipItem { item_id } => {
+                            if let Some(player_id) = current_player_id {
+                                let result = game_world.equip_item(player_id, item_id).await;
+                                send_equip_result(session_manager, game_world, &message_tx, player_id, result).await;
+                            }
+                        }
+                        ClientMessage::UnequipItem { slot } => {
+                            if let Some(player_id) = current_player_id {
+                                let result = game_world.unequip_item(player_id, slot).await;
+                                send_equip_result(session_manager, game_world, &message_tx, player_id, result).await;
+                            }
+                        }
+                        ClientMessage::ChatMessage { channel, message, target_id } => {
+                            if let Some(player_id) = current_player_id {
+                                handle_chat_message(&ctx, &message_tx, player_id, channel, message, target_id).await;
                             } else {
                                 println!("❌ No player_id for chat message");
                             }
                         }
+                        ClientMessage::WhisperByName { target_name, message } => {
+                            if let Some(player_id) = current_player_id {
+                                handle_whisper(&ctx, &message_tx, player_id, WhisperTarget::Name(target_name), message).await;
+                            }
+                        }
+                        ClientMessage::WhisperReply { message } => {
+                            if let Some(player_id) = current_player_id {
+                                handle_whisper(&ctx, &message_tx, player_id, WhisperTarget::Reply, message).await;
+                            }
+                        }
+                        ClientMessage::PartyInvite { .. }
+                        | ClientMessage::PartyInviteResponse { .. }
+                        | ClientMessage::PartyLeave
+                        | ClientMessage::PartyKick { .. }
+                        | ClientMessage::PartyPromote { .. } => {
+                            if let Some(player_id) = current_player_id {
+                                handle_party_message(&ctx, &message_tx, player_id, client_message).await;
+                            }
+                        }
+                        ClientMessage::GuildCreate { .. }
+                        | ClientMessage::GuildInvite { .. }
+                        | ClientMessage::GuildInviteResponse { .. }
+                        | ClientMessage::GuildLeave
+                        | ClientMessage::GuildKick { .. }
+                        | ClientMessage::GuildSetRank { .. }
+                        | ClientMessage::GuildTransferLeadership { .. }
+                        | ClientMessage::GuildSetMotd { .. }
+                        | ClientMessage::GuildDisband
+                        | ClientMessage::GuildRosterRequest => {
+                            if let Some(player_id) = current_player_id {
+                                handle_guild_message(&ctx, &message_tx, player_id, client_message).await;
+                            }
+                        }
+                        ClientMessage::ZoneTransferRequest { zone_id } => {
+                            if let Some(player_id) = current_player_id {
+                                handle_zone_transfer_request(&ctx, &message_tx, player_id, zone_id).await;
+                            }
+                        }
+                        ClientMessage::MailSend { .. }
+                        | ClientMessage::MailListRequest
+                        | ClientMessage::MailRead { .. }
+                        | ClientMessage::MailTakeAttachments { .. }
+                        | ClientMessage::MailDelete { .. } => {
+                            if let Some(player_id) = current_player_id {
+                                handle_mail_message(&ctx, &message_tx, player_id, client_message).await;
+                            }
+                        }
                         
                         _ => {
                             println!("[{}] ❓ Unhandled message type: {:?}",
@@ -259,7 +693,6 @@ async fn handle_connection(
                                         let target_id = if offset < data.len() {
                                             // Option serialization: 0 for None, 1 for Some
                                             if data[offset] == 0 {
-                                                offset += 1;
                                                 None
                                             } else if data[offset] == 1 && offset + 16 <= data.len() {
                                                 offset += 1;
@@ -286,56 +719,16 @@ async fn handle_connection(
                                         
                                         // 🔥 ОБРАБОТКА КАК ОБЫЧНОГО СООБЩЕНИЯ
                                         if let Some(player_id) = current_player_id {
-                                            if let Some(player_state) = game_world.get_player_state(&player_id).await {
-                                                let channel = match channel_val {
-                                                    0 => ChatChannel::Global,
-                                                    1 => ChatChannel::Local,
-                                                    2 => ChatChannel::Party,
-                                                    3 => ChatChannel::Guild,
-                                                    4 => ChatChannel::Whisper,
-                                                    _ => ChatChannel::Local,
-                                                };
-                                                
-                                                let chat_message = ServerMessage::ChatMessage {
-                                                    from_player: player_state.username.clone(),
-                                                    channel: channel.clone(),
-                                                    message: message.clone(),
-                                                };
-                                                
-                                                println!("📨 Created ServerMessage: {:?}", chat_message);
-                                                
-                                                match channel {
-                                                    ChatChannel::Global | ChatChannel::Local | ChatChannel::Party | ChatChannel::Guild => {
-                                                        println!("📢 Broadcasting to all except {}", player_id);
-                                                        session_manager.broadcast_except(&player_id, &chat_message).await;
-                                                        println!("✅ Broadcast completed");
-                                                    }
-                                                    ChatChannel::Whisper => {
-                                                        if let Some(target_id) = target_id {
-                                                            println!("🤫 Sending whisper to {}", target_id);
-                                                            if session_manager.send_to_player(&target_id, chat_message.clone()).await.is_ok() {
-                                                                // Также отправляем копию отправителю
-                                                                message_tx.send(chat_message.clone()).ok();
-                                                                println!("✅ Whisper sent successfully");
-                                                            } else {
-                                                                println!("❌ Whisper target not found: {}", target_id);
-                                                                let error_msg = ServerMessage::ChatError {
-                                                                    reason: format!("Player not found: {}", target_id)
-                                                                };
-                                                                message_tx.send(error_msg).ok();
-                                                            }
-                                                        } else {
-                                                            println!("❌ No target_id for whisper");
-                                                            let error_msg = ServerMessage::ChatError {
-                                                                reason: "Whisper requires target player ID".to_string()
-                                                            };
-                                                            message_tx.send(error_msg).ok();
-                                                        }
-                                                    }
-                                                }
-                                            } else {
-                                                println!("❌ Player state not found for {}", player_id);
-                                            }
+                                            let channel = match channel_val {
+                                                0 => ChatChannel::Global,
+                                                1 => ChatChannel::Local,
+                                                2 => ChatChannel::Party,
+                                                3 => ChatChannel::Guild,
+                                                4 => ChatChannel::Whisper,
+                                                5 => ChatChannel::Yell,
+                                                _ => ChatChannel::Local,
+                                            };
+                                            handle_chat_message(&ctx, &message_tx, player_id, channel, message, target_id).await;
                                         } else {
                                             println!("❌ No player_id for chat message");
                                         }
@@ -368,8 +761,17 @@ async fn handle_connection(
     }
     
     if let Some(player_id) = current_player_id {
-        session_manager.remove_session(&player_id).await;
-        game_world.remove_player(&player_id).await;
+        if let Some(session) = session_manager.remove_session(&player_id).await {
+            ctx.moderation.remove_player(&session.username);
+        }
+        if let Some(change) = ctx.parties.remove_player(player_id).await {
+            send_party_change(&ctx, change, "Disconnected").await;
+        }
+        if let Some(state) = game_world.remove_player(&player_id).await {
+            broadcast_to_zone(session_manager, game_world, state.zone_id, &ServerMessage::PlayerLeft { player_id }).await;
+            let record = game_world.character_record(player_id, &state).await;
+            ctx.characters.store(vec![record]).await;
+        }
         
         println!("[{}] 🚪 Player {} disconnected",
             chrono::Local::now().format("%H:%M:%S"),
@@ -377,11 +779,41 @@ async fn handle_connection(
         );
     }
     
+    // Задача отправки допишет оставшиеся сообщения и закроет соединение
+    drop(message_tx);
+    drop(serialized_tx);
     let _ = send_task.await;
     println!("🔚 CONNECTION ENDED - handle_connection finished");
 }
 
+// Инвентарь и характеристики - владельцу, смену экипировки видят все в зоне
+async fn send_equip_result(
+    session_manager: &SessionManager,
+    game_world: &GameWorld,
+    message_tx: &tokio::sync::mpsc::UnboundedSender<ServerMessage>,
+    player_id: uuid::Uuid,
+    result: Result<EquipOutcome, String>,
+) {
+    match result {
+        Ok(outcome) => {
+            message_tx.send(ServerMessage::InventoryChanged { slots: outcome.changed_slots }).ok();
+            message_tx.send(ServerMessage::PlayerStatsUpdate { player_id, stats: outcome.stats }).ok();
+            
+            let change = ServerMessage::EquipmentChanged {
+                player_id,
+                slot: outcome.slot,
+                template_id: outcome.template_id,
+            };
+            broadcast_to_zone(session_manager, game_world, outcome.zone_id, &change).await;
+        }
+        Err(reason) => {
+            message_tx.send(ServerMessage::ItemError { reason }).ok();
+        }
+    }
+}
+
 // Вспомогательная функция для форматирования канала
+#[allow(dead_code)]
 fn format_channel(channel: &ChatChannel) -> String {
     match channel {
         ChatChannel::Global => "🌍 Global",
@@ -389,5 +821,6 @@ fn format_channel(channel: &ChatChannel) -> String {
         ChatChannel::Party => "👥 Party",
         ChatChannel::Guild => "⚔️ Guild",
         ChatChannel::Whisper => "🤫 Whisper",
+        ChatChannel::Yell => "📣 Yell",
     }.to_string()
 }
\ No newline at end of file
@@ -1,13 +1,17 @@
 use crate::protocol::ServerMessage;
 use uuid::Uuid;
 
+// Транспорт под UDP, сервер пока работает только через WebSocket
+#[allow(dead_code)]
 pub type PlayerId = Uuid; // Создаем алиас
 
+#[allow(dead_code)]
 pub enum TransportMessage {
     Reliable(ServerMessage),
     Unreliable(Vec<u8>), 
 }
 
+#[allow(dead_code)]
 pub trait GameTransport: Send + Sync {
     async fn send(&self, player_id: &PlayerId, message: TransportMessage); // Используем наш PlayerId
     async fn broadcast(&self, message: TransportMessage);
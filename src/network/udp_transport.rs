@@ -1,10 +1,13 @@
 use tokio::net::UdpSocket;
 use std::sync::Arc;
 
+// Пока не подключен к серверу
+#[allow(dead_code)]
 pub struct UdpTransport {
     socket: Arc<UdpSocket>,
 }
 
+#[allow(dead_code)]
 impl UdpTransport {
     pub async fn new(bind_addr: &str) -> Result<Self, std::io::Error> {
         let socket = UdpSocket::bind(bind_addr).await?;
@@ -0,0 +1,92 @@
+use tokio::sync::mpsc::UnboundedSender;
+use crate::game::world::ZoneTransfer;
+use crate::game::{GameWorld, SessionManager};
+use crate::protocol::{PlayerId, ServerMessage, Vector3};
+use super::context::ServerContext;
+
+// Рассылка всем игрокам зоны
+pub async fn broadcast_to_zone(
+    session_manager: &SessionManager,
+    game_world: &GameWorld,
+    zone_id: u32,
+    message: &ServerMessage,
+) {
+    let player_ids = game_world.get_zone_player_ids(zone_id).await;
+    session_manager.send_to_players(&player_ids, message).await;
+}
+
+pub async fn broadcast_to_zone_except(ctx: &ServerContext, zone_id: u32, except: &PlayerId, message: &ServerMessage) {
+    let player_ids: Vec<PlayerId> = ctx
+        .game_world
+        .get_zone_player_ids(zone_id)
+        .await
+        .into_iter()
+        .filter(|id| id != except)
+        .collect();
+    ctx.session_manager.send_to_players(&player_ids, message).await;
+}
+
+// Телепорт внутри зоны видят все в зоне. При смене зоны старая зона
+// получает PlayerLeft, новая - PlayerJoined, а сам игрок - ZoneChanged и снимок зоны
+pub async fn send_zone_transfer(ctx: &ServerContext, transfer: &ZoneTransfer) {
+    let player_id = transfer.player_id;
+    if transfer.from_zone == transfer.to_zone {
+        let update = ServerMessage::PlayerTransformUpdate {
+            player_id,
+            transform: transfer.transform.clone(),
+            velocity: Vector3 { x: 0.0, y: 0.0, z: 0.0 },
+        };
+        broadcast_to_zone(&ctx.session_manager, &ctx.game_world, transfer.to_zone, &update).await;
+        return;
+    }
+    
+    broadcast_to_zone(&ctx.session_manager, &ctx.game_world, transfer.from_zone, &ServerMessage::PlayerLeft { player_id }).await;
+    if let Some(state) = ctx.game_world.get_player_state(&player_id).await {
+        let joined = ServerMessage::PlayerJoined { player_data: state.to_player_data(player_id) };
+        broadcast_to_zone_except(ctx, transfer.to_zone, &player_id, &joined).await;
+    }
+    
+    let zone_name = ctx.game_world.zone_name(transfer.to_zone).await.unwrap_or_default();
+    println!("[{}] 🗺️ Player {} moved from zone {} to {} ({})",
+        chrono::Local::now().format("%H:%M:%S"),
+        player_id, transfer.from_zone, transfer.to_zone, zone_name
+    );
+    
+    // Через ту же очередь сессии, чтобы снимок пришел после ZoneChanged
+    let changed = ServerMessage::ZoneChanged {
+        zone_id: transfer.to_zone,
+        zone_name,
+        transform: transfer.transform.clone(),
+    };
+    ctx.session_manager.send_to_player(&player_id, changed).await.ok();
+    
+    let (players, objects) = ctx.game_world.get_zone_snapshot(transfer.to_zone).await;
+    let snapshot = ServerMessage::WorldState {
+        players,
+        npcs: Vec::new(),
+        objects,
+        timestamp: chrono::Utc::now().timestamp_millis() as u64,
+        game_time: ctx.game_world.clock().now().await,
+    };
+    ctx.session_manager.send_to_player(&player_id, snapshot).await.ok();
+    
+    let weather = ServerMessage::WeatherChanged {
+        zone_id: transfer.to_zone,
+        weather: ctx.game_world.zone_weather(transfer.to_zone).await,
+    };
+    ctx.session_manager.send_to_player(&player_id, weather).await.ok();
+}
+
+pub async fn handle_zone_transfer_request(
+    ctx: &ServerContext,
+    message_tx: &UnboundedSender<ServerMessage>,
+    player_id: PlayerId,
+    zone_id: u32,
+) {
+    match ctx.game_world.request_zone_transfer(player_id, zone_id).await {
+        Ok(transfer) => send_zone_transfer(ctx, &transfer).await,
+        Err(reason) => {
+            message_tx.send(ServerMessage::ZoneError { reason }).ok();
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use super::{Vector3, Transform};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use super::common::ChatChannel;
+use super::common::{ChatChannel, EquipmentSlot};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ClientMessage {
@@ -45,6 +45,116 @@ pub enum ClientMessage {
     
     // Keep-alive
     Heartbeat,
+    
+    // Экипировка
+    EquipItem {
+        item_id: Uuid,
+    },
+    
+    UnequipItem {
+        slot: EquipmentSlot,
+    },
+    
+    // Крафт: station_id - объект-станция, если рецепт ее требует
+    CraftItem {
+        recipe_id: u32,
+        station_id: Option<Uuid>,
+    },
+    
+    CancelCraft,
+    
+    // Группа
+    PartyInvite {
+        target_id: Uuid,
+    },
+    
+    // Ответ на последнее полученное приглашение
+    PartyInviteResponse {
+        accept: bool,
+    },
+    
+    PartyLeave,
+    
+    PartyKick {
+        target_id: Uuid,
+    },
+    
+    // Передача лидерства
+    PartyPromote {
+        target_id: Uuid,
+    },
+    
+    // Гильдия: участники адресуются по имени, т.к. могут быть не в сети
+    GuildCreate {
+        name: String,
+    },
+    
+    GuildInvite {
+        player_name: String,
+    },
+    
+    GuildInviteResponse {
+        accept: bool,
+    },
+    
+    GuildLeave,
+    
+    GuildKick {
+        player_name: String,
+    },
+    
+    GuildSetRank {
+        player_name: String,
+        rank: u32,
+    },
+    
+    GuildTransferLeadership {
+        player_name: String,
+    },
+    
+    GuildSetMotd {
+        motd: String,
+    },
+    
+    GuildDisband,
+    
+    GuildRosterRequest,
+    
+    // Шепот по имени персонажа (без учета регистра)
+    WhisperByName {
+        target_name: String,
+        message: String,
+    },
+    
+    // Ответ тому, кто шептал последним
+    WhisperReply {
+        message: String,
+    },
+    
+    // Почта: получатель - имя персонажа, можно и не в сети.
+    // item_ids - стопки из инвентаря, прикладываются целиком
+    MailSend {
+        recipient: String,
+        subject: String,
+        body: String,
+        item_ids: Vec<Uuid>,
+        gold: u64,
+    },
+    MailListRequest,
+    MailRead {
+        mail_id: Uuid,
+    },
+    MailTakeAttachments {
+        mail_id: Uuid,
+    },
+    MailDelete {
+        mail_id: Uuid,
+    },
+    
+    // Переход в соседнюю зону, игрок должен стоять у перехода
+    ZoneTransferRequest {
+        zone_id: u32,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
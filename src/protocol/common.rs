@@ -7,4 +7,38 @@ pub enum ChatChannel {
     Party,
     Guild,
     Whisper,
-}
\ No newline at end of file
+    // Как Local, но с большим радиусом
+    Yell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EquipmentSlot {
+    Head,
+    Chest,
+    Legs,
+    Feet,
+    Hands,
+    MainHand,
+    OffHand,
+    Neck,
+    Ring,
+}
+
+// Права рангов гильдии
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GuildPermission {
+    Invite,
+    Kick,
+    SetRank,
+    SetMotd,
+}
+
+// Погода в зоне
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Storm,
+    Snow,
+}
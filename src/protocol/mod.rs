@@ -9,8 +9,6 @@ pub use common::*;
 pub use client_messages::*;
 pub use server_messages::*;
 // Re-export everything from child modules
-pub use client_messages::*;
-pub use server_messages::*;
 
 
 
@@ -1,7 +1,7 @@
 use super::{Vector3, Transform};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use super::common::ChatChannel;
+use super::common::{ChatChannel, EquipmentSlot, GuildPermission, WeatherKind};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ServerMessage {
@@ -9,6 +9,7 @@ pub enum ServerMessage {
     LoginSuccess {
         player_id: Uuid,
         username: String,
+        game_time: GameTime,
     },
     
     LoginError {
@@ -27,6 +28,7 @@ pub enum ServerMessage {
         npcs: Vec<NpcUpdate>,
         objects: Vec<ObjectUpdate>,
         timestamp: u64,
+        game_time: GameTime,
     },
     
     // События игроков
@@ -66,6 +68,224 @@ pub enum ServerMessage {
     HeartbeatResponse {
         server_time: u64,
     },
+    
+    // Инвентарь
+    InventoryContents {
+        capacity: u32,
+        slots: Vec<InventorySlot>,
+    },
+    
+    // Измененные ячейки, item: None - ячейка опустела
+    InventoryChanged {
+        slots: Vec<InventorySlot>,
+    },
+    
+    ItemError {
+        reason: String,
+    },
+    
+    PlayerStatsUpdate {
+        player_id: Uuid,
+        stats: PlayerStats,
+    },
+    
+    // Экипировка
+    EquipmentContents {
+        items: Vec<EquippedItem>,
+    },
+    
+    // Видимая смена экипировки, рассылается игрокам рядом
+    EquipmentChanged {
+        player_id: Uuid,
+        slot: EquipmentSlot,
+        template_id: Option<u32>,
+    },
+    
+    // Интерактивные объекты мира
+    ObjectChanged {
+        object: ObjectUpdate,
+    },
+    
+    ActionError {
+        reason: String,
+    },
+    
+    // Крафт
+    CraftingRecipes {
+        station_id: Option<Uuid>,
+        recipes: Vec<RecipeInfo>,
+    },
+    
+    CraftingStarted {
+        recipe_id: u32,
+        craft_time_ms: u64,
+    },
+    
+    // progress от 0.0 до 1.0
+    CraftingProgress {
+        recipe_id: u32,
+        progress: f32,
+    },
+    
+    CraftingCompleted {
+        recipe_id: u32,
+    },
+    
+    CraftingCancelled {
+        recipe_id: u32,
+        reason: String,
+    },
+    
+    CraftingError {
+        reason: String,
+    },
+    
+    // Анимация/поза игрока (прыжок, сидит, танцует)
+    PlayerStateChanged {
+        update: PlayerUpdate,
+    },
+    
+    // Группа
+    PartyInviteReceived {
+        inviter_id: Uuid,
+        inviter_name: String,
+    },
+    
+    PartyInviteDeclined {
+        player_name: String,
+    },
+    
+    // Полный состав группы, рассылается участникам при любом изменении
+    PartyUpdate {
+        party_id: Uuid,
+        leader_id: Uuid,
+        members: Vec<PartyMember>,
+    },
+    
+    // Игрок больше не в группе (вышел, исключен, группа распущена)
+    PartyLeft {
+        reason: String,
+    },
+    
+    // Здоровье и позиции участников, только изменившиеся
+    PartyMemberUpdates {
+        members: Vec<PartyMember>,
+    },
+    
+    PartyError {
+        reason: String,
+    },
+    
+    // Гильдия: полные сведения и состав, рассылаются участникам в сети при изменениях
+    GuildInfo {
+        guild_id: Uuid,
+        name: String,
+        motd: String,
+        ranks: Vec<GuildRankInfo>,
+        members: Vec<GuildMemberInfo>,
+    },
+    
+    GuildInviteReceived {
+        guild_name: String,
+        inviter_name: String,
+    },
+    
+    GuildInviteDeclined {
+        player_name: String,
+    },
+    
+    GuildMotd {
+        motd: String,
+    },
+    
+    // Игрок больше не в гильдии (вышел, исключен, гильдия распущена)
+    GuildLeft {
+        reason: String,
+    },
+    
+    GuildError {
+        reason: String,
+    },
+    
+    // Ответ на серверную команду чата (/who, /roll...), может быть многострочным
+    SystemMessage {
+        message: String,
+    },
+    
+    CommandError {
+        reason: String,
+    },
+    
+    // Сервер закрывает соединение (кик, бан)
+    Disconnected {
+        reason: String,
+    },
+    Announcement {
+        message: String,
+    },
+    
+    // Почта
+    MailList {
+        mails: Vec<MailSummary>,
+    },
+    MailContent {
+        mail_id: Uuid,
+        sender: String,
+        subject: String,
+        body: String,
+        items: Vec<ItemAmount>,
+        gold: u64,
+    },
+    MailReceived {
+        mail_id: Uuid,
+        sender: String,
+        subject: String,
+    },
+    UnreadMail {
+        count: u32,
+    },
+    MailSent {
+        recipient: String,
+    },
+    MailAttachmentsTaken {
+        mail_id: Uuid,
+    },
+    MailDeleted {
+        mail_id: Uuid,
+    },
+    MailError {
+        reason: String,
+    },
+    GoldUpdate {
+        gold: u64,
+    },
+    
+    // Игрок перешел в другую зону, следом придет WorldState новой зоны
+    ZoneChanged {
+        zone_id: u32,
+        zone_name: String,
+        transform: Transform,
+    },
+    ZoneError {
+        reason: String,
+    },
+    // Сервер не принял позицию клиента или поправил высоту
+    PositionCorrection {
+        transform: Transform,
+    },
+    // Рассвет или закат
+    DayPhaseChanged {
+        game_time: GameTime,
+    },
+    // Погода в зоне игрока; приходит при смене погоды и после WorldState при входе в зону
+    WeatherChanged {
+        zone_id: u32,
+        weather: WeatherInfo,
+    },
+    // Объект исчез из мира (подобранная или истлевшая добыча)
+    ObjectRemoved {
+        object_id: Uuid,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,6 +298,23 @@ pub struct PlayerData {
     pub stats: PlayerStats,
 }
 
+// Игровое время: клиент дальше ведет часы сам по hours_per_second
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameTime {
+    pub day: u32,
+    pub hour: f32,
+    pub hours_per_second: f32,
+    pub is_day: bool,
+}
+
+// movement_multiplier умножает скорость движения, visibility - дальность видимости
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeatherInfo {
+    pub kind: WeatherKind,
+    pub movement_multiplier: f32,
+    pub visibility: f32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerUpdate {
     pub player_id: Uuid,
@@ -102,6 +339,77 @@ pub struct ObjectUpdate {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryItem {
+    pub item_id: Uuid,
+    pub template_id: u32,
+    pub quantity: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventorySlot {
+    pub slot: u32,
+    pub item: Option<InventoryItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemAmount {
+    pub template_id: u32,
+    pub quantity: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MailSummary {
+    pub mail_id: Uuid,
+    pub sender: String,
+    pub subject: String,
+    pub sent_at: i64,
+    pub expires_at: i64,
+    pub read: bool,
+    pub item_count: u32,
+    pub gold: u64,
+    pub returned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeInfo {
+    pub recipe_id: u32,
+    pub name: String,
+    pub inputs: Vec<ItemAmount>,
+    pub outputs: Vec<ItemAmount>,
+    pub craft_time_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EquippedItem {
+    pub slot: EquipmentSlot,
+    pub item: InventoryItem,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartyMember {
+    pub player_id: Uuid,
+    pub name: String,
+    pub zone_id: u32,
+    pub position: Vector3,
+    pub health: i32,
+    pub max_health: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildRankInfo {
+    pub rank: u32,
+    pub name: String,
+    pub permissions: Vec<GuildPermission>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuildMemberInfo {
+    pub name: String,
+    pub rank: u32,
+    pub online: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PlayerClass {
     Warrior,
     Mage,